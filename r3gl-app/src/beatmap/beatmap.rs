@@ -1,21 +1,171 @@
 use std::path::PathBuf;
 
+use super::{component::sample::SampleSet, source::Source, timing::Timing};
+
+pub type Colour = [u8; 3];
+
 #[derive(Debug, Clone)]
 pub struct Beatmap {
-    pub artist: String,
+    pub version: u32,
+
+    pub general: GeneralSection,
+    pub editor: EditorSection,
+    pub metadata: MetadataSection,
+    pub difficulty: DifficultySection,
+    pub events: Vec<Event>,
+    pub timing: Timing,
+    pub colours: ColoursSection,
+
+    pub(crate) source: Source,
+}
+
+#[derive(Debug, Clone)]
+pub struct GeneralSection {
+    pub audio_filename: PathBuf,
+    pub audio_lead_in: i32,
+    pub preview_time: i32,
+    pub countdown: u32,
+    pub sample_set: SampleSet,
+    pub stack_leniency: f32,
+    pub mode: u32,
+    pub letterbox_in_breaks: bool,
+    pub use_skin_sprites: bool,
+    pub overlay_position: String,
+    pub skin_preference: String,
+    pub epilepsy_warning: bool,
+    pub countdown_offset: u32,
+    pub special_style: bool,
+    pub widescreen_storyboard: bool,
+    pub samples_match_playback_rate: bool,
+}
+
+#[derive(Debug, Clone)]
+pub struct EditorSection {
+    pub bookmarks: Vec<i32>,
+    pub distance_spacing: f32,
+    pub beat_divisor: u32,
+    pub grid_size: u32,
+    pub timeline_zoom: f32,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct MetadataSection {
     pub title: String,
+    pub title_unicode: String,
+    pub artist: String,
+    pub artist_unicode: String,
     pub creator: String,
+    pub version: String,
+    pub source: String,
+    pub tags: Vec<String>,
+    pub beatmap_id: i32,
+    pub beatmap_set_id: i32,
+}
 
-    pub audio: PathBuf,
+#[derive(Debug, Clone)]
+pub struct DifficultySection {
+    pub hp_drain_rate: f32,
+    pub circle_size: f32,
+    pub overall_difficulty: f32,
+    pub approach_rate: f32,
+    pub slider_multiplier: f64,
+    pub slider_tick_rate: f64,
+}
+
+#[derive(Debug, Clone)]
+pub enum Event {
+    Background { filename: String, x_offset: i32, y_offset: i32 },
+    Video { start_time: i32, filename: String, x_offset: i32, y_offset: i32 },
+    Break { start_time: i32, end_time: i32 },
+
+    // Storyboard commands and everything else we don't interpret
+    Other(String),
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct ColoursSection {
+    pub combos: Vec<Colour>,
+    pub slider_track_override: Option<Colour>,
+    pub slider_border: Option<Colour>,
 }
 
 impl Default for Beatmap {
     fn default() -> Self {
         return Self {
-            artist  : Default::default(),
-            title   : Default::default(),
-            creator : Default::default(),
-            audio   : Default::default(),
+            version    : 14,
+            general    : Default::default(),
+            editor     : Default::default(),
+            metadata   : Default::default(),
+            difficulty : Default::default(),
+            events     : Default::default(),
+            timing     : Default::default(),
+            colours    : Default::default(),
+            source     : Default::default(),
+        };
+    }
+}
+
+impl Default for GeneralSection {
+    fn default() -> Self {
+        return Self {
+            audio_filename              : Default::default(),
+            audio_lead_in               : 0,
+            preview_time                : -1,
+            countdown                   : 1,
+            sample_set                  : SampleSet::Normal,
+            stack_leniency              : 0.7,
+            mode                        : 1,
+            letterbox_in_breaks         : false,
+            use_skin_sprites            : false,
+            overlay_position            : String::from("NoChange"),
+            skin_preference             : Default::default(),
+            epilepsy_warning            : false,
+            countdown_offset            : 0,
+            special_style               : false,
+            widescreen_storyboard       : false,
+            samples_match_playback_rate : false,
+        };
+    }
+}
+
+impl Default for EditorSection {
+    fn default() -> Self {
+        return Self {
+            bookmarks        : Default::default(),
+            distance_spacing : 1.0,
+            beat_divisor     : 4,
+            grid_size        : 4,
+            timeline_zoom    : 1.0,
+        };
+    }
+}
+
+impl Default for MetadataSection {
+    fn default() -> Self {
+        return Self {
+            title          : Default::default(),
+            title_unicode  : Default::default(),
+            artist         : Default::default(),
+            artist_unicode : Default::default(),
+            creator        : Default::default(),
+            version        : Default::default(),
+            source         : Default::default(),
+            tags           : Default::default(),
+            beatmap_id     : 0,
+            beatmap_set_id : -1,
+        };
+    }
+}
+
+impl Default for DifficultySection {
+    fn default() -> Self {
+        return Self {
+            hp_drain_rate      : 5.0,
+            circle_size        : 5.0,
+            overall_difficulty : 5.0,
+            approach_rate      : 5.0,
+            slider_multiplier  : 1.4,
+            slider_tick_rate   : 1.0,
         };
     }
 }
\ No newline at end of file
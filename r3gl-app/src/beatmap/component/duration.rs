@@ -0,0 +1,5 @@
+use crate::beatmap::Time;
+
+// Length of the object, it ends at `time + duration`
+#[derive(Copy, Clone, Debug)]
+pub struct DurationComponent(pub Time);
\ No newline at end of file
@@ -1,13 +1,21 @@
-use self::{time::TimeComponent, variant::VariantComponent};
+use self::{time::TimeComponent, variant::VariantComponent, sample::SampleComponent, duration::DurationComponent};
 
 pub mod adapter;
 pub mod variant;
 pub mod time;
+pub mod sample;
+pub mod duration;
 
 pub trait HitObject {
     fn time(&self) -> Option<&TimeComponent>;
     fn variant(&self) -> Option<&VariantComponent>;
+    fn sample(&self) -> Option<&SampleComponent>;
+    fn duration(&self) -> Option<&DurationComponent>;
 
     fn time_mut(&mut self) -> Option<&mut TimeComponent>;
     fn variant_mut(&mut self) -> Option<&mut VariantComponent>;
+    fn sample_mut(&mut self) -> Option<&mut SampleComponent>;
+    fn duration_mut(&mut self) -> Option<&mut DurationComponent>;
+
+    fn boxed_clone(&self) -> Box<dyn HitObject>;
 }
\ No newline at end of file
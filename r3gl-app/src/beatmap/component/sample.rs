@@ -0,0 +1,57 @@
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Hash, Default)]
+pub enum SampleSet {
+    #[default]
+    Auto,
+    Normal,
+    Soft,
+    Drum,
+}
+
+impl SampleSet {
+    pub fn from_index(value: u32) -> Option<Self> {
+        return match value {
+            0 => Some(SampleSet::Auto),
+            1 => Some(SampleSet::Normal),
+            2 => Some(SampleSet::Soft),
+            3 => Some(SampleSet::Drum),
+            _ => None,
+        };
+    }
+
+    pub fn index(&self) -> u32 {
+        return match self {
+            SampleSet::Auto   => 0,
+            SampleSet::Normal => 1,
+            SampleSet::Soft   => 2,
+            SampleSet::Drum   => 3,
+        };
+    }
+
+    pub fn from_name(value: &str) -> Option<Self> {
+        return match value {
+            "None" | "All" => Some(SampleSet::Auto),
+            "Normal"       => Some(SampleSet::Normal),
+            "Soft"         => Some(SampleSet::Soft),
+            "Drum"         => Some(SampleSet::Drum),
+            _ => None,
+        };
+    }
+
+    pub fn name(&self) -> &'static str {
+        return match self {
+            SampleSet::Auto   => "None",
+            SampleSet::Normal => "Normal",
+            SampleSet::Soft   => "Soft",
+            SampleSet::Drum   => "Drum",
+        };
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Default)]
+pub struct SampleComponent {
+    pub normal_set: SampleSet,
+    pub addition_set: SampleSet,
+    pub index: u32,
+    pub volume: u32,
+    pub filename: String,
+}
\ No newline at end of file
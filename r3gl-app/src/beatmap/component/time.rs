@@ -1,3 +1,4 @@
 use crate::beatmap::Time;
 
+#[derive(Copy, Clone, Debug)]
 pub struct TimeComponent(pub Time);
\ No newline at end of file
@@ -1 +1,2 @@
+#[derive(Copy, Clone, Debug)]
 pub struct VariantComponent(pub u32);
\ No newline at end of file
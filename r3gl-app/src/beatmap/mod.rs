@@ -2,7 +2,11 @@ use instant::Duration;
 
 pub mod component;
 pub mod parser;
+pub mod writer;
 pub mod beatmap;
+pub mod source;
+pub mod timing;
+pub mod objects;
 
 #[derive(Copy, Clone, Debug, Default, Ord, PartialOrd, Eq, PartialEq)]
 pub struct Time(u32); // in milliseconds
@@ -18,6 +22,10 @@ impl Time {
         return Time(value);
     }
 
+    pub fn from_ms_f64(value: f64) -> Self {
+        return Time(value.round().max(0.0) as u32);
+    }
+
     pub fn as_ms(&self) -> u32 {
         return self.0;
     }
@@ -0,0 +1,115 @@
+use slotmap::{SlotMap, new_key_type};
+
+use super::{Time, component::HitObject};
+
+new_key_type! { pub struct ObjectId; }
+
+// Ids stay valid while an object is removed, so undoing the removal brings back the same id
+#[derive(Default)]
+pub struct Objects {
+    arena: SlotMap<ObjectId, Option<Box<dyn HitObject>>>,
+    order: Vec<ObjectId>, // Sorted by time
+    longest: Time, // No object is longer, removing one doesn't shrink it until the next sort
+}
+
+impl From<Vec<Box<dyn HitObject>>> for Objects {
+    fn from(objects: Vec<Box<dyn HitObject>>) -> Self {
+        let mut arena = SlotMap::with_capacity_and_key(objects.len());
+        let order = objects.into_iter().map(|object| arena.insert(Some(object))).collect();
+
+        let mut objects = Self { arena, order, longest: Time::default() };
+        objects.sort();
+        return objects;
+    }
+}
+
+impl Objects {
+    pub fn len(&self) -> usize {
+        return self.order.len();
+    }
+
+    pub fn is_empty(&self) -> bool {
+        return self.order.is_empty();
+    }
+
+    pub fn contains(&self, id: ObjectId) -> bool {
+        return self.get(id).is_some();
+    }
+
+    // Removed, but still reserved for `attach`
+    pub fn is_detached(&self, id: ObjectId) -> bool {
+        return matches!(self.arena.get(id), Some(None));
+    }
+
+    pub fn get(&self, id: ObjectId) -> Option<&dyn HitObject> {
+        return self.arena.get(id)?.as_deref();
+    }
+
+    // `sort` has to be called after changing the time or duration of an object
+    pub fn get_mut(&mut self, id: ObjectId) -> Option<&mut Box<dyn HitObject>> {
+        return self.arena.get_mut(id)?.as_mut();
+    }
+
+    pub fn ids(&self) -> &[ObjectId] {
+        return &self.order;
+    }
+
+    pub fn iter(&self) -> impl DoubleEndedIterator<Item = (ObjectId, &dyn HitObject)> + '_ {
+        return self.order.iter().filter_map(|id| Some((*id, self.get(*id)?)));
+    }
+
+    // Objects that start in `from .. until`, or started earlier and are still going at `from`
+    pub fn overlapping(&self, from: Time, until: Time) -> impl Iterator<Item = (ObjectId, &dyn HitObject)> + '_ {
+        let earliest = Some(Time::from_ms(from.as_ms().saturating_sub(self.longest.as_ms())));
+        let start = self.order.partition_point(|x| self.time(*x) < earliest);
+        let end = self.order.partition_point(|x| self.time(*x) < Some(until));
+
+        return self.order[start .. end.max(start)].iter()
+            .filter_map(|id| Some((*id, self.get(*id)?)))
+            .filter(move |(_, object)| end_time(*object).map_or(false, |end| end >= from));
+    }
+
+    pub fn insert(&mut self, object: Box<dyn HitObject>) -> ObjectId {
+        let id = self.arena.insert(None);
+        self.attach(id, object);
+        return id;
+    }
+
+    // Puts a detached object back under its old id, after any objects at the same time
+    pub fn attach(&mut self, id: ObjectId, object: Box<dyn HitObject>) {
+        let time = object.time().map(|x| x.0);
+        self.longest = self.longest.max(duration(object.as_ref()));
+        let index = self.order.partition_point(|x| self.time(*x) <= time);
+
+        if let Some(slot) = self.arena.get_mut(id) {
+            *slot = Some(object);
+            self.order.insert(index, id);
+        }
+    }
+
+    pub fn detach(&mut self, id: ObjectId) -> Option<Box<dyn HitObject>> {
+        let object = self.arena.get_mut(id)?.take()?;
+        self.order.retain(|x| *x != id);
+        return Some(object);
+    }
+
+    pub fn sort(&mut self) {
+        let mut order = std::mem::take(&mut self.order);
+        order.sort_by_key(|id| self.time(*id));
+        self.longest = order.iter().filter_map(|id| self.get(*id)).map(duration).max().unwrap_or_default();
+        self.order = order;
+    }
+
+    fn time(&self, id: ObjectId) -> Option<Time> {
+        return self.get(id)?.time().map(|x| x.0);
+    }
+}
+
+fn duration(object: &dyn HitObject) -> Time {
+    return object.duration().map_or(Time::default(), |x| x.0);
+}
+
+fn end_time(object: &dyn HitObject) -> Option<Time> {
+    let time = object.time()?.0;
+    return Some(Time::from_ms(time.as_ms().saturating_add(duration(object).as_ms())));
+}
\ No newline at end of file
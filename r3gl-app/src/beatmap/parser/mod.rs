@@ -1,7 +1,34 @@
-use super::{beatmap::Beatmap, component::HitObject};
+use std::fmt::Display;
+
+use super::{beatmap::Beatmap, objects::Objects};
 
 pub mod osu_taiko;
 
-pub fn parse<'a: 'b, 'b>(data: &str) -> (Beatmap, Vec<Box<dyn HitObject>>) {
+#[derive(Debug, Clone)]
+pub struct ParseError {
+    pub line: usize,   // 1-based
+    pub column: usize, // 1-based, in characters
+    pub message: String,
+}
+
+impl ParseError {
+    pub fn new(line: usize, column: usize, message: impl Into<String>) -> Self {
+        return Self {
+            line,
+            column,
+            message: message.into(),
+        };
+    }
+}
+
+impl Display for ParseError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        return write!(f, "line {}, column {}: {}", self.line, self.column, self.message);
+    }
+}
+
+impl std::error::Error for ParseError {}
+
+pub fn parse(data: &str) -> Result<(Beatmap, Objects), ParseError> {
     return osu_taiko::parse(data);
 }
\ No newline at end of file
@@ -1,66 +1,722 @@
-use std::path::PathBuf;
+use std::{path::PathBuf, str::FromStr};
 
 use intbits::Bits;
 
-use crate::beatmap::{beatmap::Beatmap, Time, component::{HitObject, time::TimeComponent, variant::VariantComponent}};
+use crate::beatmap::{beatmap::{Beatmap, Event, Colour}, objects::Objects, timing::TimingPoint, Time, component::{HitObject, time::TimeComponent, variant::VariantComponent, sample::{SampleComponent, SampleSet}, duration::DurationComponent, adapter::taiko::{TaikoColor, TaikoVariantAdapter}}, source::{Source, RawSection, RawLine, LineEnding}, writer::osu_taiko as writer};
 
+use super::ParseError;
+
+#[derive(Clone)]
 pub struct TaikoCircle {
     pub time: TimeComponent,
     pub variant: VariantComponent,
+    pub sample: SampleComponent,
+}
+
+impl TaikoCircle {
+    pub fn new(time: Time, color: TaikoColor, big: bool) -> Self {
+        let mut variant = VariantComponent(0);
+        variant.set_color(color);
+        variant.set_big(big);
+
+        return Self {
+            time: TimeComponent(time),
+            variant,
+            sample: Default::default(),
+        };
+    }
 }
 
 impl HitObject for TaikoCircle {
     #[inline(always)] fn time(&self) -> Option<&TimeComponent> { Some(&self.time) }
     #[inline(always)] fn variant(&self) -> Option<&VariantComponent> { Some(&self.variant) }
+    #[inline(always)] fn sample(&self) -> Option<&SampleComponent> { Some(&self.sample) }
+    #[inline(always)] fn duration(&self) -> Option<&DurationComponent> { None }
+
+    #[inline(always)] fn time_mut(&mut self) -> Option<&mut TimeComponent> { Some(&mut self.time) }
+    #[inline(always)] fn variant_mut(&mut self) -> Option<&mut VariantComponent> { Some(&mut self.variant) }
+    #[inline(always)] fn sample_mut(&mut self) -> Option<&mut SampleComponent> { Some(&mut self.sample) }
+    #[inline(always)] fn duration_mut(&mut self) -> Option<&mut DurationComponent> { None }
+
+    fn boxed_clone(&self) -> Box<dyn HitObject> { Box::new(self.clone()) }
+}
+
+// Only the finisher bit of the variant is used
+#[derive(Clone)]
+pub struct TaikoDrumroll {
+    pub time: TimeComponent,
+    pub variant: VariantComponent,
+    pub sample: SampleComponent,
+    pub duration: DurationComponent,
+}
+
+impl TaikoDrumroll {
+    pub fn new(time: Time, duration: Time, big: bool) -> Self {
+        let mut variant = VariantComponent(0);
+        variant.set_big(big);
+
+        return Self {
+            time: TimeComponent(time),
+            variant,
+            sample: Default::default(),
+            duration: DurationComponent(duration),
+        };
+    }
+}
+
+impl HitObject for TaikoDrumroll {
+    #[inline(always)] fn time(&self) -> Option<&TimeComponent> { Some(&self.time) }
+    #[inline(always)] fn variant(&self) -> Option<&VariantComponent> { Some(&self.variant) }
+    #[inline(always)] fn sample(&self) -> Option<&SampleComponent> { Some(&self.sample) }
+    #[inline(always)] fn duration(&self) -> Option<&DurationComponent> { Some(&self.duration) }
 
     #[inline(always)] fn time_mut(&mut self) -> Option<&mut TimeComponent> { Some(&mut self.time) }
     #[inline(always)] fn variant_mut(&mut self) -> Option<&mut VariantComponent> { Some(&mut self.variant) }
+    #[inline(always)] fn sample_mut(&mut self) -> Option<&mut SampleComponent> { Some(&mut self.sample) }
+    #[inline(always)] fn duration_mut(&mut self) -> Option<&mut DurationComponent> { Some(&mut self.duration) }
+
+    fn boxed_clone(&self) -> Box<dyn HitObject> { Box::new(self.clone()) }
+}
+
+#[derive(Clone)]
+pub struct TaikoDenden {
+    pub time: TimeComponent,
+    pub sample: SampleComponent,
+    pub duration: DurationComponent,
+}
+
+impl TaikoDenden {
+    pub fn new(time: Time, duration: Time) -> Self {
+        return Self {
+            time: TimeComponent(time),
+            sample: Default::default(),
+            duration: DurationComponent(duration),
+        };
+    }
+}
+
+impl HitObject for TaikoDenden {
+    #[inline(always)] fn time(&self) -> Option<&TimeComponent> { Some(&self.time) }
+    #[inline(always)] fn variant(&self) -> Option<&VariantComponent> { None }
+    #[inline(always)] fn sample(&self) -> Option<&SampleComponent> { Some(&self.sample) }
+    #[inline(always)] fn duration(&self) -> Option<&DurationComponent> { Some(&self.duration) }
+
+    #[inline(always)] fn time_mut(&mut self) -> Option<&mut TimeComponent> { Some(&mut self.time) }
+    #[inline(always)] fn variant_mut(&mut self) -> Option<&mut VariantComponent> { None }
+    #[inline(always)] fn sample_mut(&mut self) -> Option<&mut SampleComponent> { Some(&mut self.sample) }
+    #[inline(always)] fn duration_mut(&mut self) -> Option<&mut DurationComponent> { Some(&mut self.duration) }
+
+    fn boxed_clone(&self) -> Box<dyn HitObject> { Box::new(self.clone()) }
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+enum Section {
+    None,
+    General,
+    Editor,
+    Metadata,
+    Difficulty,
+    Events,
+    TimingPoints,
+    Colours,
+    HitObjects,
+    Unknown,
+}
+
+impl Section {
+    fn from_name(name: &str) -> Self {
+        return match name {
+            "General"      => Section::General,
+            "Editor"       => Section::Editor,
+            "Metadata"     => Section::Metadata,
+            "Difficulty"   => Section::Difficulty,
+            "Events"       => Section::Events,
+            "TimingPoints" => Section::TimingPoints,
+            "Colours"      => Section::Colours,
+            "HitObjects"   => Section::HitObjects,
+            _              => Section::Unknown,
+        };
+    }
+}
+
+struct Line<'a> {
+    number: usize,
+    text: &'a str,
+}
+
+impl<'a> Line<'a> {
+    // `part` must be a subslice of `self.text` for the column to be correct
+    fn error(&self, part: &str, message: impl Into<String>) -> ParseError {
+        let start = self.text.as_ptr() as usize;
+        let offset = (part.as_ptr() as usize).wrapping_sub(start);
+        let column = if offset <= self.text.len() { self.text[.. offset].chars().count() + 1 } else { 1 };
+        return ParseError::new(self.number, column, message);
+    }
+
+    fn missing(&self, what: &str) -> ParseError {
+        return ParseError::new(self.number, self.text.chars().count() + 1, format!("missing {}", what));
+    }
+
+    fn parse<T: FromStr>(&self, part: &str, what: &str) -> Result<T, ParseError> {
+        let value = part.trim();
+        return value.parse().map_err(|_| self.error(value, format!("invalid {} `{}`", what, value)));
+    }
+
+    fn parse_bool(&self, part: &str, what: &str) -> Result<bool, ParseError> {
+        return Ok(self.parse::<i32>(part, what)? != 0);
+    }
+
+    fn next<'b>(&self, fields: &mut impl Iterator<Item = &'b str>, what: &str) -> Result<&'b str, ParseError> {
+        return fields.next().ok_or_else(|| self.missing(what));
+    }
+
+    fn key_value(&self) -> Result<(&'a str, &'a str), ParseError> {
+        return match self.text.split_once(':') {
+            Some((key, value)) => Ok((key.trim(), value.trim())),
+            None => Err(self.error(self.text.trim(), "expected `key: value` pair")),
+        };
+    }
 }
 
-pub fn parse(data: &str) -> (Beatmap, Vec<Box<dyn HitObject>>) {
+pub fn parse(data: &str) -> Result<(Beatmap, Objects), ParseError> {
     let mut beatmap = Beatmap::default();
     let mut objects = Vec::<Box<dyn HitObject>>::new();
+    let mut source = Source {
+        line_ending: if data.contains("\r\n") { LineEnding::CrLf } else { LineEnding::Lf },
+        ..Default::default()
+    };
+
+    let mut header = false;
+    let mut section = Section::None;
+    for (i, raw) in data.split_inclusive('\n').enumerate() {
+        let text = raw.trim_end_matches(|c| c == '\r' || c == '\n');
+        let text = if i == 0 { text.trim_start_matches('\u{feff}') } else { text };
+        let line = Line { number: i + 1, text };
+
+        let mut raw_line = RawLine {
+            text: raw.to_owned(),
+            key: None,
+            canonical: None,
+        };
+
+        let trimmed = text.trim();
+        if trimmed.is_empty() || trimmed.starts_with("//") {
+            push_line(&mut source, raw_line);
+            continue;
+        }
+
+        if !header {
+            beatmap.version = parse_header(&line, trimmed)?;
+            header = true;
+            push_line(&mut source, raw_line);
+            continue;
+        }
+
+        if trimmed.starts_with('[') && trimmed.ends_with(']') {
+            let name = &trimmed[1 .. trimmed.len() - 1];
+            section = Section::from_name(name);
+            source.sections.push(RawSection {
+                name: name.to_owned(),
+                header: raw.to_owned(),
+                lines: vec![],
+            });
+
+            continue;
+        }
+
+        match section {
+            Section::None         => return Err(line.error(trimmed, "data outside of any section")),
+            Section::General      => parse_general(&mut beatmap, &line)?,
+            Section::Editor       => parse_editor(&mut beatmap, &line)?,
+            Section::Metadata     => parse_metadata(&mut beatmap, &line)?,
+            Section::Difficulty   => parse_difficulty(&mut beatmap, &line)?,
+            Section::Colours      => parse_colours(&mut beatmap, &line)?,
+
+            Section::Events => {
+                let event = parse_event(&line)?;
+                raw_line.canonical = Some(writer::format_event(&event));
+                beatmap.events.push(event);
+            }
 
-    let mut objects_section = false;
-    for line in data.lines() {
-        if objects_section {
-            let mut parts = line.split(",");
-            parts.next();
-            parts.next();
-            if let Some(time) = parts.next() {
-                let time = time.parse().unwrap();
-                parts.next();
-                if let Some(hit_sound) = parts.next() {
-                    let hit_sound: u32 = hit_sound.parse().unwrap();
-                    objects.push(Box::new(TaikoCircle {
-                        time: TimeComponent(Time::from_ms(time)),
-                        variant: VariantComponent(0
-                            .with_bit(0, hit_sound.bit(1) || hit_sound.bit(3))
-                            .with_bit(1, hit_sound.bit(2))
-                        ),
-                    }));
-                }
+            Section::TimingPoints => {
+                let point = parse_timing_point(&line)?;
+                raw_line.canonical = Some(writer::format_timing_point(&point));
+                beatmap.timing.insert(point);
             }
-        } else if line.starts_with("[HitObjects]") {
-            objects_section = true;
-        } else {
-            let mut parts = line.split(":");
-            if let Some(key) = parts.next() {
-                if let Some(value) = parts.next() {
-                    let key = key.trim();
-                    let value = value.trim();
-
-                    match key {
-                        "Title" => beatmap.title = value.to_owned(),
-                        "Artist" => beatmap.artist = value.to_owned(),
-                        "AudioFilename" => beatmap.audio = PathBuf::from(value.to_owned()),
-
-                        _ => {}
-                    }
-                }
+
+            // Drumroll lengths depend on timing, which always comes before objects
+            Section::HitObjects => if let Some(object) = parse_hit_object(&beatmap, &line)? {
+                raw_line.canonical = writer::format_object(object.as_ref(), &beatmap);
+                objects.push(object);
+            },
+
+            Section::Unknown => {}
+        }
+
+        if matches!(section, Section::General | Section::Editor | Section::Metadata | Section::Difficulty | Section::Colours) {
+            raw_line.key = line.key_value().ok().map(|(key, _)| key.to_owned());
+        }
+
+        push_line(&mut source, raw_line);
+    }
+
+    if !header {
+        return Err(ParseError::new(1, 1, "missing `osu file format` header"));
+    }
+
+    // Key-value lines can only be formatted once the whole section is known
+    for section in &mut source.sections {
+        let lines = match section.name.as_str() {
+            "General"    => [writer::general_lines(&beatmap.general), writer::legacy_editor_lines(&beatmap.editor)].concat(),
+            "Editor"     => writer::editor_lines(&beatmap.editor),
+            "Metadata"   => writer::metadata_lines(&beatmap.metadata),
+            "Difficulty" => writer::difficulty_lines(&beatmap.difficulty),
+            "Colours"    => writer::colours_lines(&beatmap.colours),
+            _ => continue,
+        };
+
+        for raw_line in &mut section.lines {
+            if let Some(key) = &raw_line.key {
+                raw_line.canonical = lines.iter().find(|(k, _)| k == key).map(|(_, line)| line.clone());
             }
         }
     }
 
-    return (beatmap, objects);
+    beatmap.source = source;
+    return Ok((beatmap, objects.into()));
+}
+
+fn push_line(source: &mut Source, line: RawLine) {
+    match source.sections.last_mut() {
+        Some(section) => section.lines.push(line),
+        None => source.preamble.push(line.text),
+    }
+}
+
+fn parse_header(line: &Line, trimmed: &str) -> Result<u32, ParseError> {
+    const PREFIX: &str = "osu file format v";
+    if let Some(version) = trimmed.strip_prefix(PREFIX) {
+        return line.parse(version, "file format version");
+    }
+
+    return Err(line.error(trimmed, "expected `osu file format v<version>` header"));
+}
+
+fn parse_general(beatmap: &mut Beatmap, line: &Line) -> Result<(), ParseError> {
+    let (key, value) = line.key_value()?;
+    let general = &mut beatmap.general;
+    match key {
+        "AudioFilename"            => general.audio_filename = PathBuf::from(value),
+        "AudioLeadIn"              => general.audio_lead_in = line.parse(value, "audio lead-in")?,
+        "PreviewTime"              => general.preview_time = line.parse(value, "preview time")?,
+        "Countdown"                => general.countdown = line.parse(value, "countdown")?,
+        "SampleSet"                => general.sample_set = SampleSet::from_name(value)
+                                          .ok_or_else(|| line.error(value, format!("unknown sample set `{}`", value)))?,
+        "StackLeniency"            => general.stack_leniency = line.parse(value, "stack leniency")?,
+        "Mode"                     => general.mode = line.parse(value, "mode")?,
+        "LetterboxInBreaks"        => general.letterbox_in_breaks = line.parse_bool(value, "flag")?,
+        "UseSkinSprites"           => general.use_skin_sprites = line.parse_bool(value, "flag")?,
+        "OverlayPosition"          => general.overlay_position = value.to_owned(),
+        "SkinPreference"           => general.skin_preference = value.to_owned(),
+        "EpilepsyWarning"          => general.epilepsy_warning = line.parse_bool(value, "flag")?,
+        "CountdownOffset"          => general.countdown_offset = line.parse(value, "countdown offset")?,
+        "SpecialStyle"             => general.special_style = line.parse_bool(value, "flag")?,
+        "WidescreenStoryboard"     => general.widescreen_storyboard = line.parse_bool(value, "flag")?,
+        "SamplesMatchPlaybackRate" => general.samples_match_playback_rate = line.parse_bool(value, "flag")?,
+
+        // Pre-v14 files keep editor settings here
+        "EditorBookmarks"          => beatmap.editor.bookmarks = parse_bookmarks(line, value)?,
+        "EditorDistanceSpacing"    => beatmap.editor.distance_spacing = line.parse(value, "distance spacing")?,
+
+        _ => {}
+    }
+
+    return Ok(());
+}
+
+fn parse_editor(beatmap: &mut Beatmap, line: &Line) -> Result<(), ParseError> {
+    let (key, value) = line.key_value()?;
+    let editor = &mut beatmap.editor;
+    match key {
+        "Bookmarks"       => editor.bookmarks = parse_bookmarks(line, value)?,
+        "DistanceSpacing" => editor.distance_spacing = line.parse(value, "distance spacing")?,
+        "BeatDivisor"     => editor.beat_divisor = line.parse(value, "beat divisor")?,
+        "GridSize"        => editor.grid_size = line.parse(value, "grid size")?,
+        "TimelineZoom"    => editor.timeline_zoom = line.parse(value, "timeline zoom")?,
+
+        _ => {}
+    }
+
+    return Ok(());
+}
+
+fn parse_bookmarks(line: &Line, value: &str) -> Result<Vec<i32>, ParseError> {
+    return value.split(',')
+        .filter(|x| !x.trim().is_empty())
+        .map(|x| line.parse(x, "bookmark"))
+        .collect();
+}
+
+fn parse_metadata(beatmap: &mut Beatmap, line: &Line) -> Result<(), ParseError> {
+    let (key, value) = line.key_value()?;
+    let metadata = &mut beatmap.metadata;
+    match key {
+        "Title"         => metadata.title = value.to_owned(),
+        "TitleUnicode"  => metadata.title_unicode = value.to_owned(),
+        "Artist"        => metadata.artist = value.to_owned(),
+        "ArtistUnicode" => metadata.artist_unicode = value.to_owned(),
+        "Creator"       => metadata.creator = value.to_owned(),
+        "Version"       => metadata.version = value.to_owned(),
+        "Source"        => metadata.source = value.to_owned(),
+        "Tags"          => metadata.tags = value.split_whitespace().map(str::to_owned).collect(),
+        "BeatmapID"     => metadata.beatmap_id = line.parse(value, "beatmap id")?,
+        "BeatmapSetID"  => metadata.beatmap_set_id = line.parse(value, "beatmap set id")?,
+
+        _ => {}
+    }
+
+    return Ok(());
+}
+
+fn parse_difficulty(beatmap: &mut Beatmap, line: &Line) -> Result<(), ParseError> {
+    let (key, value) = line.key_value()?;
+    let difficulty = &mut beatmap.difficulty;
+    match key {
+        "HPDrainRate"       => difficulty.hp_drain_rate = line.parse(value, "hp drain rate")?,
+        "CircleSize"        => difficulty.circle_size = line.parse(value, "circle size")?,
+        "OverallDifficulty" => difficulty.overall_difficulty = line.parse(value, "overall difficulty")?,
+        "ApproachRate"      => difficulty.approach_rate = line.parse(value, "approach rate")?,
+        "SliderMultiplier"  => difficulty.slider_multiplier = line.parse(value, "slider multiplier")?,
+        "SliderTickRate"    => difficulty.slider_tick_rate = line.parse(value, "slider tick rate")?,
+
+        _ => {}
+    }
+
+    return Ok(());
+}
+
+fn parse_event(line: &Line) -> Result<Event, ParseError> {
+    let mut fields = line.text.split(',');
+    let kind = line.next(&mut fields, "event type")?;
+    return match kind.trim() {
+        "0" | "Background" => {
+            line.next(&mut fields, "start time")?;
+            let filename = line.next(&mut fields, "filename")?.trim().trim_matches('"').to_owned();
+            let x_offset = fields.next().map(|x| line.parse(x, "x offset")).transpose()?.unwrap_or(0);
+            let y_offset = fields.next().map(|y| line.parse(y, "y offset")).transpose()?.unwrap_or(0);
+            Ok(Event::Background { filename, x_offset, y_offset })
+        }
+
+        "1" | "Video" => {
+            let start_time = line.parse(line.next(&mut fields, "start time")?, "start time")?;
+            let filename = line.next(&mut fields, "filename")?.trim().trim_matches('"').to_owned();
+            let x_offset = fields.next().map(|x| line.parse(x, "x offset")).transpose()?.unwrap_or(0);
+            let y_offset = fields.next().map(|y| line.parse(y, "y offset")).transpose()?.unwrap_or(0);
+            Ok(Event::Video { start_time, filename, x_offset, y_offset })
+        }
+
+        "2" | "Break" => {
+            let start_time = line.parse(line.next(&mut fields, "start time")?, "start time")?;
+            let end_time = line.parse(line.next(&mut fields, "end time")?, "end time")?;
+            Ok(Event::Break { start_time, end_time })
+        }
+
+        _ => Ok(Event::Other(line.text.to_owned())),
+    };
+}
+
+fn parse_timing_point(line: &Line) -> Result<TimingPoint, ParseError> {
+    let mut fields = line.text.split(',');
+    let mut point = TimingPoint {
+        time: line.parse(line.next(&mut fields, "time")?, "time")?,
+        beat_length: line.parse(line.next(&mut fields, "beat length")?, "beat length")?,
+        ..Default::default()
+    };
+
+    // Everything past beat length is optional in older versions
+    if let Some(meter) = fields.next() {
+        point.meter = line.parse(meter, "meter")?;
+    }
+
+    if let Some(sample_set) = fields.next() {
+        point.sample_set = parse_sample_set(line, sample_set)?;
+    }
+
+    if let Some(sample_index) = fields.next() {
+        point.sample_index = line.parse(sample_index, "sample index")?;
+    }
+
+    if let Some(volume) = fields.next() {
+        point.volume = line.parse(volume, "volume")?;
+    }
+
+    if let Some(uninherited) = fields.next() {
+        point.uninherited = line.parse_bool(uninherited, "uninherited flag")?;
+    }
+
+    if let Some(effects) = fields.next() {
+        point.effects = line.parse(effects, "effects")?;
+    }
+
+    return Ok(point);
+}
+
+fn parse_colours(beatmap: &mut Beatmap, line: &Line) -> Result<(), ParseError> {
+    let (key, value) = line.key_value()?;
+    let colours = &mut beatmap.colours;
+    match key {
+        "SliderTrackOverride" => colours.slider_track_override = Some(parse_colour(line, value)?),
+        "SliderBorder"        => colours.slider_border = Some(parse_colour(line, value)?),
+        key if key.starts_with("Combo") => colours.combos.push(parse_colour(line, value)?),
+
+        _ => {}
+    }
+
+    return Ok(());
+}
+
+fn parse_colour(line: &Line, value: &str) -> Result<Colour, ParseError> {
+    let mut fields = value.split(',');
+    let r = line.parse(line.next(&mut fields, "red component")?, "red component")?;
+    let g = line.parse(line.next(&mut fields, "green component")?, "green component")?;
+    let b = line.parse(line.next(&mut fields, "blue component")?, "blue component")?;
+    return Ok([r, g, b]);
+}
+
+fn parse_sample_set(line: &Line, part: &str) -> Result<SampleSet, ParseError> {
+    let index = line.parse(part, "sample set")?;
+    return SampleSet::from_index(index).ok_or_else(|| line.error(part.trim(), format!("unknown sample set `{}`", index)));
+}
+
+fn parse_sample(line: &Line, part: &str) -> Result<SampleComponent, ParseError> {
+    let mut sample = SampleComponent::default();
+    if part.trim().is_empty() {
+        return Ok(sample);
+    }
+
+    let mut fields = part.splitn(5, ':');
+    if let Some(normal_set) = fields.next() {
+        sample.normal_set = parse_sample_set(line, normal_set)?;
+    }
+
+    if let Some(addition_set) = fields.next() {
+        sample.addition_set = parse_sample_set(line, addition_set)?;
+    }
+
+    if let Some(index) = fields.next() {
+        sample.index = line.parse(index, "sample index")?;
+    }
+
+    if let Some(volume) = fields.next() {
+        sample.volume = line.parse(volume, "sample volume")?;
+    }
+
+    if let Some(filename) = fields.next() {
+        sample.filename = filename.trim().to_owned();
+    }
+
+    return Ok(sample);
+}
+
+// Times can't be negative, clamping them would change the map on the next save
+fn parse_time(line: &Line, part: &str, what: &str) -> Result<Time, ParseError> {
+    let time: f64 = line.parse(part, what)?;
+    return to_time(line, part, what, time);
+}
+
+fn to_time(line: &Line, part: &str, what: &str, value: f64) -> Result<Time, ParseError> {
+    if !value.is_finite() || value.round() < 0.0 || value.round() > u32::MAX as f64 {
+        return Err(line.error(part.trim(), format!("{} out of range `{}`", what, part.trim())));
+    }
+
+    return Ok(Time::from_ms_f64(value));
+}
+
+fn parse_hit_object(beatmap: &Beatmap, line: &Line) -> Result<Option<Box<dyn HitObject>>, ParseError> {
+    let mut fields = line.text.split(',');
+    line.parse::<f32>(line.next(&mut fields, "x position")?, "x position")?;
+    line.parse::<f32>(line.next(&mut fields, "y position")?, "y position")?;
+    let time = parse_time(line, line.next(&mut fields, "time")?, "time")?;
+    let kind_field = line.next(&mut fields, "object type")?;
+    let kind: u32 = line.parse(kind_field, "object type")?;
+    let hit_sound: u32 = line.parse(line.next(&mut fields, "hit sound")?, "hit sound")?;
+
+    if kind.bit(0) {
+        return Ok(Some(Box::new(TaikoCircle {
+            time: TimeComponent(time),
+            variant: VariantComponent(0u32
+                .with_bit(0, hit_sound.bit(1) || hit_sound.bit(3))
+                .with_bit(1, hit_sound.bit(2))
+            ),
+            sample: parse_optional_sample(line, fields.next())?,
+        })));
+    }
+
+    if kind.bit(1) {
+        line.next(&mut fields, "slider curve")?;
+        let slides: u32 = line.parse(line.next(&mut fields, "slide count")?, "slide count")?;
+        let length_field = line.next(&mut fields, "slider length")?;
+        let length: f64 = line.parse(length_field, "slider length")?;
+
+        // Edge sounds and sets don't mean anything in taiko
+        let velocity = beatmap.timing.velocity_at(time.as_ms() as f64, beatmap.difficulty.slider_multiplier);
+        let duration = to_time(line, length_field, "drumroll length", length * slides as f64 / velocity)?;
+
+        return Ok(Some(Box::new(TaikoDrumroll {
+            time: TimeComponent(time),
+            variant: VariantComponent(0u32.with_bit(1, hit_sound.bit(2))),
+            sample: parse_optional_sample(line, fields.nth(2))?,
+            duration: DurationComponent(duration),
+        })));
+    }
+
+    if kind.bit(3) {
+        let end_field = line.next(&mut fields, "end time")?;
+        let end = parse_time(line, end_field, "end time")?;
+        if end.as_ms() < time.as_ms() {
+            return Err(line.error(end_field.trim(), format!("spinner ends before it starts `{}`", end_field.trim())));
+        }
+
+        return Ok(Some(Box::new(TaikoDenden {
+            time: TimeComponent(time),
+            sample: parse_optional_sample(line, fields.next())?,
+            duration: DurationComponent(Time::from_ms(end.as_ms() - time.as_ms())),
+        })));
+    }
+
+    // Mania holds are kept as they are
+    if kind.bit(7) {
+        return Ok(None);
+    }
+
+    return Err(line.error(kind_field.trim(), format!("unknown object type `{}`", kind)));
+}
+
+fn parse_optional_sample(line: &Line, part: Option<&str>) -> Result<SampleComponent, ParseError> {
+    return match part {
+        Some(part) => parse_sample(line, part),
+        None => Ok(SampleComponent::default()),
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse_body(body: &str) -> Result<(Beatmap, Objects), ParseError> {
+        return parse(&format!("osu file format v14\n\n{}", body));
+    }
+
+    // Line and column of the error, body lines start at 3
+    fn error_at(body: &str) -> (usize, usize) {
+        let err = parse_body(body).err().expect("parsed fine");
+        return (err.line, err.column);
+    }
+
+    const TIMING: &str = "[Difficulty]\nSliderMultiplier:1.4\n\n[TimingPoints]\n0,500,4,1,0,100,1,0\n\n[HitObjects]\n";
+
+    #[test]
+    fn parses_general() {
+        let (beatmap, _) = parse_body("[General]\nAudioFilename: song.mp3\nAudioLeadIn: 500\nPreviewTime: 12000\nSampleSet: Soft\nMode: 1\n").unwrap();
+        assert_eq!(beatmap.general.audio_filename, PathBuf::from("song.mp3"));
+        assert_eq!(beatmap.general.audio_lead_in, 500);
+        assert_eq!(beatmap.general.preview_time, 12000);
+        assert_eq!(beatmap.general.sample_set, SampleSet::Soft);
+    }
+
+    #[test]
+    fn parses_editor() {
+        let (beatmap, _) = parse_body("[Editor]\nBookmarks: 100,2000\nBeatDivisor: 3\nTimelineZoom: 1.5\n").unwrap();
+        assert_eq!(beatmap.editor.bookmarks, vec![100, 2000]);
+        assert_eq!(beatmap.editor.beat_divisor, 3);
+        assert_eq!(beatmap.editor.timeline_zoom, 1.5);
+    }
+
+    #[test]
+    fn parses_metadata() {
+        let (beatmap, _) = parse_body("[Metadata]\nTitle:Song\nArtist:Someone\nVersion:Oni\nTags:one two\nBeatmapID:42\n").unwrap();
+        assert_eq!(beatmap.metadata.title, "Song");
+        assert_eq!(beatmap.metadata.artist, "Someone");
+        assert_eq!(beatmap.metadata.version, "Oni");
+        assert_eq!(beatmap.metadata.tags, vec!["one", "two"]);
+        assert_eq!(beatmap.metadata.beatmap_id, 42);
+    }
+
+    #[test]
+    fn parses_difficulty() {
+        let (beatmap, _) = parse_body("[Difficulty]\nHPDrainRate:6\nOverallDifficulty:5.5\nSliderMultiplier:1.4\n").unwrap();
+        assert_eq!(beatmap.difficulty.hp_drain_rate, 6.0);
+        assert_eq!(beatmap.difficulty.overall_difficulty, 5.5);
+        assert_eq!(beatmap.difficulty.slider_multiplier, 1.4);
+    }
+
+    #[test]
+    fn parses_events() {
+        let (beatmap, _) = parse_body("[Events]\n0,0,\"bg.jpg\",0,0\n2,1000,2000\nSprite,Foreground,Centre,\"a.png\",320,240\n").unwrap();
+        assert!(matches!(&beatmap.events[0], Event::Background { filename, .. } if filename == "bg.jpg"));
+        assert!(matches!(beatmap.events[1], Event::Break { start_time: 1000, end_time: 2000 }));
+        assert!(matches!(&beatmap.events[2], Event::Other(_)));
+    }
+
+    #[test]
+    fn parses_timing_points() {
+        let (beatmap, _) = parse_body("[TimingPoints]\n-20,500,4,2,1,80,1,1\n1000,-50,4,2,1,80,0,0\n").unwrap();
+        let points = beatmap.timing.points();
+        assert_eq!(points[0].time, -20.0);
+        assert_eq!(points[0].bpm(), Some(120.0));
+        assert_eq!(points[0].sample_set, SampleSet::Soft);
+        assert!(points[0].kiai());
+        assert_eq!(points[1].sv(), 2.0);
+    }
+
+    #[test]
+    fn parses_colours() {
+        let (beatmap, _) = parse_body("[Colours]\nCombo1 : 255,0,0\nCombo2 : 0,255,0\nSliderBorder : 1,2,3\n").unwrap();
+        assert_eq!(beatmap.colours.combos, vec![[255, 0, 0], [0, 255, 0]]);
+        assert_eq!(beatmap.colours.slider_border, Some([1, 2, 3]));
+    }
+
+    #[test]
+    fn parses_hit_objects() {
+        let (_, objects) = parse_body(&format!("{}256,192,1000,1,2\n256,192,1500,1,4\n256,192,2000,2,0,L|300:192,1,140\n256,192,3000,12,0,4000\n", TIMING)).unwrap();
+        let objects: Vec<&dyn HitObject> = objects.iter().map(|(_, object)| object).collect();
+
+        assert_eq!(objects[0].time().unwrap().0.as_ms(), 1000);
+        assert!(objects[0].variant().unwrap().is_kat());
+        assert!(objects[1].variant().unwrap().is_big());
+        assert_eq!(objects[2].duration().unwrap().0.as_ms(), 500);
+        assert_eq!(objects[3].duration().unwrap().0.as_ms(), 1000);
+    }
+
+    #[test]
+    fn reports_missing_header() {
+        let err = parse("[General]\nMode: 1\n").err().unwrap();
+        assert_eq!((err.line, err.column), (1, 1));
+    }
+
+    #[test]
+    fn reports_invalid_values() {
+        assert_eq!(error_at("[General]\nAudioLeadIn: soon\n"), (4, 14));
+        assert_eq!(error_at("[Editor]\nBookmarks: 100,x\n"), (4, 16));
+        assert_eq!(error_at("[Metadata]\nTitle\n"), (4, 1));
+        assert_eq!(error_at("[Difficulty]\nSliderMultiplier: fast\n"), (4, 19));
+        assert_eq!(error_at("[Events]\n2,1000\n"), (4, 7));
+        assert_eq!(error_at("[TimingPoints]\n0,500,4,9\n"), (4, 9));
+        assert_eq!(error_at("[Colours]\nCombo1 : 255,0\n"), (4, 15));
+        assert_eq!(error_at("[HitObjects]\n256,192\n"), (4, 8));
+    }
+
+    #[test]
+    fn rejects_negative_times() {
+        assert_eq!(error_at(&format!("{}256,192,-5,1,0\n", TIMING)), (10, 9));
+        assert_eq!(error_at(&format!("{}256,192,NaN,1,0\n", TIMING)), (10, 9));
+        assert_eq!(error_at(&format!("{}256,192,inf,1,0\n", TIMING)), (10, 9));
+        assert_eq!(error_at(&format!("{}256,192,3000,12,0,-10\n", TIMING)), (10, 19));
+    }
+
+    #[test]
+    fn rejects_negative_durations() {
+        assert_eq!(error_at(&format!("{}256,192,3000,12,0,2000\n", TIMING)), (10, 19));
+        assert_eq!(error_at(&format!("{}256,192,2000,2,0,L|300:192,1,-140\n", TIMING)), (10, 30));
+    }
 }
\ No newline at end of file
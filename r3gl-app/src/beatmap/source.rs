@@ -0,0 +1,44 @@
+// Original text of a parsed file, kept around so the writer can reproduce
+// everything it didn't touch byte-for-byte.
+#[derive(Debug, Clone, Default)]
+pub struct Source {
+    pub(crate) line_ending: LineEnding,
+    pub(crate) preamble: Vec<String>,
+    pub(crate) sections: Vec<RawSection>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum LineEnding {
+    #[default]
+    CrLf,
+    Lf,
+}
+
+impl LineEnding {
+    pub fn as_str(&self) -> &'static str {
+        return match self {
+            LineEnding::CrLf => "\r\n",
+            LineEnding::Lf   => "\n",
+        };
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct RawSection {
+    pub(crate) name: String,
+    pub(crate) header: String,
+    pub(crate) lines: Vec<RawLine>,
+}
+
+#[derive(Debug, Clone)]
+pub struct RawLine {
+    pub(crate) text: String,              // As in the file, including the line ending
+    pub(crate) key: Option<String>,       // Key of `key: value` lines
+    pub(crate) canonical: Option<String>, // What the writer produces for this line, `None` if it's opaque to us
+}
+
+impl RawSection {
+    pub fn has_key(&self, key: &str) -> bool {
+        return self.lines.iter().any(|line| line.key.as_deref() == Some(key));
+    }
+}
\ No newline at end of file
@@ -0,0 +1,335 @@
+use intbits::Bits;
+
+use super::component::sample::SampleSet;
+
+// Ticks closer than this are considered to be the same time
+const EPSILON: f64 = 0.01;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct TimingPoint {
+    pub time: f64,
+    pub beat_length: f64, // ms per beat when uninherited, -100 / SV otherwise
+    pub meter: u32,
+    pub sample_set: SampleSet,
+    pub sample_index: u32,
+    pub volume: u32,
+    pub uninherited: bool,
+    pub effects: u32,
+}
+
+impl Default for TimingPoint {
+    fn default() -> Self {
+        return Self {
+            time         : 0.0,
+            beat_length  : 500.0,
+            meter        : 4,
+            sample_set   : SampleSet::Auto,
+            sample_index : 0,
+            volume       : 100,
+            uninherited  : true,
+            effects      : 0,
+        };
+    }
+}
+
+impl TimingPoint {
+    pub fn uninherited(time: f64, bpm: f64, meter: u32) -> Self {
+        return Self {
+            time,
+            beat_length: 60000.0 / bpm,
+            meter,
+            ..Default::default()
+        };
+    }
+
+    pub fn inherited(time: f64, sv: f64) -> Self {
+        return Self {
+            time,
+            beat_length: -100.0 / sv,
+            uninherited: false,
+            ..Default::default()
+        };
+    }
+
+    pub fn bpm(&self) -> Option<f64> {
+        return if self.uninherited { Some(60000.0 / self.beat_length) } else { None };
+    }
+
+    pub fn sv(&self) -> f64 {
+        if self.uninherited || self.beat_length >= 0.0 {
+            return 1.0;
+        }
+
+        // osu! clamps SV to 0.1x - 10x
+        return (-100.0 / self.beat_length).clamp(0.1, 10.0);
+    }
+
+    pub fn kiai(&self) -> bool { return self.effects.bit(0); }
+    pub fn set_kiai(&mut self, value: bool) { self.effects = self.effects.with_bit(0, value); }
+
+    pub fn omit_first_barline(&self) -> bool { return self.effects.bit(3); }
+}
+
+// Effective state of all points at some time
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ControlPoint {
+    pub sv: f64,
+    pub kiai: bool,
+    pub volume: u32,
+    pub sample_set: SampleSet,
+    pub sample_index: u32,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct BeatPosition {
+    pub index: i64,    // Beats since the active uninherited point
+    pub fraction: f64, // 0.0 ..< 1.0
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct Timing {
+    points: Vec<TimingPoint>,
+}
+
+impl From<Vec<TimingPoint>> for Timing {
+    fn from(mut points: Vec<TimingPoint>) -> Self {
+        // Stable, so uninherited points stay in front of inherited ones at the same time
+        points.sort_by(|a, b| a.time.total_cmp(&b.time));
+        return Self { points };
+    }
+}
+
+impl Timing {
+    pub fn points(&self) -> &[TimingPoint] {
+        return &self.points;
+    }
+
+    pub fn is_empty(&self) -> bool {
+        return !self.points.iter().any(|point| point.uninherited);
+    }
+
+    pub fn insert(&mut self, point: TimingPoint) -> usize {
+        let index = self.points.partition_point(|x| x.time <= point.time);
+        self.points.insert(index, point);
+        return index;
+    }
+
+    // `None` if there's no point at `index`
+    pub fn remove(&mut self, index: usize) -> Option<TimingPoint> {
+        return (index < self.points.len()).then(|| self.points.remove(index));
+    }
+
+    pub fn replace(&mut self, index: usize, point: TimingPoint) -> Option<(TimingPoint, usize)> {
+        let old = self.remove(index)?;
+        return Some((old, self.insert(point)));
+    }
+
+    // Uninherited point that's active at `time`, the first one is extended backwards
+    pub fn uninherited_at(&self, time: f64) -> Option<&TimingPoint> {
+        let mut uninherited = self.points.iter().filter(|point| point.uninherited);
+        let first = uninherited.next()?;
+        return Some(uninherited.take_while(|point| point.time <= time + EPSILON).last().unwrap_or(first));
+    }
+
+    pub fn control_at(&self, time: f64) -> ControlPoint {
+        let mut control = ControlPoint {
+            sv: 1.0,
+            kiai: false,
+            volume: 100,
+            sample_set: SampleSet::Auto,
+            sample_index: 0,
+        };
+
+        for point in self.points.iter().take_while(|point| point.time <= time + EPSILON) {
+            control.sv = point.sv();
+            control.kiai = point.kiai();
+            control.volume = point.volume;
+            control.sample_set = point.sample_set;
+            control.sample_index = point.sample_index;
+        }
+
+        return control;
+    }
+
+    pub fn bpm_at(&self, time: f64) -> Option<f64> {
+        return self.uninherited_at(time)?.bpm();
+    }
+
+    pub fn beat_at(&self, time: f64) -> Option<BeatPosition> {
+        let point = self.uninherited_at(time)?;
+        let beat = (time - point.time) / point.beat_length;
+        let index = (beat + EPSILON / point.beat_length).floor();
+
+        return Some(BeatPosition {
+            index: index as i64,
+            fraction: (beat - index).max(0.0),
+        });
+    }
+
+    // Slider velocity in osu!pixels per ms, converts between drumroll length and duration
+    pub fn velocity_at(&self, time: f64, multiplier: f64) -> f64 {
+        let beat_length = self.uninherited_at(time).map_or(TimingPoint::default().beat_length, |point| point.beat_length);
+        return multiplier * 100.0 * self.control_at(time).sv / beat_length;
+    }
+
+    // Time of the `n`th 1/`divisor` tick strictly after `time`
+    pub fn tick_after(&self, time: f64, divisor: u32, n: u32) -> Option<f64> {
+        let mut time = time;
+        for _ in 0 .. n {
+            let point = self.uninherited_at(time)?;
+            let step = point.beat_length / divisor as f64;
+            let tick = point.time + (((time - point.time) / step + EPSILON / step).floor() + 1.0) * step;
+
+            // Every uninherited point restarts the grid
+            time = match self.next_uninherited(time) {
+                Some(next) if next.time < tick - EPSILON => next.time,
+                _ => tick,
+            };
+        }
+
+        return Some(time);
+    }
+
+    // Time of the `n`th 1/`divisor` tick strictly before `time`
+    pub fn tick_before(&self, time: f64, divisor: u32, n: u32) -> Option<f64> {
+        let mut time = time;
+        for _ in 0 .. n {
+            // Points are only active at and after their own time
+            let point = self.uninherited_at(time - EPSILON * 2.0)?;
+            let step = point.beat_length / divisor as f64;
+            time = point.time + (((time - point.time) / step - EPSILON / step).ceil() - 1.0) * step;
+        }
+
+        return Some(time);
+    }
+
+    // Closest 1/`divisor` tick to `time`
+    pub fn snap(&self, time: f64, divisor: u32) -> Option<f64> {
+        let point = self.uninherited_at(time)?;
+        let step = point.beat_length / divisor as f64;
+        let snapped = point.time + ((time - point.time) / step).round() * step;
+
+        // Ticks of one grid never go past the start of the next one
+        return match self.next_uninherited(point.time) {
+            Some(next) if snapped > next.time => Some(next.time),
+            _ => Some(snapped),
+        };
+    }
+
+    fn next_uninherited(&self, time: f64) -> Option<&TimingPoint> {
+        return self.points.iter().find(|point| point.uninherited && point.time > time + EPSILON);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // 120 bpm 4/4 from 1000, double SV at 2000, 150 bpm 3/4 from 2300
+    fn timing() -> Timing {
+        return Timing::from(vec![
+            TimingPoint::uninherited(1000.0, 120.0, 4),
+            TimingPoint::inherited(2000.0, 2.0),
+            TimingPoint::uninherited(2300.0, 150.0, 3),
+        ]);
+    }
+
+    fn assert_time(actual: Option<f64>, expected: f64) {
+        let actual = actual.expect("no time");
+        assert!((actual - expected).abs() < 1e-9, "{} instead of {}", actual, expected);
+    }
+
+    #[test]
+    fn finds_uninherited_point() {
+        let timing = timing();
+        let at = |time| timing.uninherited_at(time).unwrap().time;
+        assert_eq!(at(-500.0), 1000.0);
+        assert_eq!(at(1000.0), 1000.0);
+        assert_eq!(at(2100.0), 1000.0); // Inherited points don't count
+        assert_eq!(at(2299.995), 2300.0);
+        assert_eq!(at(2299.9), 1000.0);
+        assert_eq!(at(5000.0), 2300.0);
+        assert!(Timing::default().uninherited_at(0.0).is_none());
+    }
+
+    #[test]
+    fn counts_beats() {
+        let timing = timing();
+        let beat = |time| timing.beat_at(time).map(|beat| (beat.index, beat.fraction));
+        assert_eq!(beat(1000.0), Some((0, 0.0)));
+        assert_eq!(beat(1750.0), Some((1, 0.5)));
+        assert_eq!(beat(2700.0), Some((1, 0.0)));
+
+        // The first grid goes on before its point
+        assert_eq!(beat(500.0), Some((-1, 0.0)));
+        assert_eq!(beat(-250.0), Some((-3, 0.5)));
+
+        // Just short of a beat is on it
+        assert_eq!(beat(1499.995), Some((1, 0.0)));
+    }
+
+    #[test]
+    fn converts_velocity() {
+        let timing = timing();
+        assert!((timing.velocity_at(2100.0, 1.4) - 0.56).abs() < 1e-9);
+        assert!((timing.velocity_at(500.0, 1.0) - 0.2).abs() < 1e-9);
+        assert!((timing.velocity_at(2500.0, 1.0) - 0.25).abs() < 1e-9);
+        assert!((Timing::default().velocity_at(0.0, 1.0) - 0.2).abs() < 1e-9);
+    }
+
+    #[test]
+    fn ticks_after() {
+        let timing = timing();
+        assert_time(timing.tick_after(1000.0, 4, 1), 1125.0);
+        assert_time(timing.tick_after(1000.0, 1, 2), 2000.0);
+
+        // Next point restarts the grid, 2500 is never a tick
+        assert_time(timing.tick_after(2000.0, 1, 1), 2300.0);
+        assert_time(timing.tick_after(1900.0, 1, 2), 2300.0);
+        assert_time(timing.tick_after(2300.0, 1, 1), 2700.0);
+
+        // Before the first point
+        assert_time(timing.tick_after(-100.0, 1, 1), 0.0);
+        assert_time(timing.tick_after(-600.0, 1, 1), -500.0);
+
+        // A time within the tolerance of a tick is on it, so the next one comes after
+        assert_time(timing.tick_after(1124.995, 4, 1), 1250.0);
+        assert_time(timing.tick_after(1124.98, 4, 1), 1125.0);
+    }
+
+    #[test]
+    fn ticks_before() {
+        let timing = timing();
+        assert_time(timing.tick_before(1250.0, 4, 1), 1125.0);
+        assert_time(timing.tick_before(2000.0, 1, 2), 1000.0);
+
+        // A point only applies after it, so its own time is on the previous grid
+        assert_time(timing.tick_before(2300.0, 1, 1), 2000.0);
+        assert_time(timing.tick_before(2700.0, 1, 1), 2300.0);
+
+        // Before the first point
+        assert_time(timing.tick_before(0.0, 1, 1), -500.0);
+        assert_time(timing.tick_before(-500.0, 2, 1), -750.0);
+
+        assert_time(timing.tick_before(1250.005, 4, 1), 1125.0);
+        assert_time(timing.tick_before(1250.02, 4, 1), 1250.0);
+    }
+
+    #[test]
+    fn snaps() {
+        let timing = timing();
+        assert_time(timing.snap(1130.0, 4), 1125.0);
+        assert_time(timing.snap(2310.0, 1), 2300.0);
+        assert_time(timing.snap(2420.0, 3), 2300.0 + 400.0 / 3.0);
+
+        // Rounding to 2500 would go past the next point
+        assert_time(timing.snap(2290.0, 1), 2300.0);
+        assert_time(timing.snap(2100.0, 1), 2000.0);
+
+        // Before the first point
+        assert_time(timing.snap(-260.0, 1), -500.0);
+        assert_time(timing.snap(-240.0, 1), 0.0);
+
+        assert!(Timing::default().snap(0.0, 4).is_none());
+    }
+}
\ No newline at end of file
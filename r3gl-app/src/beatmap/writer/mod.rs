@@ -0,0 +1,7 @@
+use super::{beatmap::Beatmap, objects::Objects};
+
+pub mod osu_taiko;
+
+pub fn write(beatmap: &Beatmap, objects: &Objects) -> String {
+    return osu_taiko::write(beatmap, objects);
+}
\ No newline at end of file
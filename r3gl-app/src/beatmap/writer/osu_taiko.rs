@@ -0,0 +1,372 @@
+use std::collections::{HashMap, VecDeque};
+
+use intbits::Bits;
+
+use crate::beatmap::{objects::Objects, beatmap::{Beatmap, GeneralSection, EditorSection, MetadataSection, DifficultySection, ColoursSection, Event, Colour}, timing::TimingPoint, component::{HitObject, sample::SampleComponent, adapter::taiko::TaikoVariantAdapter}, source::{RawSection, LineEnding}};
+
+struct Output {
+    text: String,
+    ending: &'static str,
+    blank: bool,
+}
+
+impl Output {
+    fn new(ending: LineEnding) -> Self {
+        return Self {
+            text: String::new(),
+            ending: ending.as_str(),
+            blank: false,
+        };
+    }
+
+    // Raw text already carries its line ending, except for the last line of a file
+    fn raw(&mut self, text: &str) {
+        self.terminate();
+        self.text.push_str(text);
+        self.blank = text.trim().is_empty();
+    }
+
+    fn line(&mut self, text: &str) {
+        self.terminate();
+        self.text.push_str(text);
+        self.text.push_str(self.ending);
+        self.blank = text.trim().is_empty();
+    }
+
+    fn terminate(&mut self) {
+        if !self.text.is_empty() && !self.text.ends_with('\n') {
+            self.text.push_str(self.ending);
+        }
+    }
+
+    fn separate(&mut self) {
+        if !self.text.is_empty() && !self.blank {
+            self.line("");
+        }
+    }
+}
+
+pub fn write(beatmap: &Beatmap, objects: &Objects) -> String {
+    let source = &beatmap.source;
+    let mut out = Output::new(source.line_ending);
+
+    if source.preamble.is_empty() {
+        out.line(&format!("osu file format v{}", beatmap.version));
+        out.line("");
+    } else {
+        for line in &source.preamble {
+            out.raw(line);
+        }
+    }
+
+    let mut sections = sections(beatmap, objects);
+    for raw in &source.sections {
+        if let Some(index) = sections.iter().position(|(name, _)| *name == raw.name) {
+            let (_, lines) = sections.remove(index);
+            merge(&mut out, raw, &lines);
+        } else {
+            out.raw(&raw.header);
+            for line in &raw.lines {
+                out.raw(&line.text);
+            }
+        }
+    }
+
+    // Sections the original file didn't have
+    for (name, lines) in sections {
+        if lines.is_empty() {
+            continue;
+        }
+
+        out.separate();
+        out.line(&format!("[{}]", name));
+        for line in &lines {
+            out.line(line);
+        }
+    }
+
+    return out.text;
+}
+
+fn sections(beatmap: &Beatmap, objects: &Objects) -> Vec<(&'static str, Vec<String>)> {
+    let raw = |name: &str| beatmap.source.sections.iter().find(|section| section.name == name);
+
+    // Editor settings stay in General if that's where the file had them
+    let legacy: Vec<(String, String)> = legacy_editor_lines(&beatmap.editor).into_iter()
+        .filter(|(key, _)| raw("General").map_or(false, |raw| raw.has_key(key)))
+        .collect();
+
+    let mut general = filter(general_lines(&beatmap.general), general_lines(&Default::default()), raw("General"));
+    general.extend(legacy.iter().map(|(_, line)| line.clone()));
+
+    let moved = |key: &String| {
+        legacy.iter().any(|(legacy, _)| legacy.strip_prefix(LEGACY_PREFIX) == Some(key)) && !raw("Editor").map_or(false, |raw| raw.has_key(key))
+    };
+
+    let (editor, defaults): (Vec<_>, Vec<_>) = editor_lines(&beatmap.editor).into_iter()
+        .zip(editor_lines(&Default::default()))
+        .filter(|((key, _), _)| !moved(key))
+        .unzip();
+
+    return vec![
+        ("General",      general),
+        ("Editor",       filter(editor, defaults, raw("Editor"))),
+        ("Metadata",     filter(metadata_lines(&beatmap.metadata), metadata_lines(&Default::default()), raw("Metadata"))),
+        ("Difficulty",   filter(difficulty_lines(&beatmap.difficulty), difficulty_lines(&Default::default()), raw("Difficulty"))),
+        ("Events",       beatmap.events.iter().map(format_event).collect()),
+        ("TimingPoints", beatmap.timing.points().iter().map(format_timing_point).collect()),
+        ("Colours",      colours_lines(&beatmap.colours).into_iter().map(|(_, line)| line).collect()),
+        ("HitObjects",   objects.iter().filter_map(|(_, object)| format_object(object, beatmap)).collect()),
+    ];
+}
+
+// Keys are only written if they were in the file already or differ from the default
+fn filter(lines: Vec<(String, String)>, defaults: Vec<(String, String)>, raw: Option<&RawSection>) -> Vec<String> {
+    return lines.into_iter().zip(defaults).filter_map(|((key, line), (_, default))| {
+        if line != default || raw.map_or(false, |raw| raw.has_key(&key)) {
+            Some(line)
+        } else { None }
+    }).collect();
+}
+
+// Writes `lines` in their order, but reuses the original text for every line that didn't change
+// and keeps comments and lines we don't understand close to where they were.
+fn merge(out: &mut Output, raw: &RawSection, lines: &[String]) {
+    let mut originals = HashMap::<&str, VecDeque<&str>>::new();
+    for line in &raw.lines {
+        if let Some(canonical) = &line.canonical {
+            originals.entry(canonical.as_str()).or_default().push_back(&line.text);
+        }
+    }
+
+    let mut pending = HashMap::<&str, usize>::new();
+    for line in lines {
+        *pending.entry(line.as_str()).or_default() += 1;
+    }
+
+    fn emit(out: &mut Output, line: &str, originals: &mut HashMap<&str, VecDeque<&str>>, pending: &mut HashMap<&str, usize>) {
+        if let Some(count) = pending.get_mut(line) {
+            *count = count.saturating_sub(1);
+        }
+
+        match originals.get_mut(line).and_then(VecDeque::pop_front) {
+            Some(text) => out.raw(text),
+            None => out.line(line),
+        }
+    }
+
+    // Blank lines at the end separate sections, new lines go before them
+    let trailing = raw.lines.iter().rev().take_while(|line| line.canonical.is_none() && line.text.trim().is_empty()).count();
+    let (body, tail) = raw.lines.split_at(raw.lines.len() - trailing);
+
+    out.raw(&raw.header);
+
+    let mut next = 0;
+    for line in body {
+        match &line.canonical {
+            None => out.raw(&line.text),
+            Some(canonical) => {
+                // A changed value is written where the key was
+                let is_pending = |line: &str| pending.get(line).copied().unwrap_or(0) > 0;
+                let target = if is_pending(canonical) {
+                    canonical.as_str()
+                } else {
+                    let changed = line.key.as_deref().and_then(|key| {
+                        lines[next ..].iter().find(|line| key_of(line) == Some(key) && is_pending(line))
+                    });
+
+                    // Deleted, or already written because it was moved
+                    match changed {
+                        Some(changed) => changed.as_str(),
+                        None => continue,
+                    }
+                };
+
+                while next < lines.len() {
+                    let current = lines[next].as_str();
+                    next += 1;
+
+                    emit(out, current, &mut originals, &mut pending);
+                    if current == target {
+                        break;
+                    }
+                }
+            }
+        }
+    }
+
+    for line in &lines[next ..] {
+        emit(out, line, &mut originals, &mut pending);
+    }
+
+    for line in tail {
+        out.raw(&line.text);
+    }
+}
+
+fn key_of(line: &str) -> Option<&str> {
+    return line.split_once(':').map(|(key, _)| key.trim());
+}
+
+fn flag(value: bool) -> u32 {
+    return u32::from(value);
+}
+
+fn pairs(separator: &str, values: Vec<(&str, String)>) -> Vec<(String, String)> {
+    return values.into_iter().map(|(key, value)| {
+        (key.to_owned(), format!("{}{}{}", key, separator, value))
+    }).collect();
+}
+
+pub(crate) fn general_lines(general: &GeneralSection) -> Vec<(String, String)> {
+    return pairs(": ", vec![
+        ("AudioFilename",            general.audio_filename.to_string_lossy().into_owned()),
+        ("AudioLeadIn",              general.audio_lead_in.to_string()),
+        ("PreviewTime",              general.preview_time.to_string()),
+        ("Countdown",                general.countdown.to_string()),
+        ("SampleSet",                general.sample_set.name().to_owned()),
+        ("StackLeniency",            general.stack_leniency.to_string()),
+        ("Mode",                     general.mode.to_string()),
+        ("LetterboxInBreaks",        flag(general.letterbox_in_breaks).to_string()),
+        ("UseSkinSprites",           flag(general.use_skin_sprites).to_string()),
+        ("OverlayPosition",          general.overlay_position.clone()),
+        ("SkinPreference",           general.skin_preference.clone()),
+        ("EpilepsyWarning",          flag(general.epilepsy_warning).to_string()),
+        ("CountdownOffset",          general.countdown_offset.to_string()),
+        ("SpecialStyle",             flag(general.special_style).to_string()),
+        ("WidescreenStoryboard",     flag(general.widescreen_storyboard).to_string()),
+        ("SamplesMatchPlaybackRate", flag(general.samples_match_playback_rate).to_string()),
+    ]);
+}
+
+pub(crate) fn editor_lines(editor: &EditorSection) -> Vec<(String, String)> {
+    let bookmarks = editor.bookmarks.iter().map(|x| x.to_string()).collect::<Vec<_>>().join(",");
+    return pairs(": ", vec![
+        ("Bookmarks",       bookmarks),
+        ("DistanceSpacing", editor.distance_spacing.to_string()),
+        ("BeatDivisor",     editor.beat_divisor.to_string()),
+        ("GridSize",        editor.grid_size.to_string()),
+        ("TimelineZoom",    editor.timeline_zoom.to_string()),
+    ]);
+}
+
+// Pre-v14 files keep these in General, with the prefix
+const LEGACY_PREFIX: &str = "Editor";
+const LEGACY_KEYS: [&str; 2] = ["Bookmarks", "DistanceSpacing"];
+
+pub(crate) fn legacy_editor_lines(editor: &EditorSection) -> Vec<(String, String)> {
+    return editor_lines(editor).into_iter()
+        .filter(|(key, _)| LEGACY_KEYS.contains(&key.as_str()))
+        .map(|(key, line)| (format!("{}{}", LEGACY_PREFIX, key), format!("{}{}", LEGACY_PREFIX, line)))
+        .collect();
+}
+
+pub(crate) fn metadata_lines(metadata: &MetadataSection) -> Vec<(String, String)> {
+    return pairs(":", vec![
+        ("Title",         metadata.title.clone()),
+        ("TitleUnicode",  metadata.title_unicode.clone()),
+        ("Artist",        metadata.artist.clone()),
+        ("ArtistUnicode", metadata.artist_unicode.clone()),
+        ("Creator",       metadata.creator.clone()),
+        ("Version",       metadata.version.clone()),
+        ("Source",        metadata.source.clone()),
+        ("Tags",          metadata.tags.join(" ")),
+        ("BeatmapID",     metadata.beatmap_id.to_string()),
+        ("BeatmapSetID",  metadata.beatmap_set_id.to_string()),
+    ]);
+}
+
+pub(crate) fn difficulty_lines(difficulty: &DifficultySection) -> Vec<(String, String)> {
+    return pairs(":", vec![
+        ("HPDrainRate",       difficulty.hp_drain_rate.to_string()),
+        ("CircleSize",        difficulty.circle_size.to_string()),
+        ("OverallDifficulty", difficulty.overall_difficulty.to_string()),
+        ("ApproachRate",      difficulty.approach_rate.to_string()),
+        ("SliderMultiplier",  difficulty.slider_multiplier.to_string()),
+        ("SliderTickRate",    difficulty.slider_tick_rate.to_string()),
+    ]);
+}
+
+pub(crate) fn colours_lines(colours: &ColoursSection) -> Vec<(String, String)> {
+    let mut lines = colours.combos.iter().enumerate().map(|(i, colour)| {
+        let key = format!("Combo{}", i + 1);
+        let line = format!("{} : {}", key, format_colour(colour));
+        (key, line)
+    }).collect::<Vec<_>>();
+
+    if let Some(colour) = &colours.slider_track_override {
+        lines.push((String::from("SliderTrackOverride"), format!("SliderTrackOverride : {}", format_colour(colour))));
+    }
+
+    if let Some(colour) = &colours.slider_border {
+        lines.push((String::from("SliderBorder"), format!("SliderBorder : {}", format_colour(colour))));
+    }
+
+    return lines;
+}
+
+fn format_colour(colour: &Colour) -> String {
+    return format!("{},{},{}", colour[0], colour[1], colour[2]);
+}
+
+pub(crate) fn format_event(event: &Event) -> String {
+    return match event {
+        Event::Background { filename, x_offset, y_offset } => format!("0,0,\"{}\",{},{}", filename, x_offset, y_offset),
+        Event::Video { start_time, filename, x_offset, y_offset } => format!("Video,{},\"{}\",{},{}", start_time, filename, x_offset, y_offset),
+        Event::Break { start_time, end_time } => format!("2,{},{}", start_time, end_time),
+        Event::Other(line) => line.clone(),
+    };
+}
+
+pub(crate) fn format_timing_point(point: &TimingPoint) -> String {
+    return format!("{},{},{},{},{},{},{},{}",
+        point.time,
+        point.beat_length,
+        point.meter,
+        point.sample_set.index(),
+        point.sample_index,
+        point.volume,
+        flag(point.uninherited),
+        point.effects,
+    );
+}
+
+fn format_sample(sample: &SampleComponent) -> String {
+    return format!("{}:{}:{}:{}:{}",
+        sample.normal_set.index(),
+        sample.addition_set.index(),
+        sample.index,
+        sample.volume,
+        sample.filename,
+    );
+}
+
+// Taiko ignores positions, so objects are always written at the center of the playfield
+pub(crate) fn format_object(object: &dyn HitObject, beatmap: &Beatmap) -> Option<String> {
+    let time = object.time()?.0.as_ms();
+    let sample = object.sample().map(format_sample).unwrap_or_else(|| String::from("0:0:0:0:"));
+
+    return match (object.variant(), object.duration()) {
+        (Some(variant), None) => {
+            let hit_sound = 0u32
+                .with_bit(2, variant.is_big())
+                .with_bit(3, variant.is_kat());
+
+            Some(format!("256,192,{},1,{},{}", time, hit_sound, sample))
+        }
+
+        // Drumrolls are written as straight single-slide sliders
+        (Some(variant), Some(duration)) => {
+            let hit_sound = 0u32.with_bit(2, variant.is_big());
+            let velocity = beatmap.timing.velocity_at(time as f64, beatmap.difficulty.slider_multiplier);
+            let length = (duration.0.as_ms() as f64 * velocity * 100.0).round() / 100.0;
+
+            Some(format!("256,192,{},2,{},L|{}:192,1,{},0|0,0:0|0:0,{}", time, hit_sound, 256 + length.round() as i64, length, sample))
+        }
+
+        // New combo is always set on spinners
+        (None, Some(duration)) => Some(format!("256,192,{},12,0,{},{}", time, time + duration.0.as_ms(), sample)),
+
+        (None, None) => None,
+    };
+}
\ No newline at end of file
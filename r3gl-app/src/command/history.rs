@@ -0,0 +1,91 @@
+use std::collections::VecDeque;
+
+use color_eyre::eyre::Result;
+
+use crate::beatmap::{beatmap::Beatmap, objects::Objects};
+
+use super::Command;
+
+pub struct History {
+    undo: VecDeque<Box<dyn Command>>,
+    redo: Vec<Box<dyn Command>>,
+    limit: usize,
+
+    // Length of the undo stack at the last save, None if that state can't be reached anymore
+    saved: Option<usize>,
+}
+
+impl History {
+    pub fn new(limit: usize) -> Self {
+        return Self {
+            undo: VecDeque::new(),
+            redo: Vec::new(),
+            limit,
+
+            saved: Some(0),
+        };
+    }
+
+    // Failed commands aren't recorded
+    pub fn execute(&mut self, mut command: Box<dyn Command>, beatmap: &mut Beatmap, objects: &mut Objects) -> Result<&dyn Command> {
+        command.apply(beatmap, objects)?;
+
+        // Saved state was in the branch we just replaced
+        if self.saved.map_or(false, |saved| saved > self.undo.len()) {
+            self.saved = None;
+        }
+
+        self.redo.clear();
+        self.undo.push_back(command);
+
+        if self.undo.len() > self.limit {
+            self.undo.pop_front();
+            self.saved = self.saved.and_then(|saved| saved.checked_sub(1));
+        }
+
+        return Ok(self.undo.back().unwrap().as_ref());
+    }
+
+    // A command that fails stays where it was
+    pub fn undo(&mut self, beatmap: &mut Beatmap, objects: &mut Objects) -> Result<Option<&dyn Command>> {
+        let command = match self.undo.back_mut() {
+            Some(command) => command,
+            None => return Ok(None),
+        };
+
+        command.revert(beatmap, objects)?;
+        self.redo.push(self.undo.pop_back().unwrap());
+        return Ok(self.redo.last().map(|command| command.as_ref()));
+    }
+
+    pub fn redo(&mut self, beatmap: &mut Beatmap, objects: &mut Objects) -> Result<Option<&dyn Command>> {
+        let command = match self.redo.last_mut() {
+            Some(command) => command,
+            None => return Ok(None),
+        };
+
+        command.apply(beatmap, objects)?;
+        self.undo.push_back(self.redo.pop().unwrap());
+        return Ok(self.undo.back().map(|command| command.as_ref()));
+    }
+
+    pub fn can_undo(&self) -> bool { return !self.undo.is_empty(); }
+    pub fn can_redo(&self) -> bool { return !self.redo.is_empty(); }
+
+    pub fn undo_name(&self) -> Option<&'static str> { return self.undo.back().map(|command| command.name()); }
+    pub fn redo_name(&self) -> Option<&'static str> { return self.redo.last().map(|command| command.name()); }
+
+    pub fn mark_saved(&mut self) {
+        self.saved = Some(self.undo.len());
+    }
+
+    pub fn is_dirty(&self) -> bool {
+        return self.saved != Some(self.undo.len());
+    }
+
+    pub fn clear(&mut self) {
+        self.undo.clear();
+        self.redo.clear();
+        self.saved = Some(0);
+    }
+}
\ No newline at end of file
@@ -0,0 +1,29 @@
+use color_eyre::eyre::Result;
+
+use crate::beatmap::{beatmap::{Beatmap, MetadataSection}, objects::Objects};
+
+use super::Command;
+
+pub struct ChangeMetadata {
+    metadata: MetadataSection,
+}
+
+impl ChangeMetadata {
+    pub fn new(metadata: MetadataSection) -> Self {
+        return Self { metadata };
+    }
+}
+
+impl Command for ChangeMetadata {
+    fn name(&self) -> &'static str { "Change metadata" }
+
+    fn apply(&mut self, beatmap: &mut Beatmap, _: &mut Objects) -> Result<()> {
+        std::mem::swap(&mut beatmap.metadata, &mut self.metadata);
+
+        return Ok(());
+    }
+
+    fn revert(&mut self, beatmap: &mut Beatmap, objects: &mut Objects) -> Result<()> {
+        return self.apply(beatmap, objects);
+    }
+}
\ No newline at end of file
@@ -0,0 +1,21 @@
+use color_eyre::eyre::Result;
+
+use crate::beatmap::{beatmap::Beatmap, objects::{Objects, ObjectId}};
+
+pub mod history;
+pub mod object;
+pub mod timing;
+pub mod metadata;
+
+// Reversible edit, `revert` is only ever called after `apply` and the other way around.
+// Either one fails without changing anything if the beatmap isn't what the command expects
+pub trait Command {
+    fn name(&self) -> &'static str;
+
+    fn apply(&mut self, beatmap: &mut Beatmap, objects: &mut Objects) -> Result<()>;
+    fn revert(&mut self, beatmap: &mut Beatmap, objects: &mut Objects) -> Result<()>;
+
+    // Objects to select afterwards, None keeps the current selection
+    fn applied_selection(&self) -> Option<Vec<ObjectId>> { None }
+    fn reverted_selection(&self) -> Option<Vec<ObjectId>> { None }
+}
\ No newline at end of file
@@ -0,0 +1,338 @@
+use color_eyre::eyre::{Report, Result, eyre};
+
+use crate::beatmap::{beatmap::Beatmap, component::{HitObject, adapter::taiko::TaikoVariantAdapter}, objects::{Objects, ObjectId}, Time};
+
+use super::Command;
+
+fn missing(id: ObjectId) -> Report {
+    return eyre!("There's no object {:?}", id);
+}
+
+// Every id has to be in `objects`, checked before anything is changed
+fn check(objects: &Objects, ids: &[ObjectId]) -> Result<()> {
+    return match ids.iter().find(|id| !objects.contains(**id)) {
+        Some(id) => Err(missing(*id)),
+        None => Ok(()),
+    };
+}
+
+// Same, and every object has to pass `valid`, `problem` says what's wrong otherwise
+fn check_each(objects: &Objects, ids: &[ObjectId], valid: impl Fn(&dyn HitObject) -> bool, problem: &str) -> Result<()> {
+    check(objects, ids)?;
+    return match ids.iter().find(|id| objects.get(**id).map_or(false, |object| !valid(object))) {
+        Some(id) => Err(eyre!("Object {:?} {}", id, problem)),
+        None => Ok(()),
+    };
+}
+
+// Every id has to be detached, so its object can be put back
+fn check_detached(objects: &Objects, ids: &[ObjectId]) -> Result<()> {
+    return match ids.iter().find(|id| !objects.is_detached(**id)) {
+        Some(id) => Err(eyre!("Object {:?} is already there", id)),
+        None => Ok(()),
+    };
+}
+
+// Drumrolls and dendens don't have a color
+pub fn has_color(object: &dyn HitObject) -> bool {
+    return object.duration().is_none() && object.variant().is_some();
+}
+
+// Dendens can't be finishers
+pub fn can_be_big(object: &dyn HitObject) -> bool {
+    return object.variant().is_some();
+}
+
+pub struct AddObjects {
+    added: Vec<Box<dyn HitObject>>,
+    ids: Vec<ObjectId>,
+}
+
+impl AddObjects {
+    pub fn new(objects: Vec<Box<dyn HitObject>>) -> Self {
+        return Self {
+            added: objects,
+            ids: vec![],
+        };
+    }
+}
+
+impl Command for AddObjects {
+    fn name(&self) -> &'static str { "Add objects" }
+
+    // Ids are only created the first time, redoing reuses them
+    fn apply(&mut self, _: &mut Beatmap, objects: &mut Objects) -> Result<()> {
+        if self.ids.is_empty() {
+            self.ids = self.added.drain(..).map(|object| objects.insert(object)).collect();
+        } else {
+            check_detached(objects, &self.ids)?;
+            for (id, object) in self.ids.iter().zip(self.added.drain(..)) {
+                objects.attach(*id, object);
+            }
+        }
+
+        return Ok(());
+    }
+
+    fn revert(&mut self, _: &mut Beatmap, objects: &mut Objects) -> Result<()> {
+        check(objects, &self.ids)?;
+        self.added = self.ids.iter().filter_map(|id| objects.detach(*id)).collect();
+
+        return Ok(());
+    }
+
+    fn applied_selection(&self) -> Option<Vec<ObjectId>> { Some(self.ids.clone()) }
+    fn reverted_selection(&self) -> Option<Vec<ObjectId>> { Some(vec![]) }
+}
+
+pub struct RemoveObjects {
+    ids: Vec<ObjectId>,
+    removed: Vec<Box<dyn HitObject>>,
+}
+
+impl RemoveObjects {
+    pub fn new(ids: Vec<ObjectId>) -> Self {
+        return Self {
+            ids,
+            removed: vec![],
+        };
+    }
+}
+
+impl Command for RemoveObjects {
+    fn name(&self) -> &'static str { "Remove objects" }
+
+    fn apply(&mut self, _: &mut Beatmap, objects: &mut Objects) -> Result<()> {
+        check(objects, &self.ids)?;
+        self.removed = self.ids.iter().filter_map(|id| objects.detach(*id)).collect();
+
+        return Ok(());
+    }
+
+    fn revert(&mut self, _: &mut Beatmap, objects: &mut Objects) -> Result<()> {
+        check_detached(objects, &self.ids)?;
+        for (id, object) in self.ids.iter().zip(self.removed.drain(..)) {
+            objects.attach(*id, object);
+        }
+
+        return Ok(());
+    }
+
+    fn applied_selection(&self) -> Option<Vec<ObjectId>> { Some(vec![]) }
+    fn reverted_selection(&self) -> Option<Vec<ObjectId>> { Some(self.ids.clone()) }
+}
+
+pub struct MoveObjects {
+    ids: Vec<ObjectId>,
+    offset: i64,
+
+    original: Vec<(ObjectId, Time)>,
+}
+
+impl MoveObjects {
+    pub fn new(ids: Vec<ObjectId>, offset: i64) -> Self {
+        return Self {
+            ids,
+            offset,
+
+            original: vec![],
+        };
+    }
+}
+
+impl Command for MoveObjects {
+    fn name(&self) -> &'static str { "Move objects" }
+
+    // The whole group stops at 0, so it keeps its spacing
+    fn apply(&mut self, _: &mut Beatmap, objects: &mut Objects) -> Result<()> {
+        check(objects, &self.ids)?;
+        let times: Vec<i64> = self.ids.iter().filter_map(|id| objects.get(*id)?.time()).map(|time| time.0.as_ms() as i64).collect();
+        let offset = self.offset.max(-times.iter().min().copied().unwrap_or(0));
+        if times.iter().any(|time| time + offset > u32::MAX as i64) {
+            return Err(eyre!("Objects can't be moved that far"));
+        }
+
+        self.original.clear();
+        for id in &self.ids {
+            if let Some(time) = objects.get_mut(*id).and_then(|object| object.time_mut()) {
+                self.original.push((*id, time.0));
+                time.0 = Time::from_ms((time.0.as_ms() as i64 + offset) as u32);
+            }
+        }
+
+        objects.sort();
+
+        return Ok(());
+    }
+
+    fn revert(&mut self, _: &mut Beatmap, objects: &mut Objects) -> Result<()> {
+        check(objects, &self.ids)?;
+        for (id, original) in &self.original {
+            if let Some(time) = objects.get_mut(*id).and_then(|object| object.time_mut()) {
+                time.0 = *original;
+            }
+        }
+
+        objects.sort();
+
+        return Ok(());
+    }
+}
+
+// Only objects that `has_color`
+pub struct SwitchColor {
+    ids: Vec<ObjectId>,
+}
+
+impl SwitchColor {
+    pub fn new(ids: Vec<ObjectId>) -> Self {
+        return Self { ids };
+    }
+}
+
+impl Command for SwitchColor {
+    fn name(&self) -> &'static str { "Switch color" }
+
+    fn apply(&mut self, _: &mut Beatmap, objects: &mut Objects) -> Result<()> {
+        check_each(objects, &self.ids, has_color, "has no color")?;
+        for id in &self.ids {
+            if let Some(variant) = objects.get_mut(*id).and_then(|object| object.variant_mut()) {
+                variant.switch_color();
+            }
+        }
+
+        return Ok(());
+    }
+
+    fn revert(&mut self, beatmap: &mut Beatmap, objects: &mut Objects) -> Result<()> {
+        return self.apply(beatmap, objects);
+    }
+}
+
+// Only objects that `can_be_big`
+pub struct ToggleBig {
+    ids: Vec<ObjectId>,
+}
+
+impl ToggleBig {
+    pub fn new(ids: Vec<ObjectId>) -> Self {
+        return Self { ids };
+    }
+}
+
+impl Command for ToggleBig {
+    fn name(&self) -> &'static str { "Toggle finisher" }
+
+    fn apply(&mut self, _: &mut Beatmap, objects: &mut Objects) -> Result<()> {
+        check_each(objects, &self.ids, can_be_big, "can't be a finisher")?;
+        for id in &self.ids {
+            if let Some(variant) = objects.get_mut(*id).and_then(|object| object.variant_mut()) {
+                variant.toggle_big();
+            }
+        }
+
+        return Ok(());
+    }
+
+    fn revert(&mut self, beatmap: &mut Beatmap, objects: &mut Objects) -> Result<()> {
+        return self.apply(beatmap, objects);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{beatmap::{component::adapter::taiko::TaikoColor, parser::osu_taiko::{TaikoCircle, TaikoDrumroll, TaikoDenden}}, command::history::History};
+
+    fn circle(time: u32) -> Box<dyn HitObject> {
+        return Box::new(TaikoCircle::new(Time::from_ms(time), TaikoColor::DON, false));
+    }
+
+    fn times(objects: &Objects) -> Vec<u32> {
+        return objects.iter().filter_map(|(_, object)| object.time()).map(|time| time.0.as_ms()).collect();
+    }
+
+    #[test]
+    fn undo_checks_every_object_first() {
+        let (mut beatmap, mut objects) = (Beatmap::default(), Objects::default());
+        let mut history = History::new(10);
+        history.execute(Box::new(AddObjects::new(vec![circle(100), circle(200)])), &mut beatmap, &mut objects).unwrap();
+
+        // One of them removed behind the history's back
+        let id = objects.ids()[0];
+        let removed = objects.detach(id).unwrap();
+        assert!(history.undo(&mut beatmap, &mut objects).is_err());
+        assert_eq!(times(&objects), vec![200]);
+
+        objects.attach(id, removed);
+        history.undo(&mut beatmap, &mut objects).unwrap();
+        assert!(objects.is_empty());
+    }
+
+    #[test]
+    fn redo_keeps_ids_with_their_objects() {
+        let (mut beatmap, mut objects) = (Beatmap::default(), Objects::default());
+        let mut history = History::new(10);
+        history.execute(Box::new(AddObjects::new(vec![circle(100), circle(200)])), &mut beatmap, &mut objects).unwrap();
+        let ids = objects.ids().to_vec();
+        history.undo(&mut beatmap, &mut objects).unwrap();
+
+        // Something else took one of the ids back
+        objects.attach(ids[0], circle(300));
+        assert!(history.redo(&mut beatmap, &mut objects).is_err());
+        assert_eq!(times(&objects), vec![300]);
+
+        objects.detach(ids[0]);
+        history.redo(&mut beatmap, &mut objects).unwrap();
+        assert_eq!(ids.iter().map(|id| objects.get(*id).unwrap().time().unwrap().0.as_ms()).collect::<Vec<_>>(), vec![100, 200]);
+    }
+
+    #[test]
+    fn move_keeps_spacing_at_zero() {
+        let mut beatmap = Beatmap::default();
+        let mut objects = Objects::from(vec![circle(100), circle(250), circle(400)]);
+        let ids = objects.ids().to_vec();
+
+        let mut command = MoveObjects::new(ids[.. 2].to_vec(), -300);
+        command.apply(&mut beatmap, &mut objects).unwrap();
+        assert_eq!(times(&objects), vec![0, 150, 400]);
+
+        command.revert(&mut beatmap, &mut objects).unwrap();
+        assert_eq!(times(&objects), vec![100, 250, 400]);
+
+        assert!(MoveObjects::new(ids, u32::MAX as i64).apply(&mut beatmap, &mut objects).is_err());
+        assert_eq!(times(&objects), vec![100, 250, 400]);
+    }
+
+    #[test]
+    fn remove_fails_on_missing_object() {
+        let mut beatmap = Beatmap::default();
+        let mut objects = Objects::from(vec![circle(100), circle(200)]);
+        let ids = objects.ids().to_vec();
+        objects.detach(ids[1]);
+
+        assert!(RemoveObjects::new(ids).apply(&mut beatmap, &mut objects).is_err());
+        assert_eq!(times(&objects), vec![100]);
+    }
+
+    #[test]
+    fn long_objects_keep_their_variant() {
+        let mut beatmap = Beatmap::default();
+        let mut objects = Objects::from(vec![
+            circle(100),
+            Box::new(TaikoDrumroll::new(Time::from_ms(200), Time::from_ms(100), false)) as Box<dyn HitObject>,
+            Box::new(TaikoDenden::new(Time::from_ms(400), Time::from_ms(100))),
+        ]);
+        let ids = objects.ids().to_vec();
+        let variants = |objects: &Objects| ids.iter().map(|id| objects.get(*id).unwrap().variant().map(|variant| variant.0)).collect::<Vec<_>>();
+        let before = variants(&objects);
+
+        // Nothing changes, not even the circle
+        assert!(SwitchColor::new(ids[.. 2].to_vec()).apply(&mut beatmap, &mut objects).is_err());
+        assert!(ToggleBig::new(ids.clone()).apply(&mut beatmap, &mut objects).is_err());
+        assert_eq!(variants(&objects), before);
+
+        ToggleBig::new(ids[.. 2].to_vec()).apply(&mut beatmap, &mut objects).unwrap();
+        assert!(objects.get(ids[1]).unwrap().variant().unwrap().is_big());
+    }
+}
\ No newline at end of file
@@ -0,0 +1,150 @@
+use color_eyre::eyre::{Report, Result, eyre};
+
+use crate::beatmap::{beatmap::Beatmap, objects::Objects, timing::TimingPoint};
+
+use super::Command;
+
+fn missing(index: usize) -> Report {
+    return eyre!("There's no timing point at index {}", index);
+}
+
+pub struct AddTimingPoint {
+    point: TimingPoint,
+    index: usize,
+}
+
+impl AddTimingPoint {
+    pub fn new(point: TimingPoint) -> Self {
+        return Self { point, index: 0 };
+    }
+}
+
+impl Command for AddTimingPoint {
+    fn name(&self) -> &'static str { "Add timing point" }
+
+    fn apply(&mut self, beatmap: &mut Beatmap, _: &mut Objects) -> Result<()> {
+        self.index = beatmap.timing.insert(self.point.clone());
+        return Ok(());
+    }
+
+    fn revert(&mut self, beatmap: &mut Beatmap, _: &mut Objects) -> Result<()> {
+        beatmap.timing.remove(self.index).ok_or_else(|| missing(self.index))?;
+        return Ok(());
+    }
+}
+
+pub struct RemoveTimingPoint {
+    index: usize,
+    point: Option<TimingPoint>,
+}
+
+impl RemoveTimingPoint {
+    pub fn new(index: usize) -> Self {
+        return Self { index, point: None };
+    }
+}
+
+impl Command for RemoveTimingPoint {
+    fn name(&self) -> &'static str { "Remove timing point" }
+
+    fn apply(&mut self, beatmap: &mut Beatmap, _: &mut Objects) -> Result<()> {
+        self.point = Some(beatmap.timing.remove(self.index).ok_or_else(|| missing(self.index))?);
+        return Ok(());
+    }
+
+    fn revert(&mut self, beatmap: &mut Beatmap, _: &mut Objects) -> Result<()> {
+        if let Some(point) = self.point.take() {
+            self.index = beatmap.timing.insert(point);
+        }
+
+        return Ok(());
+    }
+}
+
+// Swaps the point at `index` with the stored one, so it's its own inverse
+pub struct ChangeTimingPoint {
+    index: usize,
+    point: TimingPoint,
+}
+
+impl ChangeTimingPoint {
+    pub fn new(index: usize, point: TimingPoint) -> Self {
+        return Self { index, point };
+    }
+}
+
+impl Command for ChangeTimingPoint {
+    fn name(&self) -> &'static str { "Change timing point" }
+
+    fn apply(&mut self, beatmap: &mut Beatmap, _: &mut Objects) -> Result<()> {
+        let (old, index) = beatmap.timing.replace(self.index, self.point.clone()).ok_or_else(|| missing(self.index))?;
+        self.point = old;
+        self.index = index;
+        return Ok(());
+    }
+
+    fn revert(&mut self, beatmap: &mut Beatmap, objects: &mut Objects) -> Result<()> {
+        return self.apply(beatmap, objects);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::command::history::History;
+
+    fn beatmap() -> (Beatmap, Objects) {
+        let mut beatmap = Beatmap::default();
+        beatmap.timing.insert(TimingPoint::uninherited(0.0, 120.0, 4));
+        beatmap.timing.insert(TimingPoint::uninherited(1000.0, 180.0, 3));
+        return (beatmap, Objects::default());
+    }
+
+    #[test]
+    fn bad_index_is_an_error() {
+        let (mut beatmap, mut objects) = beatmap();
+        assert!(RemoveTimingPoint::new(2).apply(&mut beatmap, &mut objects).is_err());
+        assert!(ChangeTimingPoint::new(2, TimingPoint::default()).apply(&mut beatmap, &mut objects).is_err());
+        assert_eq!(beatmap.timing.points().len(), 2);
+    }
+
+    #[test]
+    fn failed_command_isnt_recorded() {
+        let (mut beatmap, mut objects) = beatmap();
+        let mut history = History::new(10);
+        assert!(history.execute(Box::new(RemoveTimingPoint::new(5)), &mut beatmap, &mut objects).is_err());
+        assert!(!history.can_undo());
+        assert!(!history.is_dirty());
+    }
+
+    #[test]
+    fn failed_undo_stays_undoable() {
+        let (mut beatmap, mut objects) = beatmap();
+        let mut history = History::new(10);
+        history.execute(Box::new(AddTimingPoint::new(TimingPoint::uninherited(2000.0, 90.0, 4))), &mut beatmap, &mut objects).unwrap();
+
+        // Points changed behind the history's back
+        beatmap.timing.remove(2);
+        beatmap.timing.remove(1);
+        assert!(history.undo(&mut beatmap, &mut objects).is_err());
+        assert!(history.can_undo());
+        assert!(!history.can_redo());
+    }
+
+    #[test]
+    fn change_is_undone() {
+        let (mut beatmap, mut objects) = beatmap();
+        let mut history = History::new(10);
+        let original = beatmap.timing.points().to_vec();
+
+        // Moved past the other point, so its index changes
+        history.execute(Box::new(ChangeTimingPoint::new(0, TimingPoint::uninherited(1500.0, 150.0, 4))), &mut beatmap, &mut objects).unwrap();
+        assert_eq!(beatmap.timing.points()[1].time, 1500.0);
+
+        history.undo(&mut beatmap, &mut objects).unwrap();
+        assert_eq!(beatmap.timing.points(), original.as_slice());
+
+        history.redo(&mut beatmap, &mut objects).unwrap();
+        assert_eq!(beatmap.timing.points()[1].time, 1500.0);
+    }
+}
\ No newline at end of file
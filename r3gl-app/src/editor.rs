@@ -1,53 +1,239 @@
-use std::path::Path;
+use std::path::{Path, PathBuf};
 
+use color_eyre::eyre::{Result, WrapErr, eyre};
 use instant::Duration;
-use r3gl_audio::{Audio, AudioData};
+use log::warn;
+use r3gl_audio::{Analysis, Audio, AudioFile, Bus, BusMix, CpalBackend, NullBackend, Drive, Sound, Summary, Track, TrackMix};
 use wcore::clock::{SyncClock, Clock};
 
-use crate::{beatmap::{Time, beatmap::Beatmap, component::HitObject}, project::project_manager::ProjectManager};
+use crate::{beatmap::{Time, beatmap::Beatmap, component::HitObject, objects::{Objects, ObjectId}, timing::{Timing, TimingPoint, ControlPoint, BeatPosition}}, project::{project_manager::ProjectManager, samples::{self, SampleBank}, mix::{ProjectMix, StemSettings}}, settings::AudioOutput, tool::Tool, command::{Command, history::History, object::{self, AddObjects, RemoveObjects, MoveObjects, SwitchColor, ToggleBig}}};
+
+pub const SNAP_DIVISORS: [u32; 8] = [1, 2, 3, 4, 6, 8, 12, 16];
+pub const HISTORY_LIMIT: usize = 256;
+pub const PLAYBACK_RATES: [f64; 7] = [0.25, 0.5, 0.75, 1.0, 1.25, 1.5, 2.0];
+
+// How far ahead of the playhead hitsounds are handed to the mixer
+pub const HITSOUND_LOOKAHEAD: u32 = 100;
+
+// Metronome clicks, downbeats are an octave higher
+const DOWNBEAT_FREQUENCY: f32 = 1760.0;
+const BEAT_FREQUENCY: f32 = 880.0;
+const CLICK_LENGTH: u64 = 40; // ms
 
 pub struct Editor {
-    pub beatmap: Option<Beatmap>,
-    pub hitobjects: Option<Vec<Box<dyn HitObject>>>,
+    beatmap: Option<Beatmap>,
+    hitobjects: Option<Objects>,
+    history: History,
+
+    selection: Vec<ObjectId>,
+
+    // Times are relative to the first object, kept when switching projects
+    clipboard: Vec<Box<dyn HitObject>>,
+
+    // Snapping
+    snap_divisor: u32,
+    snap_enabled: bool,
+
+    // Placement
+    tool: Tool,
+    finisher: bool,
     
     // Audio/Time managment
     audio: Audio,
+    output: Option<AudioOutput>,
     clock: SyncClock,
+
+    // Hitsounds
+    samples: SampleBank,
+    scheduled_until: Option<Time>,
+
+    // Metronome, its volume is the metronome bus
+    metronome: bool,
+    clicks: [Sound; 2], // Downbeat, beat
+
+    // Stem files and the slots they're playing in
+    stems: Vec<(PathBuf, usize)>,
+
+    // Waveform
+    analysis: Option<Analysis>,
+    summary: Option<Summary>,
 }
 
 impl Editor {
-    pub fn new() -> Self {
+    pub fn new(output: Option<AudioOutput>) -> Self {
+        // Keep working without a sound card, just silently
+        let audio = open_output(output.as_ref()).and_then(Audio::with_backend).unwrap_or_else(|err| {
+            warn!("No audio output, falling back to a null backend: {:#}", err);
+            Audio::with_backend(NullBackend::new(48000, 2, Drive::Wall)).unwrap()
+        });
+
+        let mut editor = Self::with_audio(audio);
+        editor.output = output;
+        return editor;
+    }
+
+    pub fn with_audio(audio: Audio) -> Self {
+        let clicks = load_clicks(&audio);
         return Self {
             beatmap: None,
             hitobjects: None,
+            history: History::new(HISTORY_LIMIT),
 
-            audio: Audio::new().unwrap(),
+            selection: vec![],
+            clipboard: vec![],
+
+            snap_divisor: 4,
+            snap_enabled: true,
+
+            tool: Tool::Select,
+            finisher: false,
+
+            audio: audio,
+            output: None,
             clock: SyncClock::new(),
+
+            samples: SampleBank::new(),
+            scheduled_until: None,
+
+            metronome: false,
+            clicks: clicks,
+
+            stems: vec![],
+
+            analysis: None,
+            summary: None,
         };
     }
 
+    // Hitsounds missing from the beatmap folder are taken from here, then generated
+    pub fn set_skin_dir(&mut self, skin: Option<PathBuf>) {
+        self.samples.set_skin_dir(skin);
+    }
+
+    // Called every frame
+    pub fn update(&mut self) {
+        // Unplugged, carry on with whatever is there now
+        if self.audio.is_device_lost() {
+            warn!("Audio device lost, reopening the output");
+            let output = self.output.clone();
+            if let Err(err) = self.set_output(output) {
+                warn!("Failed to reopen the audio output: {:#}", err);
+            }
+        }
+
+        // Audio only moves a callback at a time, the clock fills in between and follows it
+        if let Some((time, heard)) = self.audio.timestamp() {
+            self.clock.sync(time.as_secs_f64() * 1000.0, heard);
+        }
+
+        self.schedule_hitsounds();
+
+        if let Some(result) = self.analysis.as_ref().and_then(Analysis::try_take) {
+            self.analysis = None;
+            match result {
+                Ok(summary) => self.summary = Some(summary),
+                Err(err) => warn!("Failed to analyze audio: {:#}", err),
+            }
+        }
+    }
+
+    // Waveform and spectrogram of the song, `None` until they're computed
+    pub fn summary(&self) -> Option<&Summary> {
+        return self.summary.as_ref();
+    }
+
+    // Audio output
+    pub fn output(&self) -> Option<&AudioOutput> {
+        return self.output.as_ref();
+    }
+
+    // `None` is the default device. Always ends up with some output, the error is only for reporting
+    pub fn set_output(&mut self, output: Option<AudioOutput>) -> Result<()> {
+        self.output = output;
+        let result = open_output(self.output.as_ref()).and_then(|backend| self.audio.set_backend(backend));
+        if result.is_err() {
+            self.audio.set_backend(NullBackend::new(48000, 2, Drive::Wall))?;
+        }
+
+        // Sounds were converted for the old output
+        self.samples.clear_cache();
+        self.clicks = load_clicks(&self.audio);
+        self.preload_hitsounds();
+        self.reset_hitsounds();
+        return result;
+    }
+
     // Project Management
-    pub fn open_project(&mut self, path: impl AsRef<Path>, projects: &mut ProjectManager) {
+    pub fn open_project(&mut self, path: impl AsRef<Path>, projects: &mut ProjectManager) -> Result<()> {
         // Parse beatmap
-        let (beatmap, game_data) = projects.open(&path);
+        let (beatmap, game_data, project) = ProjectManager::load(&path)?;
         
-        // Load audio
-        let mp3 = path.as_ref().parent().unwrap().join(&beatmap.audio);
-        self.audio.play(&AudioData::from_file(mp3).unwrap()).unwrap();
+        // Load audio, the open project stays as it is if that fails
+        let mp3 = path.as_ref().parent().unwrap().join(&beatmap.general.audio_filename);
+        let file = AudioFile::open(&mp3)
+            .and_then(|file| self.audio.play(&file).map(|_| file))
+            .wrap_err_with(|| format!("Failed to load {:?}", &mp3))?;
+        projects.set_current(project);
+
+        // Playing may have switched the output rate
+        self.clicks = load_clicks(&self.audio);
+
+        // Stems and the mix were saved with the project
+        let mix = projects.current.as_ref().map_or(ProjectMix::default(), |project| project.mix.clone());
+        self.load_mix(&mix);
+
+        // Waveform takes a while, the timeline shows it once it's there
+        self.summary = None;
+        self.analysis = Some(Analysis::start(&file, true));
         
         // Update clock data
         self.clock.set_time(0);
         self.clock.set_paused(true, 0);
         self.clock.set_length(self.audio.length().as_millis() as u32);
 
+        // Pick up the divisor the map was saved with
+        if SNAP_DIVISORS.contains(&beatmap.editor.beat_divisor) {
+            self.snap_divisor = beatmap.editor.beat_divisor;
+        }
+
         // Set as current
         self.beatmap = Some(beatmap);
         self.hitobjects = Some(game_data);
+        self.history.clear();
+        self.selection.clear();
+
+        self.samples.set_beatmap_dir(path.as_ref().parent().map(Path::to_path_buf));
+        self.preload_hitsounds();
+
+        return Ok(());
+    }
+    pub fn save_project(&mut self, projects: &mut ProjectManager) -> Result<()> {
+        if let (Some(beatmap), Some(objects)) = (&self.beatmap, &self.hitobjects) {
+            projects.save(beatmap, objects)?;
+            self.history.mark_saved();
+        }
+
+        return Ok(());
+    }
+    pub fn save_project_as(&mut self, path: impl AsRef<Path>, projects: &mut ProjectManager) -> Result<()> {
+        if let (Some(beatmap), Some(objects)) = (&self.beatmap, &self.hitobjects) {
+            projects.save_as(path, beatmap, objects)?;
+            self.history.mark_saved();
+        }
+
+        return Ok(());
     }
     pub fn close_project(&mut self, projects: &mut ProjectManager) {
         projects.current = None;
         self.hitobjects = None;
         self.beatmap = None;
+        self.history.clear();
+        self.selection.clear();
+        self.samples.set_beatmap_dir(None);
+        self.reset_hitsounds();
+        self.clear_stems();
+        self.analysis = None;
+        self.summary = None;
 
         let time = self.audio.get_time();
         self.clock.set_paused(true, time.as_millis() as u32);
@@ -56,11 +242,14 @@ impl Editor {
         self.audio.set_time(Duration::ZERO);
     }
 
-    // Time
+    // Time. Pausing keeps what's on screen, the audio is ahead of it by the output latency
+    // and resuming from there lines up again on its own
     pub fn toggle_paused(&mut self) {
         let time = self.audio.get_time();
-        self.clock.toggle_paused(time.as_millis() as u32);
+        let shown = self.clock.get_time();
+        self.clock.toggle_paused(shown);
         self.audio.pause();
+        self.reset_hitsounds();
 
         if time.as_millis() as u32 >= self.clock.get_length() {
             self.clock.set_time(0);
@@ -68,9 +257,10 @@ impl Editor {
         }
     }
     pub fn set_paused(&mut self, value: bool) {
-        let time = self.audio.get_time();
-        self.clock.set_paused(value, time.as_millis() as u32);
+        let shown = self.clock.get_time();
+        self.clock.set_paused(value, shown);
         self.audio.set_paused(value);
+        self.reset_hitsounds();
     }
     pub fn is_paused(&self) -> bool {
         return self.clock.is_paused();
@@ -80,12 +270,486 @@ impl Editor {
         let time = Duration::from_millis(time as u64);
         self.clock.set_time(time.as_millis() as u32);
         self.audio.set_time(time);
+        self.reset_hitsounds();
     }
     pub fn get_time(&mut self) -> Time {
         return Time::from_ms(self.clock.get_time());
     }
 
+    // What the output last rendered, ahead of `get_time` by the latency
+    pub fn audio_time(&self) -> Duration {
+        return self.audio.get_time();
+    }
+
     pub fn get_length(&self) -> u32 {
         return self.clock.get_length();
     }
+
+    // Global audio offset in ms, positive when the sound is heard later than it should be
+    pub fn audio_offset(&self) -> i32 {
+        return self.clock.get_offset();
+    }
+    pub fn set_audio_offset(&mut self, offset: i32) {
+        self.clock.set_offset(offset);
+    }
+
+    // Playback rate
+    pub fn get_rate(&self) -> f64 {
+        return self.audio.get_rate();
+    }
+    pub fn set_rate(&mut self, rate: f64) -> Result<()> {
+        let shown = self.clock.get_time();
+        self.audio.set_rate(rate)?;
+        self.clock.set_rate(self.audio.get_rate(), shown);
+        self.reset_hitsounds();
+        return Ok(());
+    }
+
+    pub fn is_preserving_pitch(&self) -> bool {
+        return self.audio.is_preserving_pitch();
+    }
+    pub fn set_preserving_pitch(&mut self, value: bool) -> Result<()> {
+        return self.audio.set_preserving_pitch(value);
+    }
+
+    // Hitsounds
+    fn schedule_hitsounds(&mut self) {
+        if self.clock.is_paused() {
+            return;
+        }
+
+        // Scheduled from what the callback rendered last, the clock lags behind it by the latency
+        let now = Time::from_ms(self.audio.get_time().as_millis() as u32);
+        let from = self.scheduled_until.unwrap_or(now);
+        let until = Time::from_ms(now.as_ms() + HITSOUND_LOOKAHEAD).max(from);
+        if let (Some(beatmap), Some(objects)) = (&self.beatmap, &self.hitobjects) {
+            let range = from.as_ms() as f64 .. until.as_ms() as f64;
+            for (_, object) in objects.overlapping(from, until) {
+                for hit in samples::object_hits(beatmap, object).into_iter().filter(|hit| range.contains(&hit.time)) {
+                    let (sounds, volume) = self.samples.hit_sounds(&self.audio, beatmap, object, hit);
+                    for sound in &sounds {
+                        self.audio.schedule(sound, Duration::from_secs_f64(hit.time / 1000.0), volume, Bus::Hitsounds);
+                    }
+                }
+            }
+        }
+
+        if self.metronome {
+            self.schedule_metronome(from, until);
+        }
+
+        self.scheduled_until = Some(until);
+    }
+
+    // A click on every beat in `from .. until`, accented on the first beat of a measure
+    fn schedule_metronome(&self, from: Time, until: Time) {
+        let timing = match self.timing() {
+            Some(timing) if !timing.is_empty() => timing,
+            _ => return,
+        };
+
+        // Ticks fall between milliseconds, they're scheduled at their exact time
+        let (from, until) = (from.as_ms() as f64, until.as_ms() as f64);
+        let mut tick = timing.tick_after(from - 1.0, 1, 1);
+        while let Some(time) = tick.filter(|time| *time < until) {
+            if time >= from {
+                let downbeat = match (timing.uninherited_at(time), timing.beat_at(time)) {
+                    (Some(point), Some(beat)) => beat.index.rem_euclid(point.meter.max(1) as i64) == 0,
+                    _ => false,
+                };
+
+                let sound = if downbeat { &self.clicks[0] } else { &self.clicks[1] };
+                self.audio.schedule(sound, Duration::from_secs_f64(time / 1000.0), 1.0, Bus::Metronome);
+            }
+
+            tick = timing.tick_after(time, 1, 1);
+        }
+    }
+
+    // Loads hitsounds up front, so playback doesn't hit the disk
+    fn preload_hitsounds(&mut self) {
+        if let (Some(beatmap), Some(objects)) = (&self.beatmap, &self.hitobjects) {
+            for (_, object) in objects.iter() {
+                for hit in samples::object_hits(beatmap, object) {
+                    self.samples.hit_sounds(&self.audio, beatmap, object, hit);
+                }
+            }
+        }
+    }
+
+    // Anything that moves the playhead other than playback has to reschedule
+    fn reset_hitsounds(&mut self) {
+        self.audio.clear_scheduled();
+        self.scheduled_until = None;
+    }
+
+    // Metronome
+    pub fn is_metronome(&self) -> bool {
+        return self.metronome;
+    }
+    pub fn set_metronome(&mut self, value: bool) {
+        self.metronome = value;
+        self.reset_hitsounds();
+    }
+
+    // Mixing. Tracks belong to the project, buses and the master volume to the settings
+    pub fn stems(&self) -> impl Iterator<Item = (Track, &Path)> + '_ {
+        return self.stems.iter().map(|(path, slot)| (Track::Stem(*slot), path.as_path()));
+    }
+    pub fn add_stem(&mut self, path: impl AsRef<Path>, projects: &mut ProjectManager) -> Result<()> {
+        self.open_stem(path.as_ref(), TrackMix::default())?;
+        self.sync_mix(projects);
+        return Ok(());
+    }
+    pub fn remove_stem(&mut self, track: Track, projects: &mut ProjectManager) {
+        self.stems.retain(|(_, slot)| {
+            if Track::Stem(*slot) != track {
+                return true;
+            }
+
+            self.audio.remove_stem(*slot);
+            return false;
+        });
+
+        self.sync_mix(projects);
+    }
+
+    pub fn track_mix(&self, track: Track) -> TrackMix {
+        return self.audio.get_mix(track);
+    }
+    pub fn set_track_mix(&mut self, track: Track, mix: TrackMix, projects: &mut ProjectManager) {
+        self.audio.set_mix(track, mix);
+        self.sync_mix(projects);
+    }
+
+    pub fn bus(&self, bus: Bus) -> BusMix {
+        return self.audio.get_bus(bus);
+    }
+    pub fn set_bus(&mut self, bus: Bus, mix: BusMix) {
+        self.audio.set_bus(bus, mix);
+    }
+
+    pub fn get_volume(&self) -> f32 {
+        return self.audio.get_volume();
+    }
+    pub fn set_volume(&mut self, volume: f32) {
+        self.audio.set_volume(volume);
+    }
+
+    fn open_stem(&mut self, path: &Path, mix: TrackMix) -> Result<()> {
+        let file = AudioFile::open(path).wrap_err_with(|| format!("Failed to load {:?}", path))?;
+        let slot = self.audio.add_stem(&file)?;
+        self.audio.set_mix(Track::Stem(slot), mix);
+        self.stems.push((path.to_path_buf(), slot));
+        return Ok(());
+    }
+
+    // Missing stems are left out, the project still opens without them
+    fn load_mix(&mut self, mix: &ProjectMix) {
+        self.clear_stems();
+        self.audio.set_mix(Track::Song, mix.song.into());
+        for stem in &mix.stems {
+            if let Err(err) = self.open_stem(&stem.path, stem.mix.into()) {
+                warn!("{:#}", err);
+            }
+        }
+    }
+
+    fn sync_mix(&self, projects: &mut ProjectManager) {
+        projects.set_mix(ProjectMix {
+            song: self.audio.get_mix(Track::Song).into(),
+            stems: self.stems.iter().map(|(path, slot)| StemSettings {
+                path: path.clone(),
+                mix: self.audio.get_mix(Track::Stem(*slot)).into(),
+            }).collect(),
+        });
+    }
+
+    fn clear_stems(&mut self) {
+        for (_, slot) in self.stems.drain(..) {
+            self.audio.remove_stem(slot);
+        }
+
+        self.audio.set_mix(Track::Song, TrackMix::default());
+    }
+
+    // Timing
+    pub fn timing(&self) -> Option<&Timing> {
+        return self.beatmap.as_ref().map(|beatmap| &beatmap.timing);
+    }
+    pub fn timing_point_at(&self, time: Time) -> Option<&TimingPoint> {
+        return self.timing()?.uninherited_at(time.as_ms() as f64);
+    }
+    pub fn control_point_at(&self, time: Time) -> Option<ControlPoint> {
+        return Some(self.timing()?.control_at(time.as_ms() as f64));
+    }
+    pub fn beat_at(&self, time: Time) -> Option<BeatPosition> {
+        return self.timing()?.beat_at(time.as_ms() as f64);
+    }
+    pub fn tick_after(&self, time: Time, divisor: u32, n: u32) -> Option<Time> {
+        return self.timing()?.tick_after(time.as_ms() as f64, divisor, n).map(Time::from_ms_f64);
+    }
+    pub fn tick_before(&self, time: Time, divisor: u32, n: u32) -> Option<Time> {
+        return self.timing()?.tick_before(time.as_ms() as f64, divisor, n).map(Time::from_ms_f64);
+    }
+
+    // Snapping
+    pub fn snap_divisor(&self) -> u32 {
+        return self.snap_divisor;
+    }
+    // Only the editor's, the beatmap keeps whatever divisor it was saved with
+    pub fn set_snap_divisor(&mut self, divisor: u32) -> Result<()> {
+        if !SNAP_DIVISORS.contains(&divisor) {
+            return Err(eyre!("1/{} isn't a snap divisor", divisor));
+        }
+
+        self.snap_divisor = divisor;
+        return Ok(());
+    }
+
+    pub fn is_snapping(&self) -> bool {
+        return self.snap_enabled;
+    }
+    pub fn set_snapping(&mut self, value: bool) {
+        self.snap_enabled = value;
+    }
+    pub fn toggle_snapping(&mut self) {
+        self.snap_enabled = !self.snap_enabled;
+    }
+
+    // Every tool that changes `TimeComponent` should go through this
+    pub fn snap(&self, time: Time) -> Time {
+        if !self.snap_enabled {
+            return time;
+        }
+
+        return self.timing()
+            .and_then(|timing| timing.snap(time.as_ms() as f64, self.snap_divisor))
+            .map(Time::from_ms_f64)
+            .unwrap_or(time);
+    }
+
+    // Tools
+    pub fn tool(&self) -> Tool {
+        return self.tool;
+    }
+    pub fn set_tool(&mut self, tool: Tool) {
+        self.tool = tool;
+    }
+
+    pub fn is_finisher(&self) -> bool {
+        return self.finisher;
+    }
+    pub fn set_finisher(&mut self, value: bool) {
+        self.finisher = value;
+    }
+    pub fn toggle_finisher(&mut self) {
+        self.finisher = !self.finisher;
+    }
+
+    // Editing
+    pub fn beatmap(&self) -> Option<&Beatmap> {
+        return self.beatmap.as_ref();
+    }
+    pub fn hitobjects(&self) -> Option<&Objects> {
+        return self.hitobjects.as_ref();
+    }
+
+    // Every change to the beatmap or its objects should go through this to be undoable
+    pub fn execute(&mut self, command: impl Command + 'static) -> Result<()> {
+        if let (Some(beatmap), Some(objects)) = (&mut self.beatmap, &mut self.hitobjects) {
+            let command = self.history.execute(Box::new(command), beatmap, objects)?;
+            if let Some(selection) = command.applied_selection() {
+                self.selection = selection;
+            }
+        }
+
+        return Ok(());
+    }
+    pub fn undo(&mut self) -> Result<bool> {
+        if let (Some(beatmap), Some(objects)) = (&mut self.beatmap, &mut self.hitobjects) {
+            if let Some(command) = self.history.undo(beatmap, objects)? {
+                if let Some(selection) = command.reverted_selection() {
+                    self.selection = selection;
+                }
+
+                self.selection.retain(|id| objects.contains(*id));
+                return Ok(true);
+            }
+        }
+
+        return Ok(false);
+    }
+    pub fn redo(&mut self) -> Result<bool> {
+        if let (Some(beatmap), Some(objects)) = (&mut self.beatmap, &mut self.hitobjects) {
+            if let Some(command) = self.history.redo(beatmap, objects)? {
+                if let Some(selection) = command.applied_selection() {
+                    self.selection = selection;
+                }
+
+                self.selection.retain(|id| objects.contains(*id));
+                return Ok(true);
+            }
+        }
+
+        return Ok(false);
+    }
+
+    pub fn history(&self) -> &History {
+        return &self.history;
+    }
+    pub fn is_dirty(&self) -> bool {
+        return self.history.is_dirty();
+    }
+
+    // Selection
+    pub fn selection(&self) -> &[ObjectId] {
+        return &self.selection;
+    }
+    pub fn is_selected(&self, id: ObjectId) -> bool {
+        return self.selection.contains(&id);
+    }
+    pub fn set_selection(&mut self, selection: Vec<ObjectId>) {
+        self.selection = selection;
+    }
+    pub fn toggle_selected(&mut self, id: ObjectId) {
+        match self.selection.iter().position(|x| *x == id) {
+            Some(position) => { self.selection.remove(position); }
+            None => self.selection.push(id),
+        }
+    }
+    pub fn clear_selection(&mut self) {
+        self.selection.clear();
+    }
+
+    pub fn delete_selection(&mut self) -> Result<()> {
+        if !self.selection.is_empty() {
+            self.execute(RemoveObjects::new(self.selection.clone()))?;
+        }
+
+        return Ok(());
+    }
+    // Selections of only drumrolls or dendens would leave an undo step that does nothing
+    pub fn switch_selection_color(&mut self) -> Result<()> {
+        let ids = self.selection_where(object::has_color);
+        if !ids.is_empty() {
+            self.execute(SwitchColor::new(ids))?;
+        }
+
+        return Ok(());
+    }
+    pub fn toggle_selection_big(&mut self) -> Result<()> {
+        let ids = self.selection_where(object::can_be_big);
+        if !ids.is_empty() {
+            self.execute(ToggleBig::new(ids))?;
+        }
+
+        return Ok(());
+    }
+
+    // Moves the selection by one tick of the current divisor, relative to its first object
+    pub fn nudge_selection(&mut self, forward: bool) -> Result<()> {
+        let first = self.selection_start();
+        if let Some(first) = first {
+            let tick = if forward { self.tick_after(first, self.snap_divisor, 1) }
+                       else       { self.tick_before(first, self.snap_divisor, 1) };
+
+            if let Some(tick) = tick {
+                let offset = tick.as_ms() as i64 - first.as_ms() as i64;
+                if offset != 0 {
+                    self.execute(MoveObjects::new(self.selection.clone(), offset))?;
+                }
+            }
+        }
+
+        return Ok(());
+    }
+
+    pub fn copy_selection(&mut self) {
+        if let (Some(objects), Some(first)) = (&self.hitobjects, self.selection_start()) {
+            let selection = &self.selection;
+            self.clipboard = objects.iter().filter(|(id, _)| selection.contains(id)).map(|(_, object)| {
+                let mut object = object.boxed_clone();
+                if let Some(time) = object.time_mut() {
+                    time.0 = Time::from_ms(time.0.as_ms() - first.as_ms());
+                }
+
+                object
+            }).collect();
+        }
+    }
+    pub fn has_clipboard(&self) -> bool {
+        return !self.clipboard.is_empty();
+    }
+    pub fn cut_selection(&mut self) -> Result<()> {
+        self.copy_selection();
+        return self.delete_selection();
+    }
+
+    // Pasted objects start at the playhead
+    pub fn paste(&mut self) -> Result<()> {
+        if self.clipboard.is_empty() || self.hitobjects.is_none() {
+            return Ok(());
+        }
+
+        let time = self.get_time();
+        let time = self.snap(time);
+        let objects = self.clipboard.iter().map(|object| {
+            let mut object = object.boxed_clone();
+            if let Some(object_time) = object.time_mut() {
+                object_time.0 = Time::from_ms(object_time.0.as_ms() + time.as_ms());
+            }
+
+            object
+        }).collect();
+
+        return self.execute(AddObjects::new(objects));
+    }
+
+    fn selection_where(&self, filter: impl Fn(&dyn HitObject) -> bool) -> Vec<ObjectId> {
+        let objects = match &self.hitobjects {
+            Some(objects) => objects,
+            None => return vec![],
+        };
+
+        return self.selection.iter().copied().filter(|id| objects.get(*id).map_or(false, &filter)).collect();
+    }
+
+    // Earliest selected object
+    pub fn selection_start(&self) -> Option<Time> {
+        let objects = self.hitobjects.as_ref()?;
+        return self.selection.iter().filter_map(|id| objects.get(*id)?.time()).map(|time| time.0).min();
+    }
+}
+
+// Picked device if it's still around, the default one otherwise
+fn open_output(output: Option<&AudioOutput>) -> Result<CpalBackend> {
+    if let Some(output) = output {
+        match CpalBackend::open(&output.host, &output.device) {
+            Ok(backend) => return Ok(backend),
+            Err(err) => warn!("Failed to open {:?}, using the default device: {:#}", output.device, err),
+        }
+    }
+
+    return CpalBackend::new();
+}
+
+fn load_clicks(audio: &Audio) -> [Sound; 2] {
+    let length = Duration::from_millis(CLICK_LENGTH);
+    return [audio.tone(DOWNBEAT_FREQUENCY, length), audio.tone(BEAT_FREQUENCY, length)];
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rejects_unknown_snap_divisors() {
+        let mut editor = Editor::with_audio(Audio::with_backend(NullBackend::new(48000, 2, Drive::Manual)).unwrap());
+        editor.set_snap_divisor(6).unwrap();
+
+        assert!(editor.set_snap_divisor(0).is_err());
+        assert!(editor.set_snap_divisor(5).is_err());
+        assert_eq!(editor.snap_divisor(), 6);
+    }
 }
\ No newline at end of file
@@ -66,7 +66,7 @@ impl Instance<CircleRaw> for Circle {
     fn to_raw(&self) -> CircleRaw {
         return CircleRaw {
             color: self.color.into(),
-            finisher: if self.finisher { 1 } else { 0 },
+            finisher: u32::from(self.finisher),
             model: (cgmath::Matrix4::from_translation(self.position)
                   * cgmath::Matrix4::from_nonuniform_scale(self.scale.x, self.scale.y, self.scale.z)
                   * cgmath::Matrix4::from(self.rotation)).into(),
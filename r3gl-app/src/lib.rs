@@ -1,3 +1,5 @@
+#![allow(clippy::needless_return, clippy::redundant_field_names, clippy::new_without_default, clippy::module_inception)]
+
 use std::{path::Path, fs::{OpenOptions, self}, io::Write, env};
 
 use log::{error, warn};
@@ -13,6 +15,9 @@ pub mod graphics;
 pub mod identifier;
 pub mod store;
 pub mod unit;
+pub mod tool;
+pub mod command;
+pub mod settings;
 
 pub fn save<T: Serialize>(obj: &T, path: impl AsRef<Path>) {
     let path = path.as_ref();
@@ -20,7 +25,7 @@ pub fn save<T: Serialize>(obj: &T, path: impl AsRef<Path>) {
       .read(true)
       .write(true)
       .create(true)
-      .open(&path) {
+      .open(path) {
         if file.write_all(toml::to_string(&obj).unwrap().as_bytes()).is_err() {
             warn!("Failed to save {:?}", &path);
         }
@@ -0,0 +1,99 @@
+use std::{fs, path::{Path, PathBuf}};
+
+use color_eyre::eyre::{Result, WrapErr};
+use r3gl_audio::TrackMix;
+use serde::{Deserialize, Serialize};
+
+// Tracks of one project, saved next to its .osu
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq)]
+pub struct ProjectMix {
+    #[serde(default)]
+    pub song: TrackSettings,
+
+    #[serde(default)]
+    pub stems: Vec<StemSettings>,
+}
+
+impl ProjectMix {
+    // `map.osu` has its mix in `map.mix.toml`, every difficulty gets its own
+    pub fn path(project: &Path) -> PathBuf {
+        return project.with_extension("mix.toml");
+    }
+
+    // `None` if the project never had one saved
+    pub fn load(project: &Path) -> Result<Option<Self>> {
+        let path = Self::path(project);
+        if !path.is_file() {
+            return Ok(None);
+        }
+
+        let data = fs::read_to_string(&path).wrap_err_with(|| format!("Failed to read {:?}", &path))?;
+        let mut mix: Self = toml::from_str(&data).wrap_err_with(|| format!("Failed to parse {:?}", &path))?;
+        if let Some(dir) = project.parent() {
+            mix.stems.iter_mut().for_each(|stem| stem.path = dir.join(&stem.path));
+        }
+
+        return Ok(Some(mix));
+    }
+
+    // Stems in the project folder are saved relative to it, so the folder can be moved
+    pub fn save(&self, project: &Path) -> Result<()> {
+        let mut mix = self.clone();
+        if let Some(dir) = project.parent() {
+            for stem in &mut mix.stems {
+                if let Ok(relative) = stem.path.strip_prefix(dir) {
+                    stem.path = relative.to_owned();
+                }
+            }
+        }
+
+        let path = Self::path(project);
+        fs::write(&path, toml::to_string(&mix)?).wrap_err_with(|| format!("Failed to write {:?}", &path))?;
+        return Ok(());
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct StemSettings {
+    pub path: PathBuf,
+
+    #[serde(default)]
+    pub mix: TrackSettings,
+}
+
+// Same as `TrackMix`, which doesn't know about serde
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+pub struct TrackSettings {
+    pub gain: f32,
+    pub pan: f32,
+    pub mute: bool,
+    pub solo: bool,
+}
+
+impl Default for TrackSettings {
+    fn default() -> Self {
+        return TrackMix::default().into();
+    }
+}
+
+impl From<TrackMix> for TrackSettings {
+    fn from(mix: TrackMix) -> Self {
+        return Self {
+            gain: mix.gain,
+            pan: mix.pan,
+            mute: mix.mute,
+            solo: mix.solo,
+        };
+    }
+}
+
+impl From<TrackSettings> for TrackMix {
+    fn from(settings: TrackSettings) -> Self {
+        return Self {
+            gain: settings.gain,
+            pan: settings.pan,
+            mute: settings.mute,
+            solo: settings.solo,
+        };
+    }
+}
\ No newline at end of file
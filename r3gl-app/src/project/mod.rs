@@ -1,2 +1,4 @@
 pub mod project_manager;
-pub mod project;
\ No newline at end of file
+pub mod project;
+pub mod mix;
+pub mod samples;
\ No newline at end of file
@@ -1,12 +1,14 @@
 use std::path::{PathBuf, Path};
 use color_eyre::eyre::Result;
 
-use super::project_manager::ProjectInfo;
+use super::{project_manager::ProjectInfo, mix::ProjectMix};
 
 #[derive(Debug, Clone)]
 pub struct Project {
     pub(crate) path: PathBuf,
     pub(crate) name: String,
+    pub(crate) mix: ProjectMix,
+    pub(crate) saved_mix: ProjectMix,
 }
 
 impl Project {
@@ -14,6 +16,8 @@ impl Project {
         return Ok(Project {
             path: path.as_ref().to_owned(),
             name,
+            mix: ProjectMix::default(),
+            saved_mix: ProjectMix::default(),
         });
     }
 
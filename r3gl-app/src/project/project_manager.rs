@@ -1,10 +1,12 @@
 use std::{path::{PathBuf, Path}, fs};
 
+use color_eyre::eyre::{Report, Result, WrapErr};
+use log::warn;
 use serde::{Deserialize, Serialize};
 
-use crate::beatmap::{parser, beatmap::Beatmap, component::HitObject};
+use crate::beatmap::{parser, writer, beatmap::Beatmap, objects::Objects};
 
-use super::project::Project;
+use super::{project::Project, mix::ProjectMix};
 
 #[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct ProjectManager {
@@ -31,33 +33,146 @@ impl Default for ProjectManager {
 }
 
 impl ProjectManager {
-    pub fn open(&mut self, path: impl AsRef<Path>) -> (Beatmap, Vec<Box<dyn HitObject>>) {
-        // TODO: handle errors case properly
+    pub fn open(&mut self, path: impl AsRef<Path>) -> Result<(Beatmap, Objects)> {
+        let (beatmap, objects, project) = Self::load(path)?;
+        self.set_current(project);
+        return Ok((beatmap, objects));
+    }
+
+    // Reads a project without opening it, so nothing changes if something else about it fails
+    pub fn load(path: impl AsRef<Path>) -> Result<(Beatmap, Objects, Project)> {
         let path = path.as_ref();
-        let data = fs::read_to_string(&path).unwrap();
-        let (beatmap, objects) = parser::parse(&data);
-        let project = Project::from_path(path, format!("{} - {}", &beatmap.artist, &beatmap.title)).unwrap();
+        let data = fs::read_to_string(path).wrap_err_with(|| format!("Failed to read {:?}", &path))?;
+        let (beatmap, objects) = parser::parse(&data).wrap_err_with(|| format!("Failed to parse {:?}", &path))?;
+        let mut project = Project::from_path(path, format!("{} - {}", &beatmap.metadata.artist, &beatmap.metadata.title))?;
+
+        // A broken mix isn't worth failing the whole project over
+        project.mix = ProjectMix::load(path).unwrap_or_else(|err| {
+            warn!("{:#}", err);
+            None
+        }).unwrap_or_default();
+        project.saved_mix = project.mix.clone();
+
+        return Ok((beatmap, objects, project));
+    }
+
+    pub fn set_current(&mut self, project: Project) {
+        self.push_recent(&project);
+        self.current = Some(project);
+    }
+
+    pub fn save(&mut self, beatmap: &Beatmap, objects: &Objects) -> Result<()> {
+        let project = self.current.as_mut().ok_or_else(|| Report::msg("No project is open"))?;
+        fs::write(&project.path, writer::write(beatmap, objects)).wrap_err_with(|| format!("Failed to write {:?}", &project.path))?;
+        save_mix(project)?;
+
+        return Ok(());
+    }
 
+    pub fn save_as(&mut self, path: impl AsRef<Path>, beatmap: &Beatmap, objects: &Objects) -> Result<()> {
+        let path = path.as_ref();
+        fs::write(path, writer::write(beatmap, objects)).wrap_err_with(|| format!("Failed to write {:?}", &path))?;
+        let mut project = Project::from_path(path, format!("{} - {}", &beatmap.metadata.artist, &beatmap.metadata.title))?;
+        project.mix = self.current.as_ref().map_or(ProjectMix::default(), |project| project.mix.clone());
+        save_mix(&mut project)?;
+
+        self.push_recent(&project);
+        self.current = Some(project);
+
+        return Ok(());
+    }
+
+    // Saved along with the beatmap
+    pub fn set_mix(&mut self, mix: ProjectMix) {
+        if let Some(project) = &mut self.current {
+            project.mix = mix;
+        }
+    }
+
+    pub fn is_mix_dirty(&self) -> bool {
+        return self.current.as_ref().map_or(false, |project| project.mix != project.saved_mix);
+    }
 
+    fn push_recent(&mut self, project: &Project) {
+        let path = project.path.as_path();
         let recent = &mut self.recent;
         if recent.len() > 1 { // `split_at_mut()` panics otherwise
             let (first, rest) = recent.split_at_mut(1);
             let project_info = rest.iter_mut().find(|proj| proj.path == path);
             if let Some(project_info) = project_info {
                 std::mem::swap(&mut first[0], project_info);
-            } else {
-                if first[0].path != path {
-                    recent.insert(0, project.info());
-                }
-            }
-        } else {
-            if !recent.iter().any(|proj| proj.path == path) {
+            } else if first[0].path != path {
                 recent.insert(0, project.info());
             }
+        } else if !recent.iter().any(|proj| proj.path == path) {
+            recent.insert(0, project.info());
         }
+    }
+}
 
-        self.current = Some(project);
+// Projects that never touched the mixer don't get a file
+fn save_mix(project: &mut Project) -> Result<()> {
+    if project.mix != ProjectMix::default() || ProjectMix::path(&project.path).is_file() {
+        project.mix.save(&project.path)?;
+    }
+
+    project.saved_mix = project.mix.clone();
+    return Ok(());
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::project::mix::{StemSettings, TrackSettings};
+
+    const BEATMAP: &str = "osu file format v14
+
+[General]
+AudioFilename: song.mp3
+Mode: 1
+
+[Metadata]
+Title:Mix
+Artist:Test
+";
+
+    fn project(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("r3gl-app-projects-{}", std::process::id())).join(name);
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("map.osu");
+        fs::write(&path, BEATMAP).unwrap();
+        return path;
+    }
+
+    fn mix(stem: PathBuf) -> ProjectMix {
+        return ProjectMix {
+            song: TrackSettings { gain: 0.5, pan: -0.25, mute: false, solo: true },
+            stems: vec![StemSettings { path: stem, mix: TrackSettings { gain: 1.5, pan: 0.0, mute: true, solo: false } }],
+        };
+    }
+
+    #[test]
+    fn mix_is_saved_with_the_project() {
+        let path = project("saved");
+        let stem = path.parent().unwrap().join("stems").join("drums.ogg");
+
+        let mut projects = ProjectManager::default();
+        let (beatmap, objects) = projects.open(&path).unwrap();
+        assert!(!ProjectMix::path(&path).exists());
+
+        projects.set_mix(mix(stem.clone()));
+        assert!(projects.is_mix_dirty());
+        projects.save(&beatmap, &objects).unwrap();
+        assert!(!projects.is_mix_dirty());
+
+        // Stems inside the project folder don't depend on where it is
+        let saved = fs::read_to_string(ProjectMix::path(&path)).unwrap();
+        assert!(saved.contains("path = \"stems"), "{}", saved);
 
-        return (beatmap, objects);
+        // Another machine, nothing in its recent projects
+        let mut projects = ProjectManager::default();
+        projects.open(&path).unwrap();
+        assert_eq!(projects.current.as_ref().unwrap().mix, mix(stem));
+        assert!(!projects.is_mix_dirty());
     }
 }
\ No newline at end of file
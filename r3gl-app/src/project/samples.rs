@@ -0,0 +1,260 @@
+use std::{collections::HashMap, path::{Path, PathBuf}};
+
+use instant::Duration;
+use log::warn;
+use r3gl_audio::{Audio, AudioData, Sound};
+
+use crate::beatmap::{beatmap::Beatmap, timing::TimingPoint, component::{HitObject, sample::SampleSet, adapter::taiko::TaikoVariantAdapter}};
+
+const EXTENSIONS: [&str; 3] = ["wav", "ogg", "mp3"];
+
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Hash)]
+pub enum Hit {
+    Normal,
+    Whistle,
+    Finish,
+    Clap,
+}
+
+impl Hit {
+    pub fn name(&self) -> &'static str {
+        return match self {
+            Hit::Normal  => "hitnormal",
+            Hit::Whistle => "hitwhistle",
+            Hit::Finish  => "hitfinish",
+            Hit::Clap    => "hitclap",
+        };
+    }
+
+    // Built-in sound as frequency and length in ms, a low don, a rim knock and a long boom for the finisher
+    fn tone(&self) -> (f32, u64) {
+        return match self {
+            Hit::Normal  => (220.0, 120),
+            Hit::Whistle => (1760.0, 100),
+            Hit::Finish  => (110.0, 400),
+            Hit::Clap    => (880.0, 60),
+        };
+    }
+}
+
+#[derive(Clone, Debug, Eq, PartialEq, Hash)]
+enum SampleKey {
+    Hit(SampleSet, Hit, u32),
+    File(String),
+}
+
+// Resolves hitsounds like osu! does: beatmap folder, then the skin, then built-in defaults
+pub struct SampleBank {
+    beatmap: Option<PathBuf>,
+    skin: Option<PathBuf>,
+
+    // Failed lookups are cached too, so missing files aren't searched for every hit
+    cache: HashMap<SampleKey, Option<Sound>>,
+}
+
+impl SampleBank {
+    pub fn new() -> Self {
+        return Self {
+            beatmap: None,
+            skin: None,
+            cache: HashMap::new(),
+        };
+    }
+
+    pub fn set_beatmap_dir(&mut self, path: Option<PathBuf>) {
+        self.beatmap = path;
+        self.cache.clear();
+    }
+
+    pub fn set_skin_dir(&mut self, path: Option<PathBuf>) {
+        self.skin = path;
+        self.cache.clear();
+    }
+
+    // Sounds are only valid for the output they were loaded for
+    pub fn clear_cache(&mut self) {
+        self.cache.clear();
+    }
+
+    // Never misses, whatever isn't in a folder is generated
+    pub fn get(&mut self, audio: &Audio, set: SampleSet, hit: Hit, index: u32) -> Sound {
+        let key = SampleKey::Hit(set, hit, index);
+        if let Some(Some(sound)) = self.cache.get(&key) {
+            return sound.clone();
+        }
+
+        let sound = self.find(set, hit, index)
+            .and_then(|path| load(audio, &path))
+            .unwrap_or_else(|| {
+                let (frequency, length) = hit.tone();
+                audio.tone(frequency, Duration::from_millis(length))
+            });
+
+        self.cache.insert(key, Some(sound.clone()));
+        return sound;
+    }
+
+    // File `hit` is played from, `None` when it's the built-in one.
+    // Index 0 means the beatmap doesn't provide its own samples, 1 is the unnumbered file
+    pub fn find(&self, set: SampleSet, hit: Hit, index: u32) -> Option<PathBuf> {
+        let set = match set {
+            SampleSet::Auto | SampleSet::Normal => "normal",
+            SampleSet::Soft                     => "soft",
+            SampleSet::Drum                     => "drum",
+        };
+
+        let suffix = if index > 1 { index.to_string() } else { String::new() };
+        let beatmap_names = [format!("taiko-{}-{}{}", set, hit.name(), suffix), format!("{}-{}{}", set, hit.name(), suffix)];
+        let skin_names = [format!("taiko-{}-{}", set, hit.name()), format!("{}-{}", set, hit.name())];
+
+        let beatmap = self.beatmap.as_ref().filter(|_| index > 0);
+        return beatmap.and_then(|dir| find_first(dir, &beatmap_names))
+            .or_else(|| self.skin.as_ref().and_then(|dir| find_first(dir, &skin_names)));
+    }
+
+    // Custom per-object files only ever come from the beatmap folder
+    pub fn get_file(&mut self, audio: &Audio, filename: &str) -> Option<Sound> {
+        let key = SampleKey::File(filename.to_owned());
+        if let Some(sound) = self.cache.get(&key) {
+            return sound.clone();
+        }
+
+        let sound = self.beatmap.as_ref().and_then(|dir| load(audio, &dir.join(filename)));
+        self.cache.insert(key, sound.clone());
+        return sound;
+    }
+
+    // Everything `hit` plays, along with its volume
+    pub fn hit_sounds(&mut self, audio: &Audio, beatmap: &Beatmap, object: &dyn HitObject, hit: ObjectHit) -> (Vec<Sound>, f32) {
+        let control = beatmap.timing.control_at(hit.time);
+        let sample = object.sample().cloned().unwrap_or_default();
+        let volume = (if sample.volume > 0 { sample.volume } else { control.volume }) as f32 / 100.0;
+
+        if !sample.filename.is_empty() {
+            return (self.get_file(audio, &sample.filename).into_iter().collect(), volume);
+        }
+
+        // Auto inherits from the timing point, then from the beatmap
+        let normal_set = [sample.normal_set, control.sample_set, beatmap.general.sample_set]
+            .into_iter()
+            .find(|set| *set != SampleSet::Auto)
+            .unwrap_or(SampleSet::Normal);
+        let addition_set = if sample.addition_set != SampleSet::Auto { sample.addition_set } else { normal_set };
+        let index = if sample.index > 0 { sample.index } else { control.sample_index };
+
+        let mut sounds = vec![];
+        if hit.kat {
+            sounds.push(self.get(audio, addition_set, Hit::Clap, index));
+        } else {
+            sounds.push(self.get(audio, normal_set, Hit::Normal, index));
+        }
+
+        if hit.big {
+            sounds.push(self.get(audio, addition_set, Hit::Finish, index));
+        }
+
+        return (sounds, volume);
+    }
+}
+
+// A single sound an object makes
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct ObjectHit {
+    pub time: f64, // ms
+    pub kat: bool,
+    pub big: bool,
+}
+
+// Circles sound once, drumrolls on every tick with the finisher on the first one, dendens when they end
+pub fn object_hits(beatmap: &Beatmap, object: &dyn HitObject) -> Vec<ObjectHit> {
+    let time = match object.time() {
+        Some(time) => time.0.as_ms() as f64,
+        None => return vec![],
+    };
+
+    let variant = object.variant();
+    let kat = variant.map_or(false, |variant| variant.is_kat());
+    let big = variant.map_or(false, |variant| variant.is_big());
+    let duration = match object.duration() {
+        Some(duration) => duration.0.as_ms() as f64,
+        None => return vec![ObjectHit { time, kat, big }],
+    };
+
+    if variant.is_none() {
+        return vec![ObjectHit { time: time + duration, kat: false, big: false }];
+    }
+
+    // Same spacing as osu!, quarter beats unless the tick rate asks for thirds
+    let beat_length = beatmap.timing.uninherited_at(time).map_or(TimingPoint::default().beat_length, |point| point.beat_length);
+    let divisor = if beatmap.difficulty.slider_tick_rate == 3.0 { 3.0 } else { 4.0 };
+    let spacing = beat_length / divisor;
+    if spacing <= 0.0 {
+        return vec![ObjectHit { time, kat: false, big }];
+    }
+
+    // The last tick lands on the end, give or take half a tick
+    return (0 ..)
+        .map(|tick| time + tick as f64 * spacing)
+        .take_while(|tick| *tick < time + duration + spacing / 2.0)
+        .enumerate()
+        .map(|(i, tick)| ObjectHit { time: tick, kat: false, big: big && i == 0 })
+        .collect();
+}
+
+fn find_first(dir: &Path, names: &[String]) -> Option<PathBuf> {
+    return names.iter()
+        .flat_map(|name| EXTENSIONS.iter().map(move |extension| dir.join(format!("{}.{}", name, extension))))
+        .find(|path| path.is_file());
+}
+
+fn load(audio: &Audio, path: &Path) -> Option<Sound> {
+    let sound = AudioData::from_file(path).and_then(|data| audio.load_sound(&data));
+    if let Err(err) = &sound {
+        warn!("Failed to load {:?}: {:#}", path, err);
+    }
+
+    return sound.ok();
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use super::*;
+
+    fn folder(name: &str, files: &[&str]) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("r3gl-app-samples-{}", std::process::id())).join(name);
+        fs::create_dir_all(&dir).unwrap();
+        for file in files {
+            fs::write(dir.join(file), []).unwrap();
+        }
+
+        return dir;
+    }
+
+    #[test]
+    fn resolves_beatmap_then_skin_then_defaults() {
+        let beatmap = folder("beatmap", &["normal-hitnormal.wav", "normal-hitclap.wav", "taiko-normal-hitclap.wav", "soft-hitfinish3.ogg"]);
+        let skin = folder("skin", &["normal-hitnormal.wav", "normal-hitwhistle.wav", "drum-hitfinish.mp3", "soft-hitfinish.wav"]);
+
+        let mut bank = SampleBank::new();
+        bank.set_beatmap_dir(Some(beatmap.clone()));
+        bank.set_skin_dir(Some(skin.clone()));
+
+        // Beatmap first, taiko specific files before plain ones
+        assert_eq!(bank.find(SampleSet::Normal, Hit::Normal, 1), Some(beatmap.join("normal-hitnormal.wav")));
+        assert_eq!(bank.find(SampleSet::Normal, Hit::Clap, 1), Some(beatmap.join("taiko-normal-hitclap.wav")));
+        assert_eq!(bank.find(SampleSet::Soft, Hit::Finish, 3), Some(beatmap.join("soft-hitfinish3.ogg")));
+
+        // Index 0 skips the beatmap, skins aren't numbered
+        assert_eq!(bank.find(SampleSet::Normal, Hit::Normal, 0), Some(skin.join("normal-hitnormal.wav")));
+        assert_eq!(bank.find(SampleSet::Soft, Hit::Finish, 2), Some(skin.join("soft-hitfinish.wav")));
+        assert_eq!(bank.find(SampleSet::Auto, Hit::Whistle, 1), Some(skin.join("normal-hitwhistle.wav")));
+        assert_eq!(bank.find(SampleSet::Drum, Hit::Finish, 1), Some(skin.join("drum-hitfinish.mp3")));
+
+        // Nothing anywhere, the built-in one plays
+        assert_eq!(bank.find(SampleSet::Drum, Hit::Clap, 1), None);
+        bank.set_skin_dir(None);
+        assert_eq!(bank.find(SampleSet::Normal, Hit::Whistle, 1), None);
+    }
+}
\ No newline at end of file
@@ -11,14 +11,28 @@ use winit::event::WindowEvent;
 use crate::identifier::Identifier;
 use crate::state::State;
 use crate::view::menu::MenuView;
+use crate::view::window::audio::AudioWindow;
 use crate::view::window::bindings::BindingsWindow;
+use crate::view::window::error::ErrorWindow;
+use crate::view::window::metadata::MetadataWindow;
+use crate::view::window::mixer::MixerWindow;
+use crate::view::window::save_as::SaveAsWindow;
 use crate::view::window::startup::StartupWindow;
 use crate::view::window::timeline::TimelineWindow;
+use crate::view::window::timing::TimingWindow;
+use crate::view::window::tools::ToolsWindow;
 
 pub(crate) struct Windows {
     pub startup: StartupWindow,
     pub bindings: BindingsWindow,
     pub timeline: TimelineWindow,
+    pub tools: ToolsWindow,
+    pub save_as: SaveAsWindow,
+    pub error: ErrorWindow,
+    pub audio: AudioWindow,
+    pub timing: TimingWindow,
+    pub mixer: MixerWindow,
+    pub metadata: MetadataWindow,
 }
 
 pub struct EGuiScreen {
@@ -38,6 +52,13 @@ impl EGuiScreen {
                 startup: StartupWindow::new(),
                 bindings: BindingsWindow::new(),
                 timeline: TimelineWindow::new(),
+                tools: ToolsWindow::new(),
+                save_as: SaveAsWindow::new(),
+                error: ErrorWindow::new(),
+                audio: AudioWindow::new(),
+                timing: TimingWindow::new(),
+                mixer: MixerWindow::new(),
+                metadata: MetadataWindow::new(),
             }
         });
     }
@@ -50,9 +71,16 @@ impl Screen<State, Identifier> for EGuiScreen {
             self.windows.startup.set_visible(state.projects.current.is_none());
             
             View::show(&mut self.menu, (state, &mut self.windows), view, graphics, ctx);
-            View::show(&mut self.windows.startup, state, view, graphics, ctx);
+            View::show(&mut self.windows.startup, (state, &mut self.windows.error), view, graphics, ctx);
             View::show(&mut self.windows.bindings, (state, &mut app.bindings, &app.grab_key, &mut app.want_key), view, graphics, ctx);
             View::show(&mut self.windows.timeline, state, view, graphics, ctx);
+            View::show(&mut self.windows.tools, state, view, graphics, ctx);
+            View::show(&mut self.windows.timing, (state, &mut self.windows.error), view, graphics, ctx);
+            View::show(&mut self.windows.save_as, (state, &mut self.windows.error), view, graphics, ctx);
+            View::show(&mut self.windows.audio, (state, &mut self.windows.error), view, graphics, ctx);
+            View::show(&mut self.windows.mixer, (state, &mut self.windows.error), view, graphics, ctx);
+            View::show(&mut self.windows.metadata, (state, &mut self.windows.error), view, graphics, ctx);
+            View::show(&mut self.windows.error, (), view, graphics, ctx);
         });
     }
 
@@ -65,8 +93,11 @@ impl Screen<State, Identifier> for EGuiScreen {
 
         match event {
             WindowEvent::DroppedFile(file) => {
-                state.editor.open_project(file, &mut state.projects);
-                self.windows.startup.set_visible(false);
+                if let Err(err) = state.editor.open_project(file, &mut state.projects) {
+                    self.windows.error.show_error(&err);
+                } else {
+                    self.windows.startup.set_visible(false);
+                }
             }
 
             WindowEvent::MouseWheel { device_id, delta, phase, .. } => {
@@ -2,14 +2,44 @@ use cgmath::{vec3, Quaternion, Zero, vec4};
 use wcore::{screen::Screen, graphics::{context::Context, bindable::Bindable, drawable::Drawable, scene::Scene2D, primitive::mesh::{instanced::InstancedMesh, data::{vertex::Vertex, model::{ModelRaw, Model}}}, pipeline::{model::ModelPipeline, shader::scene::SceneSlot, Pipeline}, camera::Projection, utils}, input::Input, app::AppState, unit::Unit};
 use winit::event::WindowEvent;
 
-use crate::{state::State, graphics::{primitive::mesh::taiko::{Circle, CircleRaw}, pipeline::taiko::TaikoCirclePipeline}, identifier::Identifier, beatmap::component::adapter::taiko::TaikoVariantAdapter, unit::selection::SelectionUnit};
+use crate::{state::State, graphics::{primitive::mesh::taiko::{Circle, CircleRaw}, pipeline::taiko::TaikoCirclePipeline}, identifier::Identifier, beatmap::{Time, component::{HitObject, adapter::taiko::TaikoVariantAdapter}}, unit::{selection::SelectionUnit, placement::PlacementUnit}, tool::Tool};
 use color_eyre::eyre::Result;
+use instant::Instant;
 
 pub const OFFSET: f32 = 200.0;
 pub const SCALE: f32 = 0.8;
 pub const CIRCLE_SIZE: f32 = 128.0 * 0.75;
+pub const BIG_SCALE: f32 = 1.55;
 pub const DEAD_ZONE: f32 = 20.0;
 
+pub const DON_COLOR: [f32; 4] = [0.92, 0.0, 0.27, 1.0];
+pub const KAT_COLOR: [f32; 4] = [0.0, 0.47, 0.67, 1.0];
+pub const DRUMROLL_COLOR: [f32; 4] = [0.99, 0.73, 0.0, 1.0];
+pub const DENDEN_COLOR: [f32; 4] = [0.96, 0.45, 0.12, 1.0];
+
+// Inverse of the playfield camera, `now` is the time at the hit position
+pub fn screen_to_time(now: Time, x: f32) -> f64 {
+    return ((x - OFFSET) / SCALE + now.as_ms() as f32) as f64;
+}
+
+pub fn object_end(object: &dyn HitObject) -> Option<Time> {
+    let time = object.time()?.0;
+    return Some(object.duration().map_or(time, |duration| Time::from_ms(time.as_ms() + duration.0.as_ms())));
+}
+
+pub fn object_size(object: &dyn HitObject) -> f32 {
+    return if object.variant().map_or(false, |x| x.is_big()) { CIRCLE_SIZE * BIG_SCALE } else { CIRCLE_SIZE };
+}
+
+pub fn object_color(object: &dyn HitObject) -> [f32; 4] {
+    return match (object.variant(), object.duration()) {
+        (Some(_), Some(_))            => DRUMROLL_COLOR,
+        (None, Some(_))               => DENDEN_COLOR,
+        (Some(x), None) if x.is_kat() => KAT_COLOR,
+        _                             => DON_COLOR,
+    };
+}
+
 pub struct TaikoScreen {
     pub pipeline_taiko: TaikoCirclePipeline,
     pub pipeline_field: ModelPipeline,
@@ -18,14 +48,19 @@ pub struct TaikoScreen {
     pub scene: Scene2D,
     
     pub mesh_circle: InstancedMesh<Circle, CircleRaw, Vertex>,
+    pub mesh_circle_tail: InstancedMesh<Circle, CircleRaw, Vertex>,
+    pub mesh_model_body: InstancedMesh<Model, ModelRaw, Vertex>,
     pub mesh_model_hit: InstancedMesh<Model, ModelRaw, Vertex>,
 
-    selection_unit: SelectionUnit
+    selection_unit: SelectionUnit,
+    placement_unit: PlacementUnit,
 }
 
 impl TaikoScreen {
     pub fn new(graphics: &Context) -> Result<Self> {
         let mesh_circle = InstancedMesh::new(&graphics.device, Vertex::vertices_rect(-0.5, 0.5), vec![]);
+        let mesh_circle_tail = InstancedMesh::new(&graphics.device, Vertex::vertices_rect(-0.5, 0.5), vec![]);
+        let mesh_model_body = InstancedMesh::new(&graphics.device, Vertex::vertices_rect(-0.5, 0.5), vec![]);
         let mesh_model_hit = InstancedMesh::new(&graphics.device, Vertex::vertices_rect(-0.5, 0.5), vec![Model {
             position: vec3(OFFSET, OFFSET, 0.0),
             rotation: Quaternion::zero(),
@@ -49,17 +84,24 @@ impl TaikoScreen {
             scene,
 
             mesh_circle,
+            mesh_circle_tail,
+            mesh_model_body,
             mesh_model_hit,
 
             selection_unit: SelectionUnit::new(graphics),
+            placement_unit: PlacementUnit::new(graphics),
         });
     }
 }
 
 impl Screen<State, Identifier> for TaikoScreen {
+    fn update(&mut self, state: &mut State, _app: &mut AppState<State, Identifier>, _now: Instant) {
+        state.editor.update();
+    }
+
     fn render(&mut self, state: &mut State, app: &mut AppState<State, Identifier>, view: &wgpu::TextureView) {
         utils::submit(&app.graphics.queue, &app.graphics.device, |encoder| {
-            utils::render(encoder, &view, None, |mut render_pass| {
+            utils::render(encoder, view, None, |mut render_pass| {
                 /* Hit position */
                 self.scene.camera.position.x = 0.0;                           // Manipulate camera
                 self.pipeline_model.attach(&mut render_pass);                 // Attach to renderpass
@@ -67,63 +109,97 @@ impl Screen<State, Identifier> for TaikoScreen {
                 state.textures.t_hit_position.bind(&mut render_pass, 1);      // Bind texture
                 self.mesh_model_hit.draw(&mut render_pass);                   // Draw
 
-                /* Circles */
-                self.pipeline_taiko.attach(&mut render_pass);
-
-                // Scene
+                /* Objects */
                 let time = state.editor.get_time();
                 self.scene.camera.position.x = -((time.as_ms() as f32 * SCALE) - OFFSET);
-                self.pipeline_taiko.update(&app.graphics.queue, &self.scene);
 
-                // Textures
-                state.textures.t_circle.bind(&mut render_pass, 1);
-                state.textures.t_overlay.bind(&mut render_pass, 2);
-                state.textures.t_big_circle.bind(&mut render_pass, 3);
-                state.textures.t_big_overlay.bind(&mut render_pass, 4);
+                self.mesh_circle.instances.clear();
+                self.mesh_circle_tail.instances.clear();
+                self.mesh_model_body.instances.clear();
+                if let Some(objects) = state.editor.hitobjects() {
+                    for (_, obj) in objects.iter().rev() {
+                        let (obj_time, obj_end) = match (obj.time(), object_end(obj)) {
+                            (Some(time), Some(end)) => (time.0, end),
+                            _ => continue,
+                        };
+
+                        if time > obj_end {
+                            continue;
+                        }
 
-                // Drawing
-                if let Some(objects) = &state.editor.hitobjects {
-                    if objects[0].time().is_some()
-                       && objects[0].variant().is_some() {
-                        self.mesh_circle.instances.clear();
-                        for obj in objects.iter().rev() {
-                            let obj_time = obj.time().unwrap().0;
-                            let obj_variant = obj.variant().unwrap();
-
-                            if time > obj_time {
-                                continue;
-                            }
-
-                            self.mesh_circle.instances.push(Circle {
-                                position: vec3(obj_time.as_ms() as f32 * SCALE, 200.0, 0.0),
-                                rotation: Quaternion::zero(),
-                                scale: if obj_variant.is_big() { vec3(CIRCLE_SIZE * 1.55, CIRCLE_SIZE * 1.55, 1.0) }
-                                else                           { vec3(CIRCLE_SIZE       , CIRCLE_SIZE       , 1.0) },
+                        let big = obj.variant().map_or(false, |x| x.is_big());
+                        let size = object_size(obj);
+                        let obj_end = obj.duration().map(|_| obj_end);
+                        let color = object_color(obj);
 
-                                color: if obj_variant.is_kat() { vec4(0.0, 0.47, 0.67, 1.0) }
-                                else                           { vec4(0.92, 0.0, 0.27, 1.0) },
+                        // Body stretched between head and tail
+                        if let Some(obj_end) = obj_end {
+                            let start = obj_time.as_ms() as f32 * SCALE;
+                            let end = obj_end.as_ms() as f32 * SCALE;
 
-                                finisher: obj_variant.is_big(),
+                            self.mesh_model_body.instances.push(Model {
+                                position: vec3((start + end) / 2.0, 200.0, 0.0),
+                                rotation: Quaternion::zero(),
+                                scale: vec3(end - start, size, 1.0),
+                                color: color.into(),
+                            });
+
+                            self.mesh_circle_tail.instances.push(Circle {
+                                position: vec3(end, 200.0, 0.0),
+                                rotation: Quaternion::zero(),
+                                scale: vec3(size, size, 1.0),
+                                color: color.into(),
+                                finisher: big,
                             });
                         }
 
-                        self.mesh_circle.bake_instances(&app.graphics.device);
-                        self.mesh_circle.draw(&mut render_pass);
+                        self.mesh_circle.instances.push(Circle {
+                            position: vec3(obj_time.as_ms() as f32 * SCALE, 200.0, 0.0),
+                            rotation: Quaternion::zero(),
+                            scale: vec3(size, size, 1.0),
+                            color: color.into(),
+                            finisher: big,
+                        });
                     }
-
-                } else if !self.mesh_circle.instances.is_empty() {
-                    self.mesh_circle.instances.clear();
                 }
 
+                self.mesh_model_body.bake_instances(&app.graphics.device);
+                self.mesh_circle_tail.bake_instances(&app.graphics.device);
+                self.mesh_circle.bake_instances(&app.graphics.device);
+
+                // Bodies
+                self.pipeline_field.attach(&mut render_pass);
+                self.pipeline_field.update(&app.graphics.queue, &self.scene);
+                state.textures.t_selection_box.bind(&mut render_pass, 1);
+                self.mesh_model_body.draw(&mut render_pass);
+
+                // Circles
+                self.pipeline_taiko.attach(&mut render_pass);
+                self.pipeline_taiko.update(&app.graphics.queue, &self.scene);
+
+                state.textures.t_circle.bind(&mut render_pass, 1);
+                state.textures.t_overlay.bind(&mut render_pass, 2);
+                state.textures.t_big_circle.bind(&mut render_pass, 3);
+                state.textures.t_big_overlay.bind(&mut render_pass, 4);
+
+                self.mesh_circle_tail.draw(&mut render_pass);
+                self.mesh_circle.draw(&mut render_pass);
+
+                self.placement_unit.render((&state.textures, &self.pipeline_field, &self.scene, &state.editor, time), &mut render_pass, &app.graphics);
+
                 self.pipeline_model.attach(&mut render_pass);
-                self.selection_unit.render((&state.textures, &mut self.pipeline_field, &self.pipeline_model, &self.scene, &self.mesh_circle), &mut render_pass, &app.graphics);
+                self.selection_unit.render((&state.textures, &self.pipeline_field, &self.pipeline_model, &self.scene, &state.editor, time), &mut render_pass, &app.graphics);
             });
         });
     }
 
     #[allow(unused_variables)]
     fn input(&mut self, state: &mut State, app: &mut AppState<State, Identifier>, event: &WindowEvent, input: &Input) -> bool {
-        self.selection_unit.input((state, &self.mesh_circle), event, input);
+        if state.editor.tool() == Tool::Select {
+            self.selection_unit.input(state, event, input);
+        }
+
+        self.placement_unit.input(state, event, input);
 
         return true;
     }
@@ -0,0 +1,92 @@
+use std::path::PathBuf;
+
+use r3gl_audio::{Bus, BusMix};
+use serde::{Deserialize, Serialize};
+
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct Settings {
+    // osu! skin folder, used for hitsounds the beatmap doesn't have
+    #[serde(default)]
+    pub skin: Option<PathBuf>,
+
+    // In ms, see `Editor::set_audio_offset`
+    #[serde(default)]
+    pub audio_offset: i32,
+
+    // Default device when not set. Tables go last, toml can't have values after them
+    #[serde(default)]
+    pub audio_output: Option<AudioOutput>,
+
+    // Tracks are per project, this is everything on top of them
+    #[serde(default)]
+    pub mix: MixSettings,
+}
+
+// Names as listed by `r3gl_audio::list_outputs`
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct AudioOutput {
+    pub host: String,
+    pub device: String,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct MixSettings {
+    pub master: f32,
+    pub song: BusSettings,
+    pub hitsounds: BusSettings,
+    pub metronome: BusSettings,
+}
+
+impl Default for MixSettings {
+    fn default() -> Self {
+        return Self {
+            master: 1.0,
+            song: BusSettings::default(),
+            hitsounds: BusSettings::default(),
+            metronome: BusSettings { volume: 0.5, mute: false },
+        };
+    }
+}
+
+impl MixSettings {
+    pub fn bus(&self, bus: Bus) -> BusSettings {
+        return match bus {
+            Bus::Song => self.song,
+            Bus::Hitsounds => self.hitsounds,
+            Bus::Metronome => self.metronome,
+        };
+    }
+
+    pub fn set_bus(&mut self, bus: Bus, settings: BusSettings) {
+        match bus {
+            Bus::Song => self.song = settings,
+            Bus::Hitsounds => self.hitsounds = settings,
+            Bus::Metronome => self.metronome = settings,
+        }
+    }
+}
+
+// Same as `BusMix`, which doesn't know about serde
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+pub struct BusSettings {
+    pub volume: f32,
+    pub mute: bool,
+}
+
+impl Default for BusSettings {
+    fn default() -> Self {
+        return BusMix::default().into();
+    }
+}
+
+impl From<BusMix> for BusSettings {
+    fn from(mix: BusMix) -> Self {
+        return Self { volume: mix.volume, mute: mix.mute };
+    }
+}
+
+impl From<BusSettings> for BusMix {
+    fn from(settings: BusSettings) -> Self {
+        return Self { volume: settings.volume, mute: settings.mute };
+    }
+}
\ No newline at end of file
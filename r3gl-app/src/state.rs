@@ -1,22 +1,35 @@
 use std::env;
 
+use r3gl_audio::Bus;
 use wcore::graphics::context::Context;
 
-use crate::{project::project_manager::ProjectManager, load_or_default, save, editor::Editor, store::texture::TextureStore};
+use crate::{project::project_manager::ProjectManager, load_or_default, save, editor::Editor, settings::Settings, store::texture::TextureStore};
 
 pub struct State {
     pub textures: TextureStore,
     pub projects: ProjectManager,
+    pub settings: Settings,
     pub editor: Editor,
 }
 
 impl State {
     pub fn new(graphics: &Context) -> Self {
         let t_path = env::current_dir().unwrap().join("resources").join("textures");
+
+        let settings: Settings = load_or_default("settings.toml");
+        let mut editor = Editor::new(settings.audio_output.clone());
+        editor.set_skin_dir(settings.skin.clone());
+        editor.set_audio_offset(settings.audio_offset);
+        editor.set_volume(settings.mix.master);
+        for bus in [Bus::Song, Bus::Hitsounds, Bus::Metronome] {
+            editor.set_bus(bus, settings.mix.bus(bus).into());
+        }
+
         return Self {
             textures: TextureStore::from_path(t_path, graphics),
             projects: load_or_default("projects.toml"),
-            editor: Editor::new(),
+            settings,
+            editor,
         }
     }
 }
@@ -24,5 +37,6 @@ impl State {
 impl Drop for State {
     fn drop(&mut self) {
         save(&self.projects, "projects.toml");
+        save(&self.settings, "settings.toml");
     }
 }
\ No newline at end of file
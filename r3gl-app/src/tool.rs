@@ -0,0 +1,40 @@
+use crate::beatmap::{Time, component::{HitObject, adapter::taiko::TaikoColor}, parser::osu_taiko::{TaikoCircle, TaikoDrumroll, TaikoDenden}};
+
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Default)]
+pub enum Tool {
+    #[default]
+    Select,
+    Don,
+    Kat,
+    Drumroll,
+    Denden,
+}
+
+impl Tool {
+    pub const ALL: [Tool; 5] = [Tool::Select, Tool::Don, Tool::Kat, Tool::Drumroll, Tool::Denden];
+
+    pub fn name(&self) -> &'static str {
+        return match self {
+            Tool::Select   => "Select",
+            Tool::Don      => "Don",
+            Tool::Kat      => "Kat",
+            Tool::Drumroll => "Drumroll",
+            Tool::Denden   => "Denden",
+        };
+    }
+
+    // Duration tools are dragged out instead of placed with a single click
+    pub fn has_duration(&self) -> bool {
+        return matches!(self, Tool::Drumroll | Tool::Denden);
+    }
+
+    pub fn create(&self, time: Time, duration: Time, finisher: bool) -> Option<Box<dyn HitObject>> {
+        return match self {
+            Tool::Select   => None,
+            Tool::Don      => Some(Box::new(TaikoCircle::new(time, TaikoColor::DON, finisher))),
+            Tool::Kat      => Some(Box::new(TaikoCircle::new(time, TaikoColor::KAT, finisher))),
+            Tool::Drumroll => Some(Box::new(TaikoDrumroll::new(time, duration, finisher))),
+            Tool::Denden   => Some(Box::new(TaikoDenden::new(time, duration))),
+        };
+    }
+}
\ No newline at end of file
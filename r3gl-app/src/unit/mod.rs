@@ -1 +1,2 @@
-pub mod selection;
\ No newline at end of file
+pub mod selection;
+pub mod placement;
\ No newline at end of file
@@ -0,0 +1,156 @@
+use cgmath::{vec3, vec4, Quaternion, Vector2, Vector4, Zero};
+use log::error;
+use wcore::{unit::Unit, graphics::{primitive::mesh::{data::{model::{Model, ModelRaw}, vertex::Vertex}, instanced::InstancedMesh}, context::Context, pipeline::{model::ModelPipeline, Pipeline, shader::scene::SceneSlot}, scene::Scene2D, bindable::Bindable, drawable::Drawable}, input::Input};
+use winit::event::{WindowEvent, MouseButton, ElementState};
+
+use crate::{state::State, editor::Editor, command::object::AddObjects, beatmap::Time, tool::Tool, screen::taiko::{SCALE, OFFSET, CIRCLE_SIZE, screen_to_time, BIG_SCALE, DON_COLOR, KAT_COLOR, DRUMROLL_COLOR, DENDEN_COLOR}, store::texture::TextureStore};
+
+pub struct PlacementUnit {
+    pub mesh_model_preview: InstancedMesh<Model, ModelRaw, Vertex>,
+    pub mesh_model_preview_body: InstancedMesh<Model, ModelRaw, Vertex>,
+
+    cursor: Vector2<f32>,
+    drag_start: Option<Time>,
+}
+
+impl PlacementUnit {
+    pub fn new(graphics: &Context) -> Self {
+        let mesh_model_preview = InstancedMesh::new(&graphics.device, Vertex::vertices_rect(-0.5, 0.5), vec![]);
+        let mesh_model_preview_body = InstancedMesh::new(&graphics.device, Vertex::vertices_rect(-0.5, 0.5), vec![]);
+
+        return Self {
+            mesh_model_preview,
+            mesh_model_preview_body,
+
+            cursor: (0.0, 0.0).into(),
+            drag_start: None,
+        };
+    }
+
+    fn cursor_time(editor: &Editor, time: Time, x: f32) -> Time {
+        return editor.snap(Time::from_ms_f64(screen_to_time(time, x)));
+    }
+
+    fn is_hovering(&self) -> bool {
+        return (self.cursor.y - OFFSET).abs() <= CIRCLE_SIZE * BIG_SCALE / 2.0;
+    }
+
+    fn color(tool: Tool) -> Vector4<f32> {
+        let [r, g, b, _] = match tool {
+            Tool::Kat      => KAT_COLOR,
+            Tool::Drumroll => DRUMROLL_COLOR,
+            Tool::Denden   => DENDEN_COLOR,
+            _              => DON_COLOR,
+        };
+
+        return vec4(r, g, b, 0.5);
+    }
+
+    fn place(state: &mut State, start: Time, end: Time) {
+        let tool = state.editor.tool();
+        let duration = Time::from_ms(end.as_ms().saturating_sub(start.as_ms()));
+        if let Some(object) = tool.create(start, duration, state.editor.is_finisher()) {
+            if let Err(err) = state.editor.execute(AddObjects::new(vec![object])) {
+                error!("{:#}", err);
+            }
+        }
+    }
+}
+
+impl Unit for PlacementUnit {
+    type RenderState<'a> = (&'a TextureStore, &'a ModelPipeline, &'a Scene2D, &'a Editor, Time);
+    type InputState<'a> = &'a mut State;
+
+    fn render<'a: 'b, 'b>(&'a mut self, (textures, pipeline_field, scene, editor, now): Self::RenderState<'a>, render_pass: &mut wgpu::RenderPass<'b>, graphics: &Context) {
+        self.mesh_model_preview.instances.clear();
+        self.mesh_model_preview_body.instances.clear();
+
+        let tool = editor.tool();
+        if editor.hitobjects().is_some() && tool != Tool::Select && (self.is_hovering() || self.drag_start.is_some()) {
+            let time = Self::cursor_time(editor, now, self.cursor.x);
+            let color = Self::color(tool);
+            let size = if editor.is_finisher() && tool != Tool::Denden { CIRCLE_SIZE * BIG_SCALE } else { CIRCLE_SIZE };
+
+            let start = self.drag_start.unwrap_or(time);
+            let start_x = start.as_ms() as f32 * SCALE;
+            if let Some(start) = self.drag_start {
+                let end_x = time.max(start).as_ms() as f32 * SCALE;
+                self.mesh_model_preview_body.instances.push(Model {
+                    position: vec3((start_x + end_x) / 2.0, OFFSET, 0.0),
+                    rotation: Quaternion::zero(),
+                    scale: vec3(end_x - start_x, size, 1.0),
+                    color,
+                });
+
+                self.mesh_model_preview.instances.push(Model {
+                    position: vec3(end_x, OFFSET, 0.0),
+                    rotation: Quaternion::zero(),
+                    scale: vec3(size, size, 1.0),
+                    color,
+                });
+            }
+
+            self.mesh_model_preview.instances.push(Model {
+                position: vec3(start_x, OFFSET, 0.0),
+                rotation: Quaternion::zero(),
+                scale: vec3(size, size, 1.0),
+                color,
+            });
+        }
+
+        self.mesh_model_preview.bake_instances(&graphics.device);
+        self.mesh_model_preview_body.bake_instances(&graphics.device);
+
+        pipeline_field.attach(render_pass);            // Attach to renderpass
+        pipeline_field.update(&graphics.queue, scene); // Update camera (! buffred !)
+
+        textures.t_selection_box.bind(render_pass, 1);
+        self.mesh_model_preview_body.draw(render_pass);
+
+        textures.t_circle.bind(render_pass, 1);
+        self.mesh_model_preview.draw(render_pass);
+    }
+
+    fn input<'a>(&mut self, state: Self::InputState<'a>, event: &WindowEvent, input: &Input) {
+        match event {
+            WindowEvent::CursorMoved { .. } => {
+                self.cursor = input.cursor_position;
+            }
+
+            WindowEvent::MouseInput { state: button_state, button: MouseButton::Left, .. } => {
+                let tool = state.editor.tool();
+                if tool == Tool::Select || state.editor.hitobjects().is_none() {
+                    self.drag_start = None;
+                    return;
+                }
+
+                match *button_state {
+                    ElementState::Pressed => if self.is_hovering() {
+                        let now = state.editor.get_time();
+                        let time = Self::cursor_time(&state.editor, now, input.cursor_position.x);
+                        if tool.has_duration() {
+                            self.drag_start = Some(time);
+                        } else {
+                            Self::place(state, time, time);
+                        }
+                    }
+
+                    ElementState::Released => if let Some(start) = self.drag_start.take() {
+                        let now = state.editor.get_time();
+                        let end = Self::cursor_time(&state.editor, now, input.cursor_position.x);
+
+                        // A click without dragging makes the object one tick long
+                        let end = if end > start { Some(end) }
+                                  else           { state.editor.tick_after(start, state.editor.snap_divisor(), 1) };
+
+                        if let Some(end) = end {
+                            Self::place(state, start, end);
+                        }
+                    }
+                }
+            }
+
+            _ => {}
+        }
+    }
+}
\ No newline at end of file
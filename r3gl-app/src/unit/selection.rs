@@ -1,38 +1,46 @@
 use cgmath::{vec2, vec3, Quaternion, vec4, Vector2, MetricSpace, Zero};
+use log::error;
 use wcore::{unit::Unit, graphics::{primitive::mesh::{data::{model::{Model, ModelRaw}, vertex::Vertex}, instanced::InstancedMesh}, context::Context, pipeline::{model::ModelPipeline, Pipeline, shader::scene::SceneSlot}, scene::Scene2D, bindable::Bindable, drawable::Drawable}, collider::collide, input::Input};
 use winit::event::{WindowEvent, MouseButton, ElementState};
 
-use crate::{state::State, screen::taiko::{DEAD_ZONE, SCALE, OFFSET, CIRCLE_SIZE}, graphics::primitive::mesh::taiko::{Circle, CircleRaw}, store::texture::TextureStore};
+use crate::{state::State, editor::Editor, beatmap::Time, command::object::MoveObjects, screen::taiko::{DEAD_ZONE, SCALE, OFFSET, object_end, object_size, object_color, screen_to_time}, store::texture::TextureStore};
 
 pub struct SelectionUnit {
     pub mesh_model_selection: InstancedMesh<Model, ModelRaw, Vertex>,
     pub mesh_model_selection_box: InstancedMesh<Model, ModelRaw, Vertex>,
+    pub mesh_model_drag: InstancedMesh<Model, ModelRaw, Vertex>,
 
-    pub selection: Vec<usize>,
     pub selection_start: Vector2<f32>,
+
+    // Time of the grabbed object and the unsnapped cursor time when it was grabbed
+    drag_start: Option<(Time, f64)>,
+    drag_offset: i64,
 }
 
 impl SelectionUnit {
     pub fn new(graphics: &Context) -> Self {
         let mesh_model_selection_box = InstancedMesh::new(&graphics.device, Vertex::vertices_rect(0.0, 1.0), vec![]);
         let mesh_model_selection = InstancedMesh::new(&graphics.device, Vertex::vertices_rect(-0.5, 0.5), vec![]);
-        let selection = vec![];
+        let mesh_model_drag = InstancedMesh::new(&graphics.device, Vertex::vertices_rect(-0.5, 0.5), vec![]);
 
         return Self {
             mesh_model_selection,
             mesh_model_selection_box,
+            mesh_model_drag,
 
-            selection,
             selection_start: (0.0, 0.0,).into(),
+
+            drag_start: None,
+            drag_offset: 0,
         };
     }
 }
 
 impl Unit for SelectionUnit {
-    type RenderState<'a> = (&'a TextureStore, &'a ModelPipeline, &'a ModelPipeline, &'a Scene2D, &'a InstancedMesh<Circle, CircleRaw, Vertex>);
-    type InputState<'a> = (&'a mut State, &'a InstancedMesh<Circle, CircleRaw, Vertex>);
+    type RenderState<'a> = (&'a TextureStore, &'a ModelPipeline, &'a ModelPipeline, &'a Scene2D, &'a Editor, Time);
+    type InputState<'a> = &'a mut State;
 
-    fn render<'a: 'b, 'b>(&'a mut self, (textures, pipeline_field, pipeline_model, scene, mesh_circle): Self::RenderState<'a>, render_pass: &mut wgpu::RenderPass<'b>, graphics: &Context) {
+    fn render<'a: 'b, 'b>(&'a mut self, (textures, pipeline_field, pipeline_model, scene, editor, time): Self::RenderState<'a>, render_pass: &mut wgpu::RenderPass<'b>, graphics: &Context) {
         /* Selection */
         pipeline_model.attach(render_pass);                             // Attach to renderpass
         textures.t_selection_box.bind(render_pass, 1);                  // Bind texture
@@ -41,27 +49,53 @@ impl Unit for SelectionUnit {
 
         pipeline_field.attach(render_pass);            // Attach to renderpass
         pipeline_field.update(&graphics.queue, scene); // Update camera (! buffred !)
-        textures.t_selection.bind(render_pass, 1);     // Bind texture
 
-        // Draw
+        // Draw, shifted by the drag preview
         self.mesh_model_selection.instances.clear();
-        for index in self.selection.iter().rev() {
-            if let Some(obj) = mesh_circle.instances.get(*index) {
-                self.mesh_model_selection.instances.push(Model {
-                    position: obj.position,
-                    rotation: obj.rotation,
-                    scale: obj.scale,
-
-                    color: vec4(1.0, 1.0, 1.0, 1.0)
-                });
+        self.mesh_model_drag.instances.clear();
+        if let Some(objects) = editor.hitobjects() {
+            for id in editor.selection() {
+                let obj = match objects.get(*id) { Some(obj) => obj, None => continue };
+                if let (Some(obj_time), Some(obj_end)) = (obj.time(), object_end(obj)) {
+                    if time > obj_end {
+                        continue;
+                    }
+
+                    let size = object_size(obj);
+                    let x = (obj_time.0.as_ms() as i64 + self.drag_offset) as f32 * SCALE;
+                    self.mesh_model_selection.instances.push(Model {
+                        position: vec3(x, OFFSET, 0.0),
+                        rotation: Quaternion::zero(),
+                        scale: vec3(size, size, 1.0),
+
+                        color: vec4(1.0, 1.0, 1.0, 1.0)
+                    });
+
+                    if self.drag_offset != 0 {
+                        let [r, g, b, _] = object_color(obj);
+                        self.mesh_model_drag.instances.push(Model {
+                            position: vec3(x, OFFSET, 0.0),
+                            rotation: Quaternion::zero(),
+                            scale: vec3(size, size, 1.0),
+
+                            color: vec4(r, g, b, 0.6)
+                        });
+                    }
+                }
             }
         }
 
+        self.mesh_model_drag.bake_instances(&graphics.device);
         self.mesh_model_selection.bake_instances(&graphics.device);
+
+        textures.t_circle.bind(render_pass, 1);
+        self.mesh_model_drag.draw(render_pass);
+
+        textures.t_selection.bind(render_pass, 1);
         self.mesh_model_selection.draw(render_pass);
     }
 
-    fn input<'a>(&mut self, (state, mesh_circle): Self::InputState<'a>, event: &WindowEvent, input: &Input) {
+    fn input<'a>(&mut self, state: Self::InputState<'a>, event: &WindowEvent, input: &Input) {
         #[allow(deprecated)]
         match event {
             WindowEvent::CursorMoved { device_id: _, position, modifiers: _ } => {
@@ -70,6 +104,17 @@ impl Unit for SelectionUnit {
                         return;
                     }
 
+                    // Keeps relative spacing by snapping only the grabbed object
+                    if let Some((anchor, cursor)) = self.drag_start {
+                        let now = state.editor.get_time();
+                        let target = anchor.as_ms() as f64 + screen_to_time(now, input.cursor_position.x) - cursor;
+                        let target = state.editor.snap(Time::from_ms_f64(target));
+                        // Same as the move itself, the earliest object stops at 0
+                        let earliest = state.editor.selection_start().map_or(0, |time| time.as_ms() as i64);
+                        self.drag_offset = (target.as_ms() as i64 - anchor.as_ms() as i64).max(-earliest);
+                        return;
+                    }
+
                     self.mesh_model_selection_box.instances.push(Model {
                         position: vec3(0.0, 0.0, 0.0),
                         rotation: Quaternion::zero(),
@@ -104,15 +149,20 @@ impl Unit for SelectionUnit {
 
                     let time = state.editor.get_time();
                     let offset = -((time.as_ms() as f32 * SCALE) - OFFSET);
-                    self.selection.clear();
-                    self.selection = mesh_circle.instances.iter().enumerate().filter_map(|(i, x)| {
-                        let mut pos = x.position.truncate();
-                        pos.x += offset;
+                    let selection = state.editor.hitobjects().map_or(vec![], |objects| {
+                        objects.iter().filter_map(|(id, x)| {
+                            if time > object_end(x)? {
+                                return None;
+                            }
 
-                        if collide::square(selection_box.position.truncate(), selection_box.scale.truncate(), pos) {
-                            Some(i)
-                        } else { None }
-                    }).collect();
+                            let pos = vec2(x.time()?.0.as_ms() as f32 * SCALE + offset, OFFSET);
+                            if collide::square(selection_box.position.truncate(), selection_box.scale.truncate(), pos) {
+                                Some(id)
+                            } else { None }
+                        }).collect()
+                    });
+
+                    state.editor.set_selection(selection);
 
                 }
             }
@@ -125,29 +175,54 @@ impl Unit for SelectionUnit {
                         ElementState::Pressed => {
                             self.selection_start = input.cursor_position;
 
-                            let selection = mesh_circle.instances.iter().rev().position(|x| {
-                                collide::circle(vec2(x.position.x + offset, x.position.y), input.cursor_position, CIRCLE_SIZE / 2.0)
+                            // Earlier objects are drawn on top
+                            let grabbed = state.editor.hitobjects().and_then(|objects| {
+                                objects.iter().find(|(_, x)| {
+                                    match (x.time(), object_end(*x)) {
+                                        (Some(obj_time), Some(obj_end)) if time <= obj_end => {
+                                            collide::circle(vec2(obj_time.0.as_ms() as f32 * SCALE + offset, OFFSET), input.cursor_position, object_size(*x) / 2.0)
+                                        }
+
+                                        _ => false,
+                                    }
+                                }).map(|(id, x)| (id, x.time().unwrap().0))
                             });
 
-                            if let Some(reverse_index) = selection {
-                                let len = mesh_circle.instances.len();
-                                if !input.modifiers.ctrl() {
-                                    self.selection.clear();
+                            match grabbed {
+                                // Grabbing a selected object drags the whole selection
+                                Some((id, obj_time)) if !input.modifiers.ctrl() || !state.editor.is_selected(id) => {
+                                    if !state.editor.is_selected(id) {
+                                        if !input.modifiers.ctrl() {
+                                            state.editor.clear_selection();
+                                        }
+
+                                        state.editor.toggle_selected(id);
+                                    }
+
+                                    self.drag_start = Some((obj_time, screen_to_time(time, input.cursor_position.x)));
+                                    self.drag_offset = 0;
                                 }
 
-                                let i = len - reverse_index - 1;
-                                if let Some(selected) = self.selection.iter().copied().position(|x| x == i) {
-                                    self.selection.remove(selected);
-                                } else { self.selection.push(i); }
-                            } else {
-                                if !input.modifiers.ctrl() {
-                                    self.selection.clear();
+                                Some((id, _)) => state.editor.toggle_selected(id),
+
+                                None => if !input.modifiers.ctrl() {
+                                    state.editor.clear_selection();
                                 }
                             }
                         }
 
                         ElementState::Released => {
                             self.mesh_model_selection_box.instances.clear();
+
+                            // The whole drag is a single edit
+                            if self.drag_start.take().is_some() && self.drag_offset != 0 {
+                                let selection = state.editor.selection().to_vec();
+                                if let Err(err) = state.editor.execute(MoveObjects::new(selection, self.drag_offset)) {
+                                    error!("{:#}", err);
+                                }
+                            }
+
+                            self.drag_offset = 0;
                         }
                     }
                 }
@@ -1,4 +1,4 @@
-use egui::{TopBottomPanel, menu};
+use egui::{TopBottomPanel, menu, Button};
 use wcore::{graphics::context::Context, egui::{view::View, window::Window}};
 
 use crate::{state::State, screen::egui::Windows};
@@ -39,13 +39,33 @@ impl View<(&mut State, &mut Windows)> for MenuView {
                             if button.clicked() {
                                 ui.close_menu();
 
-                                state.editor.open_project(path, &mut state.projects);
+                                if let Err(err) = state.editor.open_project(path, &mut state.projects) {
+                                    windows.error.show_error(&err);
+                                }
                             }
                         }
                     });
 
                     ui.separator();
 
+                    let is_open = state.projects.current.is_some();
+                    let save_text = if state.editor.is_dirty() || state.projects.is_mix_dirty() { "Save*" } else { "Save" };
+                    if ui.add_enabled(is_open, Button::new(save_text)).clicked() {
+                        ui.close_menu();
+
+                        if let Err(err) = state.editor.save_project(&mut state.projects) {
+                            windows.error.show_error(&err);
+                        }
+                    }
+
+                    if ui.add_enabled(is_open, Button::new("Save As...")).clicked() {
+                        ui.close_menu();
+
+                        windows.save_as.open(state);
+                    }
+
+                    ui.separator();
+
                     if ui.button("Close Project").clicked() {
                         ui.close_menu();
 
@@ -54,12 +74,101 @@ impl View<(&mut State, &mut Windows)> for MenuView {
 
                 });
 
+                ui.menu_button("Edit", |ui| {
+                    let history = state.editor.history();
+                    let (can_undo, can_redo) = (history.can_undo(), history.can_redo());
+                    let undo_text = history.undo_name().map_or(String::from("Undo"), |name| format!("Undo {}", name));
+                    let redo_text = history.redo_name().map_or(String::from("Redo"), |name| format!("Redo {}", name));
+
+                    if ui.add_enabled(can_undo, Button::new(undo_text)).clicked() {
+                        ui.close_menu();
+
+                        if let Err(err) = state.editor.undo() {
+                            windows.error.show_error(&err);
+                        }
+                    }
+
+                    if ui.add_enabled(can_redo, Button::new(redo_text)).clicked() {
+                        ui.close_menu();
+
+                        if let Err(err) = state.editor.redo() {
+                            windows.error.show_error(&err);
+                        }
+                    }
+
+                    ui.separator();
+
+                    let has_selection = !state.editor.selection().is_empty();
+                    if ui.add_enabled(has_selection, Button::new("Cut")).clicked() {
+                        ui.close_menu();
+
+                        if let Err(err) = state.editor.cut_selection() {
+                            windows.error.show_error(&err);
+                        }
+                    }
+
+                    if ui.add_enabled(has_selection, Button::new("Copy")).clicked() {
+                        ui.close_menu();
+
+                        state.editor.copy_selection();
+                    }
+
+                    let can_paste = state.editor.has_clipboard() && state.projects.current.is_some();
+                    if ui.add_enabled(can_paste, Button::new("Paste")).clicked() {
+                        ui.close_menu();
+
+                        if let Err(err) = state.editor.paste() {
+                            windows.error.show_error(&err);
+                        }
+                    }
+
+                    if ui.add_enabled(has_selection, Button::new("Delete")).clicked() {
+                        ui.close_menu();
+
+                        if let Err(err) = state.editor.delete_selection() {
+                            windows.error.show_error(&err);
+                        }
+                    }
+                });
+
+                ui.menu_button("View", |ui| {
+                    if ui.button("Tools").clicked() {
+                        ui.close_menu();
+
+                        windows.tools.set_visible(true);
+                    }
+
+                    if ui.button("Timing").clicked() {
+                        ui.close_menu();
+
+                        windows.timing.set_visible(true);
+                    }
+
+                    if ui.button("Metadata").clicked() {
+                        ui.close_menu();
+
+                        windows.metadata.set_visible(true);
+                    }
+
+                    if ui.button("Mixer").clicked() {
+                        ui.close_menu();
+
+                        windows.mixer.set_visible(true);
+                    }
+                });
+
                 ui.menu_button("Prefrences", |ui| {
                     if ui.button("Bindings").clicked() {
                         ui.close_menu();
                         
                         windows.bindings.set_visible(true);
                     }
+
+                    if ui.button("Audio").clicked() {
+                        ui.close_menu();
+
+                        windows.audio.open(state);
+                    }
                 });
             });
         });
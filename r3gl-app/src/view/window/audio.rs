@@ -0,0 +1,135 @@
+use egui::{ComboBox, DragValue, RichText};
+use r3gl_audio::{list_outputs, OutputHost};
+use wcore::{graphics::context::Context, egui::window::Window};
+
+use crate::{state::State, settings::AudioOutput};
+
+use super::error::ErrorWindow;
+
+pub struct AudioWindow {
+    visible: bool,
+
+    // Listing devices is slow, so it's only done when asked
+    hosts: Vec<OutputHost>,
+    host: String,
+}
+
+impl AudioWindow {
+    pub fn new() -> Self {
+        return Self {
+            visible: false,
+            hosts: vec![],
+            host: String::new(),
+        };
+    }
+
+    pub fn open(&mut self, state: &State) {
+        self.refresh(state);
+        self.visible = true;
+    }
+
+    fn refresh(&mut self, state: &State) {
+        self.hosts = list_outputs();
+        self.host = match state.editor.output() {
+            Some(output) => output.host.clone(),
+            None => self.hosts.first().map_or(String::new(), |host| host.name.clone()),
+        };
+    }
+}
+
+impl Window<(&mut State, &mut ErrorWindow)> for AudioWindow {
+    type Title = &'static str;
+    fn title() -> Self::Title {
+        return "Audio";
+    }
+
+    #[allow(unused_variables)]
+    fn build<'a>(window: egui::Window<'a>, ctx: &'_ egui::Context) -> egui::Window<'a> {
+        window
+            .default_pos([64.0, 64.0])
+            .default_width(320.0)
+            .collapsible(true)
+            .resizable(true)
+            .title_bar(true)
+    }
+
+    fn set_visible(&mut self, value: bool) { self.visible = value; }
+    fn get_visible(&self) -> bool { return self.visible; }
+
+    #[allow(unused_variables)]
+    fn show(&mut self, (state, error): (&mut State, &mut ErrorWindow), view: &wgpu::TextureView, graphics: &mut Context, ui: &mut egui::Ui) {
+        let mut output = state.editor.output().cloned();
+        let mut offset = state.editor.audio_offset();
+
+        egui::Grid::new("audio_grid")
+          .num_columns(2)
+          .spacing([40.0, 4.0])
+          .show(ui, |ui| {
+            ui.label("Host");
+            ComboBox::from_id_source("audio_host")
+                .width(200.0)
+                .selected_text(self.host.as_str())
+                .show_ui(ui, |ui| {
+                    for host in &self.hosts {
+                        ui.selectable_value(&mut self.host, host.name.clone(), host.name.as_str());
+                    }
+                });
+            ui.end_row();
+
+            let selected = output.as_ref().filter(|output| output.host == self.host).map_or("Default", |output| output.device.as_str()).to_owned();
+            ui.label("Device");
+            ComboBox::from_id_source("audio_device")
+                .width(200.0)
+                .selected_text(selected)
+                .show_ui(ui, |ui| {
+                    ui.selectable_value(&mut output, None, "Default");
+                    for host in self.hosts.iter().filter(|host| host.name == self.host) {
+                        for device in &host.devices {
+                            let value = Some(AudioOutput { host: host.name.clone(), device: device.name.clone() });
+                            let text = if device.is_default { format!("{} (default)", device.name) } else { device.name.clone() };
+                            ui.selectable_value(&mut output, value, text);
+                        }
+                    }
+                });
+            ui.end_row();
+
+            // Calibrated by ear, until hitsounds line up with the picture
+            ui.label("Offset");
+            ui.add(DragValue::new(&mut offset).speed(1.0).clamp_range(-500 ..= 500).suffix(" ms"));
+            ui.end_row();
+        });
+
+        // What the picked device can do, the best of it is used
+        if let Some(current) = &output {
+            let device = self.hosts.iter()
+                .filter(|host| host.name == current.host)
+                .flat_map(|host| &host.devices)
+                .find(|device| device.name == current.device);
+
+            if let Some(device) = device {
+                ui.add_space(4.0);
+                ui.label(RichText::new("Supported configs").strong());
+                for config in &device.configs {
+                    ui.label(format!("{} ch, {} - {} Hz, {}", config.channels, config.min_sample_rate, config.max_sample_rate, config.sample_format));
+                }
+            }
+        }
+
+        ui.add_space(4.0);
+        if ui.button("Refresh").clicked() {
+            self.refresh(state);
+        }
+
+        if offset != state.editor.audio_offset() {
+            state.settings.audio_offset = offset;
+            state.editor.set_audio_offset(offset);
+        }
+
+        if output.as_ref() != state.editor.output() {
+            state.settings.audio_output = output.clone();
+            if let Err(err) = state.editor.set_output(output) {
+                error.show_error(&err);
+            }
+        }
+    }
+}
\ No newline at end of file
@@ -63,11 +63,9 @@ impl Window<(&mut State, &mut BindingManager<State, Identifier>, &Receiver<KeyCo
                             if ui.button("press any key").clicked() {
                                 *want_key = false;
                             }
-                        } else {
-                            if ui.button(&format!("{}", bind)).clicked() {
-                                *want_key = true;
-                                self.editing = Some((*category, *bind));
-                            }
+                        } else if ui.button(&format!("{}", bind)).clicked() {
+                            *want_key = true;
+                            self.editing = Some((*category, *bind));
                         }
                     });
 
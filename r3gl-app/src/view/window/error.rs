@@ -0,0 +1,53 @@
+use color_eyre::eyre::Report;
+use egui::{Align2, vec2};
+use wcore::{graphics::context::Context, egui::window::Window};
+
+pub struct ErrorWindow {
+    visible: bool,
+    message: String,
+}
+
+impl ErrorWindow {
+    pub fn new() -> Self {
+        return Self {
+            visible: false,
+            message: String::new(),
+        };
+    }
+
+    pub fn show_error(&mut self, error: &Report) {
+        self.message = format!("{:#}", error);
+        self.visible = true;
+    }
+}
+
+impl Window<()> for ErrorWindow {
+    type Title = &'static str;
+    fn title() -> Self::Title {
+        return "Error";
+    }
+
+    #[allow(unused_variables)]
+    fn build<'a>(window: egui::Window<'a>, ctx: &'_ egui::Context) -> egui::Window<'a> {
+        window
+            .anchor(Align2::CENTER_CENTER, vec2(0.0, 0.0))
+            .default_width(360.0)
+            .collapsible(false)
+            .resizable(false)
+            .title_bar(true)
+    }
+
+    fn set_visible(&mut self, value: bool) { self.visible = value; }
+    fn get_visible(&self) -> bool { return self.visible; }
+
+    #[allow(unused_variables)]
+    fn show(&mut self, state: (), view: &wgpu::TextureView, graphics: &mut Context, ui: &mut egui::Ui) {
+        ui.label(&self.message);
+        ui.add_space(4.0);
+        ui.vertical_centered(|ui| {
+            if ui.button("OK").clicked() {
+                self.visible = false;
+            }
+        });
+    }
+}
\ No newline at end of file
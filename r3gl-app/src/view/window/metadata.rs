@@ -0,0 +1,113 @@
+use egui::{Button, TextEdit};
+use wcore::{graphics::context::Context, egui::window::Window};
+
+use crate::{state::State, beatmap::beatmap::MetadataSection, command::metadata::ChangeMetadata};
+
+use super::error::ErrorWindow;
+
+pub struct MetadataWindow {
+    visible: bool,
+
+    // Copy being edited, starts over whenever the beatmap changes underneath it
+    base: Option<MetadataSection>,
+    draft: Option<MetadataSection>,
+    tags: String,
+}
+
+impl MetadataWindow {
+    pub fn new() -> Self {
+        return Self {
+            visible: false,
+
+            base: None,
+            draft: None,
+            tags: String::new(),
+        };
+    }
+
+    fn load(&mut self, metadata: &MetadataSection) {
+        self.tags = metadata.tags.join(" ");
+        self.base = Some(metadata.clone());
+        self.draft = Some(metadata.clone());
+    }
+}
+
+impl Window<(&mut State, &mut ErrorWindow)> for MetadataWindow {
+    type Title = &'static str;
+    fn title() -> Self::Title {
+        return "Metadata";
+    }
+
+    #[allow(unused_variables)]
+    fn build<'a>(window: egui::Window<'a>, ctx: &'_ egui::Context) -> egui::Window<'a> {
+        window
+            .default_pos([160.0, 160.0])
+            .default_width(320.0)
+            .collapsible(true)
+            .resizable(false)
+            .title_bar(true)
+    }
+
+    fn set_visible(&mut self, value: bool) { self.visible = value; }
+    fn get_visible(&self) -> bool { return self.visible; }
+
+    #[allow(unused_variables)]
+    fn show(&mut self, (state, error): (&mut State, &mut ErrorWindow), view: &wgpu::TextureView, graphics: &mut Context, ui: &mut egui::Ui) {
+        let current = match state.editor.beatmap() {
+            Some(beatmap) => beatmap.metadata.clone(),
+            None => {
+                ui.label("No project open");
+                return;
+            }
+        };
+
+        // Applied, undone, or another project
+        if self.base.as_ref() != Some(&current) {
+            self.load(&current);
+        }
+
+        let mut draft = self.draft.take().unwrap_or_else(|| current.clone());
+        egui::Grid::new("metadata_grid")
+          .num_columns(2)
+          .show(ui, |ui| {
+            for (name, value) in [
+                ("Title", &mut draft.title),
+                ("Title (unicode)", &mut draft.title_unicode),
+                ("Artist", &mut draft.artist),
+                ("Artist (unicode)", &mut draft.artist_unicode),
+                ("Creator", &mut draft.creator),
+                ("Difficulty", &mut draft.version),
+                ("Source", &mut draft.source),
+            ] {
+                ui.label(name);
+                ui.add(TextEdit::singleline(value));
+                ui.end_row();
+            }
+
+            ui.label("Tags");
+            ui.add(TextEdit::singleline(&mut self.tags));
+            ui.end_row();
+        });
+
+        draft.tags = self.tags.split_whitespace().map(str::to_owned).collect();
+
+        let changed = draft != current;
+        let mut reset = false;
+        ui.add_space(4.0);
+        ui.horizontal(|ui| {
+            if ui.add_enabled(changed, Button::new("Apply")).clicked() {
+                if let Err(err) = state.editor.execute(ChangeMetadata::new(draft.clone())) {
+                    error.show_error(&err);
+                }
+            }
+
+            reset = ui.add_enabled(changed, Button::new("Reset")).clicked();
+        });
+
+        if reset {
+            self.load(&current);
+        } else {
+            self.draft = Some(draft);
+        }
+    }
+}
\ No newline at end of file
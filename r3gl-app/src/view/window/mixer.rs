@@ -0,0 +1,132 @@
+use std::iter;
+
+use egui::{RichText, Slider, TextEdit};
+use r3gl_audio::{Bus, Track};
+use wcore::{graphics::context::Context, egui::window::Window};
+
+use crate::state::State;
+
+use super::error::ErrorWindow;
+
+const BUSES: [(Bus, &str); 3] = [(Bus::Song, "Song"), (Bus::Hitsounds, "Hitsounds"), (Bus::Metronome, "Metronome")];
+
+pub struct MixerWindow {
+    visible: bool,
+    path: String, // Stem to add
+}
+
+impl MixerWindow {
+    pub fn new() -> Self {
+        return Self {
+            visible: false,
+            path: String::new(),
+        };
+    }
+}
+
+impl Window<(&mut State, &mut ErrorWindow)> for MixerWindow {
+    type Title = &'static str;
+    fn title() -> Self::Title {
+        return "Mixer";
+    }
+
+    #[allow(unused_variables)]
+    fn build<'a>(window: egui::Window<'a>, ctx: &'_ egui::Context) -> egui::Window<'a> {
+        window
+            .default_pos([64.0, 320.0])
+            .default_width(420.0)
+            .collapsible(true)
+            .resizable(false)
+            .title_bar(true)
+    }
+
+    fn set_visible(&mut self, value: bool) { self.visible = value; }
+    fn get_visible(&self) -> bool { return self.visible; }
+
+    #[allow(unused_variables)]
+    fn show(&mut self, (state, error): (&mut State, &mut ErrorWindow), view: &wgpu::TextureView, graphics: &mut Context, ui: &mut egui::Ui) {
+        // Master and buses go into the settings
+        let mut master = state.editor.get_volume();
+        egui::Grid::new("mixer_buses")
+          .num_columns(3)
+          .spacing([16.0, 4.0])
+          .show(ui, |ui| {
+            ui.label("Master");
+            ui.add(Slider::new(&mut master, 0.0 ..= 1.0));
+            ui.end_row();
+
+            for (bus, name) in BUSES {
+                let mut mix = state.editor.bus(bus);
+                ui.label(name);
+                ui.add(Slider::new(&mut mix.volume, 0.0 ..= 1.0));
+                ui.checkbox(&mut mix.mute, "Mute");
+                ui.end_row();
+
+                if mix != state.editor.bus(bus) {
+                    state.settings.mix.set_bus(bus, mix.into());
+                    state.editor.set_bus(bus, mix);
+                }
+            }
+        });
+
+        if master != state.editor.get_volume() {
+            state.settings.mix.master = master;
+            state.editor.set_volume(master);
+        }
+
+        // Tracks go into the project
+        ui.separator();
+        ui.label(RichText::new("Tracks").strong());
+        ui.set_enabled(state.projects.current.is_some());
+
+        let stems = state.editor.stems().map(|(track, path)| {
+            let name = path.file_name().map_or(path.to_string_lossy(), |name| name.to_string_lossy());
+            (track, name.into_owned())
+        });
+        let tracks: Vec<(Track, String)> = iter::once((Track::Song, String::from("Song"))).chain(stems).collect();
+
+        let mut remove = None;
+        egui::Grid::new("mixer_tracks")
+          .num_columns(6)
+          .spacing([16.0, 4.0])
+          .show(ui, |ui| {
+            ui.label("");
+            ui.label("Gain");
+            ui.label("Pan");
+            ui.end_row();
+
+            for (track, name) in tracks {
+                let mut mix = state.editor.track_mix(track);
+                ui.label(name);
+                ui.add(Slider::new(&mut mix.gain, 0.0 ..= 2.0));
+                ui.add(Slider::new(&mut mix.pan, -1.0 ..= 1.0));
+                ui.checkbox(&mut mix.mute, "M");
+                ui.checkbox(&mut mix.solo, "S");
+                if track != Track::Song && ui.small_button("✖").clicked() {
+                    remove = Some(track);
+                }
+
+                ui.end_row();
+
+                if mix != state.editor.track_mix(track) {
+                    state.editor.set_track_mix(track, mix, &mut state.projects);
+                }
+            }
+        });
+
+        if let Some(track) = remove {
+            state.editor.remove_stem(track, &mut state.projects);
+        }
+
+        ui.add_space(4.0);
+        ui.horizontal(|ui| {
+            ui.add(TextEdit::singleline(&mut self.path).hint_text("Stem file").desired_width(280.0));
+            if ui.button("Add").clicked() {
+                match state.editor.add_stem(&self.path, &mut state.projects) {
+                    Ok(()) => self.path.clear(),
+                    Err(err) => error.show_error(&err),
+                }
+            }
+        });
+    }
+}
\ No newline at end of file
@@ -1,3 +1,10 @@
 pub mod startup;
 pub mod timeline;
-pub mod bindings;
\ No newline at end of file
+pub mod bindings;
+pub mod error;
+pub mod save_as;
+pub mod tools;
+pub mod audio;
+pub mod timing;
+pub mod mixer;
+pub mod metadata;
\ No newline at end of file
@@ -0,0 +1,67 @@
+use egui::{Align2, vec2, TextEdit};
+use wcore::{graphics::context::Context, egui::window::Window};
+
+use crate::state::State;
+
+use super::error::ErrorWindow;
+
+pub struct SaveAsWindow {
+    visible: bool,
+    path: String,
+}
+
+impl SaveAsWindow {
+    pub fn new() -> Self {
+        return Self {
+            visible: false,
+            path: String::new(),
+        };
+    }
+
+    pub fn open(&mut self, state: &State) {
+        if let Some(project) = &state.projects.current {
+            self.path = project.path.to_string_lossy().into_owned();
+        }
+
+        self.visible = true;
+    }
+}
+
+impl Window<(&mut State, &mut ErrorWindow)> for SaveAsWindow {
+    type Title = &'static str;
+    fn title() -> Self::Title {
+        return "Save As";
+    }
+
+    #[allow(unused_variables)]
+    fn build<'a>(window: egui::Window<'a>, ctx: &'_ egui::Context) -> egui::Window<'a> {
+        window
+            .anchor(Align2::CENTER_CENTER, vec2(0.0, 0.0))
+            .default_width(480.0)
+            .collapsible(false)
+            .resizable(false)
+            .title_bar(true)
+    }
+
+    fn set_visible(&mut self, value: bool) { self.visible = value; }
+    fn get_visible(&self) -> bool { return self.visible; }
+
+    #[allow(unused_variables)]
+    fn show(&mut self, (state, error): (&mut State, &mut ErrorWindow), view: &wgpu::TextureView, graphics: &mut Context, ui: &mut egui::Ui) {
+        ui.add(TextEdit::singleline(&mut self.path).desired_width(f32::INFINITY));
+        ui.add_space(4.0);
+        ui.horizontal(|ui| {
+            if ui.button("Save").clicked() {
+                if let Err(err) = state.editor.save_project_as(&self.path, &mut state.projects) {
+                    error.show_error(&err);
+                } else {
+                    self.visible = false;
+                }
+            }
+
+            if ui.button("Cancel").clicked() {
+                self.visible = false;
+            }
+        });
+    }
+}
\ No newline at end of file
@@ -4,6 +4,8 @@ use str_macro::str;
 
 use crate::state::State;
 
+use super::error::ErrorWindow;
+
 pub struct StartupWindow {
     visible: bool
 }
@@ -16,7 +18,7 @@ impl StartupWindow {
     }
 }
 
-impl Window<&mut State> for StartupWindow {
+impl Window<(&mut State, &mut ErrorWindow)> for StartupWindow {
     type Title = &'static str;
     fn title() -> Self::Title {
         return "Startup";
@@ -37,7 +39,7 @@ impl Window<&mut State> for StartupWindow {
 
     #[allow(unused_variables)]
     #[allow(unused_must_use)] // Until I figure out a better way to precalculate width
-    fn show(&mut self, state: &mut State, view: &wgpu::TextureView, graphics: &mut Context, ui: &mut egui::Ui) {
+    fn show(&mut self, (state, error): (&mut State, &mut ErrorWindow), view: &wgpu::TextureView, graphics: &mut Context, ui: &mut egui::Ui) {
         ui.vertical_centered(|ui| {
             ui.group(|ui| {
                 ui.label(RichText::new("r3gl-client").heading().strong());
@@ -97,7 +99,9 @@ impl Window<&mut State> for StartupWindow {
 
                     for (button, path) in recent {
                         if button.clicked() {
-                            state.editor.open_project(&path, &mut state.projects);
+                            if let Err(err) = state.editor.open_project(&path, &mut state.projects) {
+                                error.show_error(&err);
+                            }
                         }
                     }
                 });
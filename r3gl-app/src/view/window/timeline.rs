@@ -1,14 +1,40 @@
-use egui::{Align2, vec2, Button, Slider};
+use std::path::PathBuf;
+
+use egui::{Align2, vec2, pos2, Button, Slider, ComboBox, Color32, ColorImage, Rect, Rgba, Sense, Shape, Stroke, TextureFilter, TextureHandle};
+use log::error;
 use wcore::{graphics::context::Context, egui::window::Window};
 
-use crate::state::State;
+use crate::{state::State, beatmap::{Time, component::adapter::taiko::TaikoVariantAdapter}, editor::{SNAP_DIVISORS, PLAYBACK_RATES}, screen::taiko::{object_color, object_end}};
 
 const OFFSET: f32 = 12.0;
 
+// Zoom limits of the waveform, in visible ms
+const MIN_SPAN: f64 = 500.0;
+const MAX_SPAN: f64 = 60000.0;
+
+const WAVE_COLOR: Color32 = Color32::from_rgb(70, 110, 160);
+const RMS_COLOR: Color32 = Color32::from_rgb(140, 190, 240);
+
+// What the spectrogram texture shows, it's only redrawn when this changes
+#[derive(PartialEq)]
+struct SpectrogramView {
+    project: PathBuf,
+    start: f64,
+    ms_per_px: f64,
+    width: usize,
+    height: usize,
+}
+
 pub struct TimelineWindow {
     visible: bool,
 
     was_playing: bool,
+
+    // Waveform
+    span: f64,           // Visible ms
+    center: Option<f64>, // Follows the playhead when `None`
+    spectrogram: bool,
+    texture: Option<(TextureHandle, SpectrogramView)>,
 }
 
 impl TimelineWindow {
@@ -16,8 +42,134 @@ impl TimelineWindow {
         return Self {
             visible: true,
             was_playing: false,
+
+            span: 4000.0,
+            center: None,
+            spectrogram: false,
+            texture: None,
         };
     }
+
+    fn show_waveform(&mut self, state: &mut State, ui: &mut egui::Ui) {
+        let (rect, response) = ui.allocate_exact_size(ui.available_size(), Sense::click_and_drag());
+        let now = state.editor.get_time().as_ms() as f64;
+        let ms_per_px = self.span / rect.width().max(1.0) as f64;
+
+        // Playback takes the view back to the playhead
+        if !state.editor.is_paused() {
+            self.center = None;
+        }
+
+        // Scrolling and dragging move the view, ctrl + scroll zooms
+        if response.hovered() {
+            let (scroll, zoom) = {
+                let input = ui.input();
+                (input.scroll_delta, input.zoom_delta())
+            };
+
+            self.span = (self.span / zoom as f64).clamp(MIN_SPAN, MAX_SPAN);
+            if scroll.x != 0.0 || scroll.y != 0.0 {
+                self.center = Some(self.center.unwrap_or(now) - (scroll.x + scroll.y) as f64 * ms_per_px);
+            }
+        }
+
+        if response.dragged() {
+            self.center = Some(self.center.unwrap_or(now) - response.drag_delta().x as f64 * ms_per_px);
+        }
+
+        let start = self.center.unwrap_or(now) - self.span / 2.0;
+        let end = start + self.span;
+        let to_x = |time: f64| rect.left() + ((time - start) / ms_per_px) as f32;
+
+        // Clicking seeks
+        if response.clicked() && state.projects.current.is_some() {
+            if let Some(position) = response.interact_pointer_pos() {
+                let time = start + (position.x - rect.left()) as f64 * ms_per_px;
+                state.editor.set_time(time.clamp(0.0, state.editor.get_length() as f64) as u32);
+            }
+        }
+
+        let painter = ui.painter_at(rect);
+        painter.rect_filled(rect, 0.0, Color32::from_gray(16));
+
+        let middle = rect.center().y;
+        let (width, height) = (rect.width() as usize, rect.height() as usize);
+        if let Some(summary) = state.editor.summary() {
+            match (&summary.spectrogram, self.spectrogram) {
+                (Some(spectrogram), true) if width > 0 && height > 0 => {
+                    let view = SpectrogramView {
+                        project: state.projects.current.as_ref().map_or(PathBuf::new(), |project| project.path.clone()),
+                        start, ms_per_px, width, height,
+                    };
+
+                    if self.texture.as_ref().map_or(true, |(_, drawn)| *drawn != view) {
+                        let mut image = ColorImage::new([width, height], Color32::BLACK);
+                        for x in 0 .. width {
+                            let column = match spectrogram.column_at((start + x as f64 * ms_per_px) / 1000.0) {
+                                Some(column) => column,
+                                None => continue,
+                            };
+
+                            // Log scale, so low frequencies get some room
+                            for y in 0 .. height {
+                                let bin = (column.len() as f32).powf(1.0 - y as f32 / height as f32) as usize;
+                                let value = column[bin.clamp(1, column.len()) - 1] as u32;
+                                image.pixels[y * width + x] = Color32::from_rgb(value as u8, (value * value / 255) as u8, (value / 3) as u8);
+                            }
+                        }
+
+                        self.texture = Some(match self.texture.take() {
+                            Some((mut texture, _)) => {
+                                texture.set(image, TextureFilter::Linear);
+                                (texture, view)
+                            }
+
+                            None => (ui.ctx().load_texture("spectrogram", image, TextureFilter::Linear), view),
+                        });
+                    }
+
+                    if let Some((texture, _)) = &self.texture {
+                        painter.add(Shape::image(texture.id(), rect, Rect::from_min_max(pos2(0.0, 0.0), pos2(1.0, 1.0)), Color32::WHITE));
+                    }
+                }
+
+                _ => {
+                    let half = rect.height() / 2.0;
+                    for (x, peak) in summary.waveform.peaks(start / 1000.0, end / 1000.0, width).iter().enumerate() {
+                        let x = rect.left() + x as f32 + 0.5;
+                        painter.line_segment([pos2(x, middle - peak.max * half), pos2(x, middle - peak.min * half)], Stroke::new(1.0, WAVE_COLOR));
+                        painter.line_segment([pos2(x, middle - peak.rms * half), pos2(x, middle + peak.rms * half)], Stroke::new(1.0, RMS_COLOR));
+                    }
+                }
+            }
+        }
+
+        // Objects on top, drumrolls and dendens as long bars
+        if let Some(objects) = state.editor.hitobjects() {
+            for (_, object) in objects.iter() {
+                let (time, until) = match (object.time(), object_end(object)) {
+                    (Some(time), Some(until)) => (time.0.as_ms() as f64, until.as_ms() as f64),
+                    _ => continue,
+                };
+
+                if until < start || time > end {
+                    continue;
+                }
+
+                let [r, g, b, _] = object_color(object);
+                let color: Color32 = Rgba::from_rgb(r, g, b).into();
+                let radius = if object.variant().map_or(false, |x| x.is_big()) { 7.0 } else { 5.0 };
+                if until > time {
+                    painter.line_segment([pos2(to_x(time), middle), pos2(to_x(until), middle)], Stroke::new(radius * 2.0, color));
+                }
+
+                painter.circle_filled(pos2(to_x(time), middle), radius, color);
+            }
+        }
+
+        // Playhead
+        painter.line_segment([pos2(to_x(now), rect.top()), pos2(to_x(now), rect.bottom())], Stroke::new(1.0, Color32::WHITE));
+    }
 }
 
 impl Window<&mut State> for TimelineWindow {
@@ -45,6 +197,11 @@ impl Window<&mut State> for TimelineWindow {
     fn show(&mut self, state: &mut State, view: &wgpu::TextureView, graphics: &mut Context, ui: &mut egui::Ui) {
         let time = state.editor.get_time().as_ms();
         let length = state.editor.get_length();
+        let mut snapping = state.editor.is_snapping();
+        let mut divisor = state.editor.snap_divisor();
+        let mut rate = state.editor.get_rate();
+        let mut preserve_pitch = state.editor.is_preserving_pitch();
+        let mut metronome = state.editor.is_metronome();
         
         ui.horizontal(|ui| {
             ui.set_enabled(state.projects.current.is_some());
@@ -61,6 +218,35 @@ impl Window<&mut State> for TimelineWindow {
                   time / (60 * 1000),   time / 1000 % 60,   time % 1000,
                 length / (60 * 1000), length / 1000 % 60, length % 1000));
 
+            // Tempo display
+            if let Some(bpm) = state.editor.timing_point_at(Time::from_ms(time)).and_then(|point| point.bpm()) {
+                ui.label(&format!("{:.2} BPM", bpm));
+            }
+
+            // Snapping
+            ui.checkbox(&mut snapping, "Snap");
+            ComboBox::from_id_source("snap_divisor")
+                .width(48.0)
+                .selected_text(format!("1/{}", divisor))
+                .show_ui(ui, |ui| {
+                    for value in SNAP_DIVISORS {
+                        ui.selectable_value(&mut divisor, value, format!("1/{}", value));
+                    }
+                });
+
+            // Playback rate
+            ComboBox::from_id_source("playback_rate")
+                .width(48.0)
+                .selected_text(format!("{}x", rate))
+                .show_ui(ui, |ui| {
+                    for value in PLAYBACK_RATES {
+                        ui.selectable_value(&mut rate, value, format!("{}x", value));
+                    }
+                });
+            ui.checkbox(&mut preserve_pitch, "Keep pitch");
+            ui.checkbox(&mut self.spectrogram, "Spectrogram");
+            ui.checkbox(&mut metronome, "Metronome");
+
             // Time slider
             let slider_width = ui.available_width();
             let style = ui.style_mut();
@@ -79,11 +265,37 @@ impl Window<&mut State> for TimelineWindow {
                 state.editor.set_time(time64 as u32);
             }
 
-            if slider.drag_released() {
-                if state.projects.current.is_some() {
-                    state.editor.set_paused(self.was_playing);
-                }
+            if slider.drag_released() && state.projects.current.is_some() {
+                state.editor.set_paused(self.was_playing);
+            }
+        });
+
+        self.show_waveform(state, ui);
+
+        if snapping != state.editor.is_snapping() {
+            state.editor.set_snapping(snapping);
+        }
+
+        if divisor != state.editor.snap_divisor() {
+            if let Err(err) = state.editor.set_snap_divisor(divisor) {
+                error!("{:#}", err);
+            }
+        }
+
+        if metronome != state.editor.is_metronome() {
+            state.editor.set_metronome(metronome);
+        }
+
+        if preserve_pitch != state.editor.is_preserving_pitch() {
+            if let Err(err) = state.editor.set_preserving_pitch(preserve_pitch) {
+                error!("{:#}", err);
+            }
+        }
+
+        if rate != state.editor.get_rate() {
+            if let Err(err) = state.editor.set_rate(rate) {
+                error!("{:#}", err);
             }
-        }); 
+        }
     }
 }
\ No newline at end of file
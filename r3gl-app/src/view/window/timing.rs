@@ -0,0 +1,153 @@
+use egui::{DragValue, RichText};
+use wcore::{graphics::context::Context, egui::window::Window};
+
+use crate::{state::State, beatmap::timing::TimingPoint, command::timing::{AddTimingPoint, RemoveTimingPoint, ChangeTimingPoint}};
+
+use super::error::ErrorWindow;
+
+pub struct TimingWindow {
+    visible: bool,
+
+    // Point about to be added, or the new values of the one being edited
+    time: f64,
+    bpm: f64,
+    meter: u32,
+
+    // Found again by value, indices move whenever points are added or removed
+    editing: Option<TimingPoint>,
+}
+
+impl TimingWindow {
+    pub fn new() -> Self {
+        return Self {
+            visible: false,
+
+            time: 0.0,
+            bpm: 120.0,
+            meter: 4,
+
+            editing: None,
+        };
+    }
+}
+
+impl Window<(&mut State, &mut ErrorWindow)> for TimingWindow {
+    type Title = &'static str;
+    fn title() -> Self::Title {
+        return "Timing";
+    }
+
+    #[allow(unused_variables)]
+    fn build<'a>(window: egui::Window<'a>, ctx: &'_ egui::Context) -> egui::Window<'a> {
+        window
+            .default_pos([160.0, 320.0])
+            .default_width(280.0)
+            .collapsible(true)
+            .resizable(false)
+            .title_bar(true)
+    }
+
+    fn set_visible(&mut self, value: bool) { self.visible = value; }
+    fn get_visible(&self) -> bool { return self.visible; }
+
+    #[allow(unused_variables)]
+    fn show(&mut self, (state, error): (&mut State, &mut ErrorWindow), view: &wgpu::TextureView, graphics: &mut Context, ui: &mut egui::Ui) {
+        ui.set_enabled(state.projects.current.is_some());
+
+        // Uninherited points, inherited ones don't change the tempo
+        let mut remove = None;
+        if let Some(timing) = state.editor.timing() {
+            egui::Grid::new("timing_grid")
+              .num_columns(4)
+              .striped(true)
+              .show(ui, |ui| {
+                for (index, point) in timing.points().iter().enumerate().filter(|(_, point)| point.uninherited) {
+                    // Picking a point loads it into the fields below
+                    let selected = self.editing.as_ref() == Some(point);
+                    if ui.selectable_label(selected, format!("{:.0} ms", point.time)).clicked() {
+                        self.editing = if selected { None } else { Some(point.clone()) };
+                        self.time = point.time;
+                        self.bpm = point.bpm().unwrap_or(self.bpm);
+                        self.meter = point.meter;
+                    }
+
+                    ui.label(format!("{:.2} BPM", point.bpm().unwrap_or_default()));
+                    ui.label(format!("{}/4", point.meter));
+                    if ui.small_button("✖").clicked() {
+                        remove = Some(index);
+                    }
+
+                    ui.end_row();
+                }
+            });
+        }
+
+        if let Some(index) = remove {
+            if let Err(err) = state.editor.execute(RemoveTimingPoint::new(index)) {
+                error.show_error(&err);
+            }
+        }
+
+        // Gone after an undo or a removal
+        let editing = self.editing.as_ref().and_then(|editing| {
+            state.editor.timing()?.points().iter().position(|point| point == editing)
+        });
+
+        if editing.is_none() {
+            self.editing = None;
+        }
+
+        ui.separator();
+        ui.horizontal(|ui| {
+            ui.add(DragValue::new(&mut self.time).speed(1.0).clamp_range(0.0 ..= f64::MAX).suffix(" ms"));
+            if ui.button("Now").clicked() {
+                self.time = state.editor.get_time().as_ms() as f64;
+            }
+
+            ui.add(DragValue::new(&mut self.bpm).speed(0.1).clamp_range(1.0 ..= 1000.0).suffix(" BPM"));
+            ui.add(DragValue::new(&mut self.meter).clamp_range(1 ..= 16).suffix("/4"));
+            if ui.button("Add").clicked() {
+                if let Err(err) = state.editor.execute(AddTimingPoint::new(TimingPoint::uninherited(self.time, self.bpm, self.meter))) {
+                    error.show_error(&err);
+                }
+            }
+
+            // Volume, sample set and effects stay as they were
+            if let (Some(index), Some(point)) = (editing, &self.editing) {
+                if ui.button("Apply").clicked() {
+                    let changed = TimingPoint {
+                        time: self.time,
+                        beat_length: 60000.0 / self.bpm,
+                        meter: self.meter,
+                        ..point.clone()
+                    };
+
+                    match state.editor.execute(ChangeTimingPoint::new(index, changed.clone())) {
+                        Ok(()) => self.editing = Some(changed),
+                        Err(err) => error.show_error(&err),
+                    }
+                }
+            }
+        });
+
+        // Detected from the song, picking one fills in the new point
+        ui.add_space(4.0);
+        ui.label(RichText::new("Detected").strong());
+        match state.editor.summary() {
+            Some(summary) if summary.tempo.is_empty() => { ui.label("No clear tempo"); }
+            Some(summary) => {
+                for candidate in &summary.tempo {
+                    ui.horizontal(|ui| {
+                        ui.label(format!("{:.2} BPM at {:.0} ms ({:.0}%)", candidate.bpm, candidate.offset, candidate.confidence * 100.0));
+                        if ui.small_button("Use").clicked() {
+                            self.bpm = (candidate.bpm * 100.0).round() / 100.0;
+                            self.time = candidate.offset.round();
+                        }
+                    });
+                }
+            }
+
+            None => { ui.label("Analyzing..."); }
+        }
+    }
+}
\ No newline at end of file
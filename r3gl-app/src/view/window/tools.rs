@@ -0,0 +1,59 @@
+use wcore::{graphics::context::Context, egui::window::Window};
+
+use crate::{state::State, tool::Tool};
+
+pub struct ToolsWindow {
+    visible: bool,
+}
+
+impl ToolsWindow {
+    pub fn new() -> Self {
+        return Self {
+            visible: true,
+        };
+    }
+}
+
+impl Window<&mut State> for ToolsWindow {
+    type Title = &'static str;
+    fn title() -> Self::Title {
+        return "Tools";
+    }
+
+    #[allow(unused_variables)]
+    fn build<'a>(window: egui::Window<'a>, ctx: &'_ egui::Context) -> egui::Window<'a> {
+        window
+            .default_pos([12.0, 320.0])
+            .default_width(120.0)
+            .collapsible(true)
+            .resizable(false)
+            .title_bar(true)
+    }
+
+    fn set_visible(&mut self, value: bool) { self.visible = value; }
+    fn get_visible(&self) -> bool { return self.visible; }
+
+    #[allow(unused_variables)]
+    fn show(&mut self, state: &mut State, view: &wgpu::TextureView, graphics: &mut Context, ui: &mut egui::Ui) {
+        let mut tool = state.editor.tool();
+        let mut finisher = state.editor.is_finisher();
+
+        ui.set_enabled(state.projects.current.is_some());
+        ui.vertical(|ui| {
+            for value in Tool::ALL {
+                ui.selectable_value(&mut tool, value, value.name());
+            }
+
+            ui.separator();
+            ui.checkbox(&mut finisher, "Finisher");
+        });
+
+        if tool != state.editor.tool() {
+            state.editor.set_tool(tool);
+        }
+
+        if finisher != state.editor.is_finisher() {
+            state.editor.set_finisher(finisher);
+        }
+    }
+}
\ No newline at end of file
@@ -0,0 +1,33 @@
+// Not every test uses every helper
+#![allow(dead_code)]
+
+use std::fs;
+use std::path::{Path, PathBuf};
+
+pub fn temp_dir(name: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("r3gl-app-{}", std::process::id())).join(name);
+    fs::create_dir_all(&dir).unwrap();
+    return dir;
+}
+
+// 32-bit float WAV
+pub fn write_wav(path: &Path, sample_rate: u32, channel_count: usize, samples: &[f32]) {
+    let block_align = channel_count as u16 * 4;
+    let data_size = samples.len() as u32 * 4;
+
+    let mut data = vec![];
+    data.extend(b"RIFF");
+    data.extend((36 + data_size).to_le_bytes());
+    data.extend(b"WAVEfmt ");
+    data.extend(16u32.to_le_bytes());
+    data.extend(3u16.to_le_bytes());
+    data.extend((channel_count as u16).to_le_bytes());
+    data.extend(sample_rate.to_le_bytes());
+    data.extend((sample_rate * block_align as u32).to_le_bytes());
+    data.extend(block_align.to_le_bytes());
+    data.extend(32u16.to_le_bytes());
+    data.extend(b"data");
+    data.extend(data_size.to_le_bytes());
+    data.extend(samples.iter().flat_map(|sample| sample.to_le_bytes()));
+    fs::write(path, data).unwrap();
+}
\ No newline at end of file
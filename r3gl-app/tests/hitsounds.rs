@@ -0,0 +1,115 @@
+#![allow(clippy::needless_return)]
+
+use std::fs;
+use std::path::Path;
+use std::thread;
+use std::time::Duration;
+
+use r3gl_app::{editor::Editor, project::project_manager::ProjectManager};
+use r3gl_audio::{Audio, Drive, WavBackend};
+
+mod common;
+
+const SAMPLE_RATE: u32 = 48000;
+const CALLBACK_FRAMES: usize = 480;
+
+// Every sample is a short flat step, so what played can be told apart by how loud it is
+const NORMAL: f32 = 0.1;
+const CLAP: f32 = 0.2;
+const FINISH: f32 = 0.4;
+
+const BEATMAP: &str = "osu file format v14
+
+[General]
+AudioFilename: song.wav
+Mode: 1
+
+[Metadata]
+Title:Hitsounds
+Artist:Test
+
+[Difficulty]
+SliderMultiplier:1.4
+SliderTickRate:1
+
+[TimingPoints]
+1000,500,4,1,0,100,1,0
+
+[HitObjects]
+256,192,1000,1,0,0:0:0:0:
+256,192,1500,1,8,0:0:0:0:
+256,192,2000,1,4,0:0:0:0:
+256,192,3000,2,4,L|396:192,1,140
+256,192,4000,12,0,5000,0:0:0:0:
+";
+
+// Recording is stereo, only the left channel is kept
+fn read_wav(path: &Path) -> Vec<f32> {
+    let data = fs::read(path).unwrap();
+    return data[44 ..].chunks_exact(8).map(|frame| f32::from_le_bytes(frame[.. 4].try_into().unwrap())).collect();
+}
+
+// Song time in ms and how loud it got for every sound that started
+fn onsets(samples: &[f32]) -> Vec<(f64, f32)> {
+    let mut onsets = vec![];
+    let mut previous = 0.0;
+    for (frame, sample) in samples.iter().enumerate() {
+        if *sample > previous + 0.01 {
+            onsets.push((frame as f64 * 1000.0 / SAMPLE_RATE as f64, *sample));
+        }
+
+        previous = *sample;
+    }
+
+    return onsets;
+}
+
+#[test]
+fn plays_every_object_kind() {
+    let project = common::temp_dir("hitsounds-project");
+    let skin = common::temp_dir("hitsounds-skin");
+    let recording = common::temp_dir("hitsounds-recording").join("output.wav");
+
+    fs::write(project.join("map.osu"), BEATMAP).unwrap();
+    common::write_wav(&project.join("song.wav"), SAMPLE_RATE, 2, &vec![0.0; SAMPLE_RATE as usize * 6 * 2]);
+    for (name, level) in [("normal-hitnormal", NORMAL), ("normal-hitclap", CLAP), ("normal-hitfinish", FINISH)] {
+        common::write_wav(&skin.join(format!("{}.wav", name)), SAMPLE_RATE, 1, &[level; 96]);
+    }
+
+    let backend = WavBackend::new(&recording, SAMPLE_RATE, 2, Drive::Manual).unwrap();
+    let handle = backend.handle();
+    let mut editor = Editor::with_audio(Audio::with_backend(backend).unwrap());
+    editor.set_skin_dir(Some(skin));
+    editor.open_project(project.join("map.osu"), &mut ProjectManager::default()).unwrap();
+    editor.set_paused(false);
+
+    // Callbacks only play as far as the decoder got, the rest of them is silence
+    let frame_at = |editor: &Editor| (editor.audio_time().as_secs_f64() * SAMPLE_RATE as f64).round() as usize;
+    let mut played = vec![];
+    while frame_at(&editor) < SAMPLE_RATE as usize * 11 / 2 {
+        editor.update();
+        let start = frame_at(&editor);
+        handle.advance(CALLBACK_FRAMES);
+        played.push(frame_at(&editor) - start);
+
+        if played.last() < Some(&CALLBACK_FRAMES) {
+            thread::sleep(Duration::from_millis(1));
+        }
+    }
+
+    drop(editor);
+    let output = read_wav(&recording);
+    let song: Vec<f32> = output.chunks(CALLBACK_FRAMES).zip(&played).flat_map(|(chunk, played)| &chunk[.. *played]).copied().collect();
+
+    // Quarter beat drumroll ticks with the finisher on the first one, the denden on its end
+    let mut expected = vec![(1000.0, NORMAL), (1500.0, CLAP), (2000.0, NORMAL + FINISH), (3000.0, NORMAL + FINISH)];
+    expected.extend([3125.0, 3250.0, 3375.0, 3500.0].map(|time| (time, NORMAL)));
+    expected.push((5000.0, NORMAL));
+
+    let found = onsets(&song);
+    assert_eq!(found.len(), expected.len(), "sounds at {found:?}");
+    for ((time, level), (expected_time, expected_level)) in found.iter().zip(&expected) {
+        assert!((time - expected_time).abs() <= 1.0 / 48.0, "sound at {time} ms, expected {expected_time} ms");
+        assert!((level - expected_level).abs() < 0.01, "{level} at {time} ms, expected {expected_level}");
+    }
+}
\ No newline at end of file
@@ -0,0 +1,32 @@
+#![allow(clippy::needless_return)]
+
+use std::fs;
+use std::path::PathBuf;
+
+use r3gl_app::{editor::Editor, project::project_manager::ProjectManager};
+use r3gl_audio::{Audio, Drive, NullBackend};
+
+mod common;
+
+fn project(name: &str, title: &str) -> PathBuf {
+    let dir = common::temp_dir(name);
+    let path = dir.join("map.osu");
+    fs::write(&path, format!("osu file format v14\n\n[General]\nAudioFilename: song.wav\nMode: 1\n\n[Metadata]\nTitle:{}\nArtist:Test\n", title)).unwrap();
+    return path;
+}
+
+#[test]
+fn missing_song_keeps_the_open_project() {
+    let first = project("open-first", "First");
+    common::write_wav(&first.with_file_name("song.wav"), 48000, 2, &vec![0.0; 48000]);
+    let second = project("open-second", "Second");
+
+    let mut editor = Editor::with_audio(Audio::with_backend(NullBackend::new(48000, 2, Drive::Manual)).unwrap());
+    let mut projects = ProjectManager::default();
+    editor.open_project(&first, &mut projects).unwrap();
+
+    assert!(editor.open_project(&second, &mut projects).is_err());
+    assert_eq!(editor.beatmap().unwrap().metadata.title, "First");
+    assert!(projects.current.is_some());
+    assert_eq!(projects.recent.iter().map(|info| info.path.clone()).collect::<Vec<_>>(), vec![first]);
+}
\ No newline at end of file
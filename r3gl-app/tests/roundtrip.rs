@@ -0,0 +1,61 @@
+#![allow(clippy::needless_return)]
+
+use std::fs;
+use std::path::PathBuf;
+
+use r3gl_app::beatmap::{parser, writer, component::adapter::taiko::TaikoVariantAdapter};
+
+fn fixture(name: &str) -> String {
+    let path = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures").join(name);
+    return fs::read_to_string(path).unwrap();
+}
+
+fn roundtrip(name: &str) {
+    let data = fixture(name);
+    let (beatmap, objects) = parser::parse(&data).unwrap();
+    assert_eq!(writer::write(&beatmap, &objects), data);
+}
+
+// Lines with their endings, so a changed ending shows up too
+fn changed_lines<'a>(before: &'a str, after: &'a str) -> Vec<(&'a str, &'a str)> {
+    let before: Vec<&str> = before.split_inclusive('\n').collect();
+    let after: Vec<&str> = after.split_inclusive('\n').collect();
+    assert_eq!(before.len(), after.len());
+    return before.into_iter().zip(after).filter(|(a, b)| a != b).collect();
+}
+
+#[test]
+fn keeps_crlf_file() {
+    roundtrip("crlf.osu");
+}
+
+#[test]
+fn keeps_lf_file() {
+    roundtrip("lf.osu");
+}
+
+#[test]
+fn only_rewrites_edited_object() {
+    for (name, time, before, after) in [
+        ("crlf.osu", 1666, "256,192,1666,5,4,0:0:0:0:\r\n", "256,192,1666,1,12,0:0:0:0:\r\n"),
+        ("lf.osu", 461, "256,192,461,1,4,0:0:0:0:\n", "256,192,461,1,12,0:0:0:0:\n"),
+    ] {
+        let data = fixture(name);
+        let (beatmap, mut objects) = parser::parse(&data).unwrap();
+        let id = objects.iter().find(|(_, object)| object.time().unwrap().0.as_ms() == time).unwrap().0;
+        objects.get_mut(id).unwrap().variant_mut().unwrap().switch_color();
+
+        let written = writer::write(&beatmap, &objects);
+        assert_eq!(changed_lines(&data, &written), vec![(before, after)]);
+    }
+}
+
+#[test]
+fn keeps_legacy_editor_keys_in_place() {
+    let data = fixture("crlf.osu");
+    let (mut beatmap, objects) = parser::parse(&data).unwrap();
+    beatmap.editor.bookmarks.push(3000);
+
+    let written = writer::write(&beatmap, &objects);
+    assert_eq!(changed_lines(&data, &written), vec![("EditorBookmarks: 1000,2000\r\n", "EditorBookmarks: 1000,2000,3000\r\n")]);
+}
\ No newline at end of file
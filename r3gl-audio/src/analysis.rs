@@ -0,0 +1,118 @@
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::thread;
+
+use color_eyre::eyre::{Report, Result};
+use crossbeam::channel::{bounded, Receiver, TryRecvError};
+
+use crate::{AudioData, source::AudioFile, spectrogram::{Spectrogram, SpectrogramBuilder}, tempo::{OnsetBuilder, TempoCandidate}, waveform::{Waveform, WaveformBuilder}};
+
+// Everything the editor wants to know about a song up front
+#[derive(Debug, Clone)]
+pub struct Summary {
+    pub waveform    : Waveform,
+    pub spectrogram : Option<Spectrogram>,
+    pub tempo       : Vec<TempoCandidate>, // Most likely first
+}
+
+impl Summary {
+    pub fn new(data: &AudioData, spectrogram: bool) -> Summary {
+        let mut builder = SummaryBuilder::new(data.sample_rate, spectrogram);
+        builder.push(&data.samples);
+        return builder.finish();
+    }
+}
+
+struct SummaryBuilder {
+    waveform    : WaveformBuilder,
+    spectrogram : Option<SpectrogramBuilder>,
+    onsets      : OnsetBuilder,
+    mono        : Vec<f32>,
+}
+
+impl SummaryBuilder {
+    fn new(sample_rate: u32, spectrogram: bool) -> SummaryBuilder {
+        return SummaryBuilder {
+            waveform    : WaveformBuilder::new(sample_rate),
+            spectrogram : spectrogram.then(|| SpectrogramBuilder::new(sample_rate)),
+            onsets      : OnsetBuilder::new(sample_rate),
+            mono        : vec![],
+        };
+    }
+
+    // Channels are mixed down, there's only room for one
+    fn push(&mut self, planar: &[Vec<f32>]) {
+        let frames = planar.iter().map(Vec::len).min().unwrap_or(0);
+        self.mono.clear();
+        self.mono.extend((0 .. frames).map(|frame| planar.iter().map(|channel| channel[frame]).sum::<f32>() / planar.len() as f32));
+
+        self.waveform.push(&self.mono);
+        self.onsets.push(&self.mono);
+        if let Some(spectrogram) = &mut self.spectrogram {
+            spectrogram.push(&self.mono);
+        }
+    }
+
+    fn finish(self) -> Summary {
+        return Summary {
+            waveform    : self.waveform.finish(),
+            spectrogram : self.spectrogram.map(SpectrogramBuilder::finish),
+            tempo       : self.onsets.finish(),
+        };
+    }
+}
+
+// Summary being computed on its own thread, the file is decoded a packet at a time.
+// Dropping it stops the thread at the next packet
+pub struct Analysis {
+    result    : Receiver<Result<Summary>>,
+    cancelled : Arc<AtomicBool>,
+}
+
+impl Analysis {
+    pub fn start(file: &AudioFile, spectrogram: bool) -> Analysis {
+        let file = file.clone();
+        let (sender, result) = bounded(1);
+        let cancelled = Arc::new(AtomicBool::new(false));
+        let flag = cancelled.clone();
+        thread::spawn(move || {
+            let _ = sender.send(summarize(&file, spectrogram, &flag));
+        });
+
+        return Analysis { result, cancelled };
+    }
+
+    // `try_take` gives an error once the thread has stopped
+    pub fn cancel(&self) {
+        self.cancelled.store(true, Ordering::Relaxed);
+    }
+
+    // `None` while it's still running
+    pub fn try_take(&self) -> Option<Result<Summary>> {
+        return match self.result.try_recv() {
+            Ok(summary) => Some(summary),
+            Err(TryRecvError::Empty) => None,
+            Err(TryRecvError::Disconnected) => Some(Err(Report::msg("Audio analysis stopped unexpectedly"))),
+        };
+    }
+}
+
+impl Drop for Analysis {
+    fn drop(&mut self) {
+        self.cancel();
+    }
+}
+
+fn summarize(file: &AudioFile, spectrogram: bool, cancelled: &AtomicBool) -> Result<Summary> {
+    let mut source = file.source()?;
+    let mut builder = SummaryBuilder::new(file.sample_rate, spectrogram);
+    while let Some(planar) = source.next()? {
+        if cancelled.load(Ordering::Relaxed) {
+            return Err(Report::msg("Audio analysis was cancelled"));
+        }
+
+        builder.push(&planar);
+    }
+
+    return Ok(builder.finish());
+}
\ No newline at end of file
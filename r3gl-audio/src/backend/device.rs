@@ -0,0 +1,264 @@
+use std::cmp::Reverse;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+use color_eyre::eyre::{Report, Result};
+use cpal::traits::{DeviceTrait, HostTrait, StreamTrait};
+use instant::Duration;
+use cpal::{OutputCallbackInfo, Sample, SampleFormat, SampleRate, SupportedBufferSize, SupportedStreamConfig, SupportedStreamConfigRange, Device, HostId, Stream, StreamConfig, StreamError};
+use log::{error, info, warn};
+
+use super::{Backend, Render};
+
+// Used until a song asks for its own rate
+const DEFAULT_SAMPLE_RATE: u32 = 48000;
+
+// Scratch for devices that don't say how large their callbacks get
+const SCRATCH_FRAMES: usize = 16384;
+
+// Some devices report absurd maximums, larger callbacks are rendered in pieces
+const MAX_SCRATCH_FRAMES: usize = 65536;
+
+// Real output device
+pub struct CpalBackend {
+    host          : HostId,
+    device        : Device,
+    config        : StreamConfig,
+    sample_format : SampleFormat,
+    max_frames    : usize, // Largest callback the device says it makes
+
+    stream        : Option<Stream>,
+    lost          : Arc<AtomicBool>,
+}
+
+// What `list_outputs` found, names are what `CpalBackend::open` takes
+#[derive(Debug, Clone)]
+pub struct OutputHost {
+    pub name    : String,
+    pub devices : Vec<OutputDevice>,
+}
+
+#[derive(Debug, Clone)]
+pub struct OutputDevice {
+    pub name       : String,
+    pub is_default : bool,
+    pub configs    : Vec<OutputConfig>,
+}
+
+#[derive(Debug, Clone)]
+pub struct OutputConfig {
+    pub channels        : u16,
+    pub min_sample_rate : u32,
+    pub max_sample_rate : u32,
+    pub sample_format   : String,
+}
+
+// Every output device of every host, ones that fail to answer are left out
+pub fn list_outputs() -> Vec<OutputHost> {
+    let mut hosts = vec![];
+    for id in cpal::available_hosts() {
+        let host = match cpal::host_from_id(id) {
+            Ok(host) => host,
+            Err(err) => {
+                warn!("Failed to open host {:?}: {}", id, err);
+                continue;
+            }
+        };
+
+        let default = host.default_output_device().and_then(|device| device.name().ok());
+        let devices = match host.output_devices() {
+            Ok(devices) => devices,
+            Err(err) => {
+                warn!("Failed to list devices of {:?}: {}", id, err);
+                continue;
+            }
+        };
+
+        hosts.push(OutputHost {
+            name    : id.name().to_string(),
+            devices : devices.filter_map(|device| {
+                let name = device.name().ok()?;
+                let configs = device.supported_output_configs().map_or(vec![], |configs| configs.map(|config| OutputConfig {
+                    channels        : config.channels(),
+                    min_sample_rate : config.min_sample_rate().0,
+                    max_sample_rate : config.max_sample_rate().0,
+                    sample_format   : format!("{:?}", config.sample_format()),
+                }).collect());
+
+                Some(OutputDevice {
+                    is_default : default.as_ref() == Some(&name),
+                    name       : name,
+                    configs    : configs,
+                })
+            }).collect(),
+        });
+    }
+
+    return hosts;
+}
+
+impl CpalBackend {
+    // Prefers JACK, then anything that goes through a sound server
+    pub fn new() -> Result<CpalBackend> {
+        let device = {
+            let mut selected_host = cpal::default_host();
+            for host in cpal::available_hosts() {
+                if host.name().to_lowercase().contains("jack") {
+                    selected_host = cpal::host_from_id(host)?;
+                }
+            }
+
+            info!("Selected Host: {:?}", selected_host.id());
+            let mut selected_device = selected_host
+                .default_output_device()
+                .ok_or_else(|| Report::msg("No output device found"))?;
+
+            for device in selected_host.output_devices()? {
+                if let Ok(name) = device.name().map(|s| s.to_lowercase()) {
+                    if name.contains("pipewire") || name.contains("pulse") || name.contains("jack")
+                    {
+                        selected_device = device;
+                    }
+                }
+            }
+
+            info!("Selected Device: {}", selected_device.name().unwrap_or_else(|_| "Unknown".to_string()));
+            (selected_host.id(), selected_device)
+        };
+
+        return Self::with_device(device.0, device.1, DEFAULT_SAMPLE_RATE);
+    }
+
+    // Names as given by `list_outputs`
+    pub fn open(host: &str, device: &str) -> Result<CpalBackend> {
+        let id = cpal::available_hosts().into_iter()
+            .find(|id| id.name() == host)
+            .ok_or_else(|| Report::msg(format!("No audio host named {:?}", host)))?;
+
+        info!("Selected Device: {} ({})", device, host);
+        return Self::with_device(id, find_device(id, device)?, DEFAULT_SAMPLE_RATE);
+    }
+
+    fn with_device(host: HostId, device: Device, sample_rate: u32) -> Result<CpalBackend> {
+        let supported_config = select_config(&device, sample_rate)?;
+        let sample_format = supported_config.sample_format();
+        let max_frames = match supported_config.buffer_size() {
+            SupportedBufferSize::Range { max, .. } => (*max as usize).clamp(1, MAX_SCRATCH_FRAMES),
+            SupportedBufferSize::Unknown => SCRATCH_FRAMES,
+        };
+
+        info!("SR, CC, SF: {}, {}, {:?}", supported_config.sample_rate().0, supported_config.channels(), sample_format);
+
+        return Ok(CpalBackend {
+            host          : host,
+            device        : device,
+            config        : supported_config.into(),
+            sample_format : sample_format,
+            max_frames    : max_frames,
+
+            stream        : None,
+            lost          : Arc::new(AtomicBool::new(false)),
+        });
+    }
+
+    fn build_stream<T: Sample>(&self, mut render: Render) -> Result<Stream> {
+        let lost = self.lost.clone();
+        let err_fn = move |err: StreamError| {
+            error!("Playback error: {}", err);
+            if let StreamError::DeviceNotAvailable = err {
+                lost.store(true, Ordering::Relaxed);
+            }
+        };
+
+        // Rendered as f32 and converted, into scratch that's allocated before the stream starts
+        let channel_count = self.config.channels as usize;
+        let sample_rate = self.config.sample_rate.0 as u64;
+        let mut buffer = vec![0.0; self.max_frames * channel_count];
+        return Ok(self.device.build_output_stream(&self.config, move |data: &mut [T], info: &OutputCallbackInfo| {
+            let timestamp = info.timestamp();
+            let latency = timestamp.playback.duration_since(&timestamp.callback).unwrap_or_default();
+
+            for (i, chunk) in data.chunks_mut(buffer.len()).enumerate() {
+                let frames = (i * buffer.len() / channel_count) as u64;
+                let scratch = &mut buffer[.. chunk.len()];
+                render(scratch, latency + Duration::from_nanos(frames * 1_000_000_000 / sample_rate));
+                for (sample, value) in chunk.iter_mut().zip(scratch.iter()) {
+                    *sample = Sample::from(value);
+                }
+            }
+        }, err_fn)?);
+    }
+}
+
+impl Backend for CpalBackend {
+    fn sample_rate(&self) -> u32 {
+        return self.config.sample_rate.0;
+    }
+
+    fn channel_count(&self) -> usize {
+        return self.config.channels as usize;
+    }
+
+    fn start(&mut self, render: Render) -> Result<()> {
+        let stream = match self.sample_format {
+            SampleFormat::F32 => self.build_stream::<f32>(render)?,
+            SampleFormat::I16 => self.build_stream::<i16>(render)?,
+            SampleFormat::U16 => self.build_stream::<u16>(render)?,
+        };
+
+        stream.play()?;
+        self.stream = Some(stream);
+        return Ok(());
+    }
+
+    fn is_lost(&self) -> bool {
+        return self.lost.load(Ordering::Relaxed);
+    }
+
+    fn at_sample_rate(&self, sample_rate: u32) -> Result<Option<Box<dyn Backend>>> {
+        let supported_config = select_config(&self.device, sample_rate)?;
+        if supported_config.sample_rate() == self.config.sample_rate {
+            return Ok(None);
+        }
+
+        // Devices can't be cloned on every host, so it's looked up again
+        let name = self.device.name()?;
+        return Ok(Some(Box::new(Self::with_device(self.host, find_device(self.host, &name)?, sample_rate)?)));
+    }
+}
+
+fn find_device(host: HostId, name: &str) -> Result<Device> {
+    return cpal::host_from_id(host)?.output_devices()?
+        .find(|candidate| candidate.name().map_or(false, |candidate| candidate == name))
+        .ok_or_else(|| Report::msg(format!("No output device named {:?}", name)));
+}
+
+// Prefers stereo f32 at exactly `sample_rate`, otherwise takes the closest rate there is
+fn select_config(device: &Device, sample_rate: u32) -> Result<SupportedStreamConfig> {
+    fn rank_supported_config(config: &SupportedStreamConfigRange, sample_rate: u32) -> (u32, Reverse<u32>) {
+        let chans = config.channels() as u32;
+        let channel_rank = match chans {
+            0 => 0,
+            1 => 1,
+            2 => 4,
+            4 => 3,
+            _ => 2,
+        };
+
+        let sample_format_rank = if config.sample_format() == SampleFormat::F32 { 4 } else { 0 };
+        let distance = closest_sample_rate(config, sample_rate).abs_diff(sample_rate);
+        let sample_rate_rank = if distance == 0 { 6 } else { 0 };
+        return (channel_rank + sample_format_rank + sample_rate_rank, Reverse(distance));
+    }
+
+    let supported_config = device.supported_output_configs()?
+        .max_by_key(|config| rank_supported_config(config, sample_rate))
+        .ok_or_else(|| Report::msg("No supported output config"))?;
+
+    let closest = closest_sample_rate(&supported_config, sample_rate);
+    return Ok(supported_config.with_sample_rate(SampleRate(closest)));
+}
+
+fn closest_sample_rate(config: &SupportedStreamConfigRange, sample_rate: u32) -> u32 {
+    return sample_rate.clamp(config.min_sample_rate().0, config.max_sample_rate().0);
+}
\ No newline at end of file
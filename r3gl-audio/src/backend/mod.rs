@@ -0,0 +1,52 @@
+use color_eyre::eyre::Result;
+use instant::Duration;
+
+pub mod device;
+pub mod null;
+pub mod wav;
+
+// Fills interleaved f32 samples, called by the backend whenever it needs more.
+// Also takes how long until the first of them is heard, if the backend knows
+pub type Render = Box<dyn FnMut(&mut [f32], Duration) + Send + 'static>;
+
+// Where rendered audio ends up
+pub trait Backend {
+    fn sample_rate(&self) -> u32;
+    fn channel_count(&self) -> usize;
+
+    // Called once, the backend should start pulling from `render` right away
+    fn start(&mut self, render: Render) -> Result<()>;
+
+    // Device went away, playback only continues on a new backend
+    fn is_lost(&self) -> bool {
+        return false;
+    }
+
+    // Same output running as close to `sample_rate` as it can, `None` if that's no different
+    #[allow(unused_variables)]
+    fn at_sample_rate(&self, sample_rate: u32) -> Result<Option<Box<dyn Backend>>> {
+        return Ok(None);
+    }
+}
+
+impl<B: Backend + ?Sized> Backend for Box<B> {
+    fn sample_rate(&self) -> u32 {
+        return (**self).sample_rate();
+    }
+
+    fn channel_count(&self) -> usize {
+        return (**self).channel_count();
+    }
+
+    fn start(&mut self, render: Render) -> Result<()> {
+        return (**self).start(render);
+    }
+
+    fn is_lost(&self) -> bool {
+        return (**self).is_lost();
+    }
+
+    fn at_sample_rate(&self, sample_rate: u32) -> Result<Option<Box<dyn Backend>>> {
+        return (**self).at_sample_rate(sample_rate);
+    }
+}
\ No newline at end of file
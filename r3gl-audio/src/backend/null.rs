@@ -0,0 +1,142 @@
+use std::sync::{Arc, Mutex, Weak};
+use std::thread;
+
+use color_eyre::eyre::Result;
+use instant::{Duration, Instant};
+
+use super::{Backend, Render};
+
+// How often the wall clock renders
+const WALL_INTERVAL: Duration = Duration::from_millis(5);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Drive {
+    Manual, // Only advances through `NullHandle::advance`
+    Wall,   // Advances in real time on its own thread
+}
+
+// Consumes rendered samples
+pub(crate) type Sink = Box<dyn FnMut(&[f32]) + Send + 'static>;
+
+struct Shared {
+    render   : Option<Render>,
+    sink     : Option<Sink>,
+    buffer   : Vec<f32>,
+    channels : usize,
+    lost     : bool,
+}
+
+// Output that plays nowhere, but still moves the playback position
+pub struct NullBackend {
+    sample_rate   : u32,
+    channel_count : usize,
+    drive         : Drive,
+
+    shared        : Arc<Mutex<Shared>>,
+}
+
+// Drives a `NullBackend` by hand, stays valid after the backend is moved into `Audio`
+#[derive(Clone)]
+pub struct NullHandle {
+    shared: Arc<Mutex<Shared>>,
+}
+
+impl NullBackend {
+    pub fn new(sample_rate: u32, channel_count: usize, drive: Drive) -> NullBackend {
+        return Self::with_sink(sample_rate, channel_count, drive, None);
+    }
+
+    pub(crate) fn with_sink(sample_rate: u32, channel_count: usize, drive: Drive, sink: Option<Sink>) -> NullBackend {
+        return NullBackend {
+            sample_rate   : sample_rate,
+            channel_count : channel_count,
+            drive         : drive,
+
+            shared        : Arc::new(Mutex::new(Shared {
+                render   : None,
+                sink     : sink,
+                buffer   : Vec::new(),
+                channels : channel_count,
+                lost     : false,
+            })),
+        };
+    }
+
+    pub fn handle(&self) -> NullHandle {
+        return NullHandle { shared: self.shared.clone() };
+    }
+}
+
+impl NullHandle {
+    // Renders `frames` frames, does nothing before the backend is started
+    pub fn advance(&self, frames: usize) {
+        advance(&self.shared, frames);
+    }
+
+    // Acts like the device was unplugged, nothing gets rendered after this
+    pub fn disconnect(&self) {
+        self.shared.lock().unwrap().lost = true;
+    }
+}
+
+fn advance(shared: &Mutex<Shared>, frames: usize) {
+    let mut shared = shared.lock().unwrap();
+    let shared = &mut *shared;
+    if shared.lost {
+        return;
+    }
+
+    if let Some(render) = &mut shared.render {
+        shared.buffer.resize(frames * shared.channels, 0.0);
+        render(&mut shared.buffer, Duration::ZERO);
+
+        if let Some(sink) = &mut shared.sink {
+            sink(&shared.buffer);
+        }
+    }
+}
+
+impl Backend for NullBackend {
+    fn sample_rate(&self) -> u32 {
+        return self.sample_rate;
+    }
+
+    fn channel_count(&self) -> usize {
+        return self.channel_count;
+    }
+
+    fn start(&mut self, render: Render) -> Result<()> {
+        self.shared.lock().unwrap().render = Some(render);
+
+        if self.drive == Drive::Wall {
+            // Stops once the backend and every handle are gone
+            let shared = Arc::downgrade(&self.shared);
+            let sample_rate = self.sample_rate as f64;
+            thread::spawn(move || wall_clock(shared, sample_rate));
+        }
+
+        return Ok(());
+    }
+
+    fn is_lost(&self) -> bool {
+        return self.shared.lock().unwrap().lost;
+    }
+}
+
+fn wall_clock(shared: Weak<Mutex<Shared>>, sample_rate: f64) {
+    let start = Instant::now();
+    let mut rendered = 0;
+    loop {
+        thread::sleep(WALL_INTERVAL);
+
+        let shared = match shared.upgrade() {
+            Some(shared) => shared,
+            None => break,
+        };
+
+        // Catch up to real time, so sleeping too long doesn't drift
+        let due = (start.elapsed().as_secs_f64() * sample_rate) as usize;
+        advance(&shared, due - rendered);
+        rendered = due;
+    }
+}
\ No newline at end of file
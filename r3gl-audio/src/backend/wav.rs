@@ -0,0 +1,102 @@
+use std::fs::File;
+use std::io::{BufWriter, Seek, SeekFrom, Write};
+use std::path::Path;
+
+use color_eyre::eyre::Result;
+use log::error;
+
+use super::{Backend, Render, null::{Drive, NullBackend, NullHandle}};
+
+// 32-bit float PCM
+const FORMAT_FLOAT: u16 = 3;
+const HEADER_SIZE: u32 = 44;
+
+// Records everything that would be played into a WAV file
+pub struct WavBackend {
+    inner: NullBackend,
+}
+
+impl WavBackend {
+    pub fn new(path: impl AsRef<Path>, sample_rate: u32, channel_count: usize, drive: Drive) -> Result<WavBackend> {
+        let mut writer = WavWriter::new(path, sample_rate, channel_count)?;
+        let sink = Box::new(move |samples: &[f32]| {
+            if let Err(err) = writer.write(samples) {
+                error!("Failed to record audio: {}", err);
+            }
+        });
+
+        return Ok(WavBackend {
+            inner: NullBackend::with_sink(sample_rate, channel_count, drive, Some(sink)),
+        });
+    }
+
+    pub fn handle(&self) -> NullHandle {
+        return self.inner.handle();
+    }
+}
+
+impl Backend for WavBackend {
+    fn sample_rate(&self) -> u32 {
+        return self.inner.sample_rate();
+    }
+
+    fn channel_count(&self) -> usize {
+        return self.inner.channel_count();
+    }
+
+    fn start(&mut self, render: Render) -> Result<()> {
+        return self.inner.start(render);
+    }
+
+    fn is_lost(&self) -> bool {
+        return self.inner.is_lost();
+    }
+}
+
+pub(crate) struct WavWriter {
+    file: BufWriter<File>,
+    data_size: u32,
+}
+
+impl WavWriter {
+    pub fn new(path: impl AsRef<Path>, sample_rate: u32, channel_count: usize) -> Result<WavWriter> {
+        let mut file = BufWriter::new(File::create(path)?);
+        let block_align = channel_count as u16 * 4;
+
+        file.write_all(b"RIFF")?;
+        file.write_all(&(HEADER_SIZE - 8).to_le_bytes())?;
+        file.write_all(b"WAVE")?;
+
+        file.write_all(b"fmt ")?;
+        file.write_all(&16u32.to_le_bytes())?;
+        file.write_all(&FORMAT_FLOAT.to_le_bytes())?;
+        file.write_all(&(channel_count as u16).to_le_bytes())?;
+        file.write_all(&sample_rate.to_le_bytes())?;
+        file.write_all(&(sample_rate * block_align as u32).to_le_bytes())?;
+        file.write_all(&block_align.to_le_bytes())?;
+        file.write_all(&32u16.to_le_bytes())?;
+
+        file.write_all(b"data")?;
+        file.write_all(&0u32.to_le_bytes())?;
+        file.flush()?;
+
+        return Ok(WavWriter { file, data_size: 0 });
+    }
+
+    // Sizes are patched after every write, so the file is valid at any point
+    pub fn write(&mut self, samples: &[f32]) -> Result<()> {
+        for sample in samples {
+            self.file.write_all(&sample.to_le_bytes())?;
+        }
+
+        self.data_size += samples.len() as u32 * 4;
+        self.file.seek(SeekFrom::Start(4))?;
+        self.file.write_all(&(HEADER_SIZE - 8 + self.data_size).to_le_bytes())?;
+        self.file.seek(SeekFrom::Start(40))?;
+        self.file.write_all(&self.data_size.to_le_bytes())?;
+        self.file.seek(SeekFrom::End(0))?;
+        self.file.flush()?;
+
+        return Ok(());
+    }
+}
\ No newline at end of file
@@ -0,0 +1,201 @@
+use symphonia::core::audio::Channels;
+
+// -3dB, for channels that end up on both sides
+const CENTRE_GAIN: f32 = std::f32::consts::FRAC_1_SQRT_2;
+
+// Mixing matrix from the source layout to the device's channels
+#[derive(Debug, Clone)]
+pub struct ChannelMap {
+    weights: Vec<Vec<f32>>, // [output][input]
+}
+
+impl ChannelMap {
+    // Channels go to the output with the same role. Ones the output doesn't have are
+    // folded into the front pair, except LFE, which is only ever played by an LFE
+    pub fn new(channels: Channels, input_count: usize, output_count: usize) -> ChannelMap {
+        // Mono files are tagged front left or centre, either way they belong in the middle
+        let inputs = if input_count == 1 {
+            vec![Channels::FRONT_CENTRE]
+        } else if channels.count() == input_count {
+            channels.iter().collect()
+        } else {
+            default_layout(input_count)
+        };
+
+        let outputs = default_layout(output_count);
+        let find = |role: Channels| outputs.iter().position(|output| *output == role);
+        let mut weights = vec![vec![0.0; input_count]; output_count];
+        for (i, input) in inputs.iter().enumerate() {
+            let routes: Vec<(Option<usize>, f32)> = if let Some(output) = find(*input) {
+                vec![(Some(output), 1.0)]
+            } else if *input == Channels::LFE1 {
+                vec![]
+            } else if output_count == 1 {
+                vec![(Some(0), 1.0)]
+            } else if input_count == 1 {
+                vec![(find(Channels::FRONT_LEFT), 1.0), (find(Channels::FRONT_RIGHT), 1.0)]
+            } else if left_roles().contains(*input) {
+                vec![(find(Channels::FRONT_LEFT), 1.0)]
+            } else if right_roles().contains(*input) {
+                vec![(find(Channels::FRONT_RIGHT), 1.0)]
+            } else {
+                vec![(find(Channels::FRONT_LEFT), CENTRE_GAIN), (find(Channels::FRONT_RIGHT), CENTRE_GAIN)]
+            };
+
+            for (output, weight) in routes {
+                if let Some(output) = output {
+                    weights[output][i] += weight;
+                }
+            }
+        }
+
+        // A full scale signal on every channel stays full scale
+        for output in &mut weights {
+            let total: f32 = output.iter().sum();
+            if total > 1.0 {
+                output.iter_mut().for_each(|weight| *weight /= total);
+            }
+        }
+
+        return ChannelMap { weights };
+    }
+
+    // Mixes `length` frames starting at `position`, missing frames are silent
+    pub fn map(&self, samples: &[Vec<f32>], position: usize, length: usize) -> Vec<Vec<f32>> {
+        return self.weights.iter().map(|weights| {
+            let mut output = vec![0.0; length];
+            for (input, weight) in samples.iter().zip(weights) {
+                if *weight == 0.0 {
+                    continue;
+                }
+
+                let input = input.get(position ..).unwrap_or(&[]);
+                for (sample, input) in output.iter_mut().zip(input) {
+                    *sample += input * weight;
+                }
+            }
+
+            output
+        }).collect();
+    }
+}
+
+fn left_roles() -> Channels {
+    return Channels::FRONT_LEFT | Channels::REAR_LEFT | Channels::SIDE_LEFT | Channels::FRONT_LEFT_CENTRE | Channels::FRONT_LEFT_WIDE | Channels::FRONT_LEFT_HIGH | Channels::TOP_FRONT_LEFT | Channels::TOP_REAR_LEFT | Channels::REAR_LEFT_CENTRE;
+}
+
+fn right_roles() -> Channels {
+    return Channels::FRONT_RIGHT | Channels::REAR_RIGHT | Channels::SIDE_RIGHT | Channels::FRONT_RIGHT_CENTRE | Channels::FRONT_RIGHT_WIDE | Channels::FRONT_RIGHT_HIGH | Channels::TOP_FRONT_RIGHT | Channels::TOP_REAR_RIGHT | Channels::REAR_RIGHT_CENTRE;
+}
+
+// Devices only report a count, so their channels are taken to be in WAVE order, like
+// WASAPI and most surround files have them. Anything past 7.1 has no role
+fn default_layout(count: usize) -> Vec<Channels> {
+    let (fl, fr, fc, lfe) = (Channels::FRONT_LEFT, Channels::FRONT_RIGHT, Channels::FRONT_CENTRE, Channels::LFE1);
+    let (rl, rr, sl, sr) = (Channels::REAR_LEFT, Channels::REAR_RIGHT, Channels::SIDE_LEFT, Channels::SIDE_RIGHT);
+    let layout = match count {
+        0 => vec![],
+        1 => vec![fc],
+        2 => vec![fl, fr],
+        3 => vec![fl, fr, fc],
+        4 => vec![fl, fr, rl, rr],
+        5 => vec![fl, fr, fc, rl, rr],
+        6 => vec![fl, fr, fc, lfe, rl, rr],
+        7 => vec![fl, fr, fc, lfe, Channels::REAR_CENTRE, sl, sr],
+        _ => vec![fl, fr, fc, lfe, rl, rr, sl, sr],
+    };
+
+    return layout.into_iter().chain(std::iter::repeat(Channels::empty())).take(count).collect();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SURROUND: [Channels; 6] = [Channels::FRONT_LEFT, Channels::FRONT_RIGHT, Channels::FRONT_CENTRE, Channels::LFE1, Channels::REAR_LEFT, Channels::REAR_RIGHT];
+
+    fn stereo() -> Channels {
+        return Channels::FRONT_LEFT | Channels::FRONT_RIGHT;
+    }
+
+    fn surround() -> Channels {
+        return SURROUND.iter().fold(Channels::empty(), |channels, channel| channels | *channel);
+    }
+
+    // One frame through the map
+    fn frame(map: &ChannelMap, input: &[f32]) -> Vec<f32> {
+        let planar: Vec<Vec<f32>> = input.iter().map(|sample| vec![*sample]).collect();
+        return map.map(&planar, 0, 1).into_iter().map(|channel| channel[0]).collect();
+    }
+
+    fn assert_close(actual: &[f32], expected: &[f32]) {
+        assert_eq!(actual.len(), expected.len());
+        for (actual, expected) in actual.iter().zip(expected) {
+            assert!((actual - expected).abs() < 1e-6, "{:?} != {:?}", actual, expected);
+        }
+    }
+
+    #[test]
+    fn mono_to_stereo() {
+        let map = ChannelMap::new(Channels::FRONT_LEFT, 1, 2);
+        assert_close(&frame(&map, &[0.5]), &[0.5, 0.5]);
+    }
+
+    #[test]
+    fn stereo_to_mono() {
+        let map = ChannelMap::new(stereo(), 2, 1);
+        assert_close(&frame(&map, &[1.0, 0.0]), &[0.5]);
+        assert_close(&frame(&map, &[0.2, 0.6]), &[0.4]);
+    }
+
+    #[test]
+    fn stereo_to_surround() {
+        let map = ChannelMap::new(stereo(), 2, 6);
+        assert_close(&frame(&map, &[0.3, 0.7]), &[0.3, 0.7, 0.0, 0.0, 0.0, 0.0]);
+    }
+
+    #[test]
+    fn surround_to_stereo() {
+        let map = ChannelMap::new(surround(), 6, 2);
+        let side = 1.0 + CENTRE_GAIN + 1.0;
+
+        // Every input channel on its own, in file order
+        let expected = [
+            (1.0 / side, 0.0),
+            (0.0, 1.0 / side),
+            (CENTRE_GAIN / side, CENTRE_GAIN / side),
+            (0.0, 0.0),
+            (1.0 / side, 0.0),
+            (0.0, 1.0 / side),
+        ];
+
+        for (channel, (left, right)) in expected.iter().enumerate() {
+            let mut input = [0.0; 6];
+            input[channel] = 1.0;
+            assert_close(&frame(&map, &input), &[*left, *right]);
+        }
+
+        // Full scale everywhere doesn't clip
+        assert_close(&frame(&map, &[1.0; 6]), &[1.0, 1.0]);
+    }
+
+    #[test]
+    fn unknown_layout_uses_wave_order() {
+        let map = ChannelMap::new(Channels::empty(), 6, 6);
+        let input = [0.1, 0.2, 0.3, 0.4, 0.5, 0.6];
+        assert_close(&frame(&map, &input), &input);
+    }
+
+    #[test]
+    fn surround_keeps_roles() {
+        // Quad output has no centre or LFE, the centre is split over the front pair
+        let map = ChannelMap::new(surround(), 6, 4);
+        let mut input = [0.0; 6];
+        input[2] = 1.0;
+        let front = CENTRE_GAIN / (1.0 + CENTRE_GAIN);
+        assert_close(&frame(&map, &input), &[front, front, 0.0, 0.0]);
+
+        input = [0.0, 0.0, 0.0, 0.0, 1.0, 0.5];
+        assert_close(&frame(&map, &input), &[0.0, 0.0, 1.0, 0.5]);
+    }
+}
\ No newline at end of file
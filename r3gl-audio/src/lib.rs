@@ -1,19 +1,16 @@
-use crossbeam::channel::{Receiver, unbounded};
-use fragile::Sticky;
-use instant::Duration;
-use itertools::Itertools;
-use rubato::{SincFixedIn, InterpolationParameters, InterpolationType, WindowFunction, Resampler};
+#![allow(clippy::needless_return, clippy::redundant_field_names, clippy::new_without_default, clippy::module_inception)]
 
-use std::collections::VecDeque;
-use std::sync::atomic::{AtomicBool, Ordering, AtomicUsize};
-use std::sync::{Arc, Mutex, RwLock};
-use std::thread;
+use fragile::{stack_token, Sticky};
+use instant::{Duration, Instant};
+
+use std::iter;
+use std::sync::atomic::{AtomicBool, Ordering, AtomicUsize, AtomicU32, AtomicU64};
+use std::sync::{Arc, Mutex};
 
 use color_eyre::eyre::{Report, Result};
-use cpal::traits::{DeviceTrait, HostTrait, StreamTrait};
-use cpal::{Sample, SampleFormat, SupportedStreamConfigRange};
-use log::{error, info, warn};
-use symphonia::core::audio::{SampleBuffer, AudioBufferRef, SignalSpec};
+use crossbeam::queue::ArrayQueue;
+use log::warn;
+use symphonia::core::audio::{SampleBuffer, AudioBufferRef, SignalSpec, Channels};
 use symphonia::core::codecs::DecoderOptions;
 use symphonia::core::errors::Error as SymphoniaError;
 use symphonia::core::formats::FormatOptions;
@@ -22,318 +19,460 @@ use symphonia::core::meta::MetadataOptions;
 use symphonia::default;
 
 pub use symphonia::core::probe::Hint;
+pub use backend::{Backend, Render, device::{CpalBackend, OutputHost, OutputDevice, OutputConfig, list_outputs}, null::{NullBackend, NullHandle, Drive}, wav::WavBackend};
+
+pub use analysis::{Analysis, Summary};
+pub use mixer::Sound;
+pub use source::AudioFile;
+pub use spectrogram::Spectrogram;
+pub use tempo::{TempoCandidate, detect_tempo};
+pub use tracks::{Bus, BusMix, Track, TrackMix, MAX_STEMS};
+pub use waveform::{Peak, Waveform};
+
+use channels::ChannelMap;
+use mixer::Voice;
+use renderer::{Control, Garbage, Renderer, Shared, CONTROL_CAPACITY, GARBAGE_CAPACITY};
+use stream::{AudioStream, StreamConfig, StreamReader};
+use tracks::{BUS_COUNT, TRACK_COUNT};
+
+pub mod backend;
+
+mod analysis;
+mod channels;
+mod mixer;
+mod renderer;
+mod ring;
+mod source;
+mod spectrogram;
+mod stream;
+mod stretch;
+mod tempo;
+mod tracks;
+mod waveform;
+
+// Playback rates outside of this are clamped
+pub const MIN_RATE: f64 = 0.25;
+pub const MAX_RATE: f64 = 2.0;
+
+pub(crate) fn interleave(channels: &[Vec<f32>]) -> Vec<f32> {
+    let frames = channels.iter().map(Vec::len).min().unwrap_or(0);
+    return (0 .. frames).flat_map(|frame| channels.iter().map(move |channel| channel[frame])).collect();
+}
+
+// Backend and everything needed to talk to its callback
+struct Output {
+    backend       : Sticky<Box<dyn Backend>>,
+
+    controls      : Arc<ArrayQueue<Control>>,
+    garbage       : Arc<ArrayQueue<Garbage>>,
+    shared        : Arc<Shared>,
 
-#[derive(Debug)]
-struct AudioBuffer {
-    channel  : Mutex<Receiver<Option<Vec<f32>>>>,
-    buffer   : VecDeque<f32>,
-    
-    done     : bool,
+    sample_rate   : u32,
+    channel_count : usize,
 }
 
-impl AudioBuffer {
-    fn new(audio: &AudioData, sample_rate: u32, channel_count: usize) -> Result<(AudioBuffer, usize)> {
-        let speed = 1.0; // TODO: implement rates
-        let resample_ratio = sample_rate as f64 / audio.sample_rate as f64 / speed;
-        let decode_block_size: usize = (1024.0 * resample_ratio) as usize;
+impl Output {
+    fn start(mut backend: impl Backend + 'static) -> Result<Output> {
+        let controls = Arc::new(ArrayQueue::new(CONTROL_CAPACITY));
+        let garbage = Arc::new(ArrayQueue::new(GARBAGE_CAPACITY));
+        let shared = Arc::new(Shared::new());
+        let (sample_rate, channel_count) = (backend.sample_rate(), backend.channel_count());
 
-        // Get ownership of samples
-        let mut samples = audio.samples.clone();
+        let mut renderer = Renderer::new(controls.clone(), garbage.clone(), shared.clone(), sample_rate, channel_count);
+        backend.start(Box::new(move |data, latency| renderer.render(data, latency)))?;
 
-        // All channles must have equal sample count
-        assert!(samples.windows(2).all(|w| w[0].len() == w[1].len()));
-        let resampled_interleaved_length = (samples[0].len() as f64 * resample_ratio * 2.0).ceil() as usize;
+        return Ok(Output {
+            backend       : Sticky::new(Box::new(backend)),
 
-        // Pad with zeroes
-        let unpadded_length = samples[0].len();
-        let padded_sample_count = ((unpadded_length as f32 / decode_block_size as f32).ceil() * decode_block_size as f32) as usize;
-        for channel_buffer in &mut samples {
-            channel_buffer.resize(padded_sample_count, 0.0);
-        }
+            controls      : controls,
+            garbage       : garbage,
+            shared        : shared,
 
-        let (tx, rx) = unbounded();
-        thread::spawn(move || {
-            let mut resampler = SincFixedIn::<f32>::new(
-            resample_ratio,
-            2.0,
-            InterpolationParameters {
-                sinc_len: 256,
-                f_cutoff: 0.95,
-                interpolation: InterpolationType::Linear,
-                oversampling_factor: 256,
-                window: WindowFunction::BlackmanHarris2,
-            },
-            decode_block_size,
-            channel_count,
-            ).unwrap();
-
-            let last = unpadded_length / decode_block_size;
-            let mut buffer = resampler.output_buffer_allocate();
-            for i in 0 ..= last {
-                let pos = i * decode_block_size;
-                resampler.process_into_buffer(&[
-                    &samples[0][pos .. (pos + decode_block_size)],
-                    &samples[1][pos .. (pos + decode_block_size)]
-                ], &mut buffer, None).unwrap();
-
-                let processed = buffer[1].iter().cloned().interleave(
-                                buffer[1].iter().cloned()).collect();
-                
-                // Stop decoding if it's not needed anymore
-                if tx.send(Some(processed)).is_err() {
-                    break;
-                }
-            }
+            sample_rate   : sample_rate,
+            channel_count : channel_count,
         });
+    }
+}
 
-        return Ok((AudioBuffer {
-            channel  : Mutex::new(rx),
-            buffer   : VecDeque::new(),
-            done     : false,
-        }, resampled_interleaved_length));
-    }
-
-    fn read_samples(&mut self, pos: usize, count: usize) -> (Vec<f32>, bool) {
-        let channel = self.channel.lock().unwrap();
-        if !self.done {
-            while self.buffer.len() < pos + count {
-                if let Ok(Some(buf)) = channel.recv() {
-                    self.buffer.append(&mut VecDeque::from(buf));
-                } else {
-                    self.done = true;
-                    break;
-                }
-            }
-        }
+// Plays along with the song, from the same position
+struct Stem {
+    file   : Arc<AudioFile>,
+    stream : AudioStream,
+}
 
-        let mut vec = Vec::new();
-        let mut done = false;
-        for i in 0 .. count {
-            if let Some(sample) = self.buffer.get(pos + i) {
-                vec.push(*sample);
-            } else {
-                done = true;
-                break;
-            }
+pub struct Audio {
+    output         : Output,
+
+    song           : Mutex<Option<Arc<AudioFile>>>,
+    stream         : Mutex<Option<AudioStream>>,
+    stems          : Mutex<Vec<Option<Stem>>>, // `MAX_STEMS` slots, locked after `stream`
+    length         : AtomicUsize,
+    generation     : AtomicU64, // Of every stream, so one seek moves them all
+
+    rate           : AtomicU64, // f64 bits
+    preserve_pitch : AtomicBool,
+    volume         : AtomicU32, // f32 bits, master
+    mixes          : Mutex<[TrackMix; TRACK_COUNT]>,
+    buses          : Mutex<[BusMix; BUS_COUNT]>,
+}
+
+impl Audio {
+    pub fn new() -> Result<Audio> {
+        return Self::with_backend(CpalBackend::new()?);
+    }
+
+    pub fn with_backend(backend: impl Backend + 'static) -> Result<Audio> {
+        return Ok(Audio {
+            output         : Output::start(backend)?,
+
+            song           : Mutex::new(None),
+            stream         : Mutex::new(None),
+            stems          : Mutex::new((0 .. MAX_STEMS).map(|_| None).collect()),
+            length         : AtomicUsize::new(0),
+            generation     : AtomicU64::new(0),
+
+            rate           : AtomicU64::new(1.0f64.to_bits()),
+            preserve_pitch : AtomicBool::new(false),
+            volume         : AtomicU32::new(1.0f32.to_bits()),
+            mixes          : Mutex::new([TrackMix::default(); TRACK_COUNT]),
+            buses          : Mutex::new([BusMix::default(); BUS_COUNT]),
+        });
+    }
+
+    // Moves playback to another output without losing the playhead. Sounds are loaded
+    // for one output's format, so they have to be loaded again afterwards
+    pub fn set_backend(&mut self, backend: impl Backend + 'static) -> Result<()> {
+        let time = self.get_time();
+        let paused = self.is_paused();
+        let finished = self.finished();
+
+        // Old backend stops playing once it's dropped, along with the stream it was reading
+        self.output = Output::start(backend)?;
+        *self.stream.lock().unwrap() = None;
+        self.output.shared.finished.store(finished, Ordering::Relaxed);
+        self.send(Control::Volume(self.get_volume()));
+        self.send_mixes();
+        self.send_buses();
+
+        // Stream has to match the new format, so it's opened again
+        let position = self.position_at(time);
+        self.output.shared.position.store(position, Ordering::Release);
+        let song = self.song.lock().unwrap().clone();
+        if let Some(song) = song {
+            let (stream, reader) = self.open_stream(song, position)?;
+            self.send(Control::Stream(Some(reader), position, self.get_rate()));
+            *self.stream.lock().unwrap() = Some(stream);
+        } else {
+            self.send(Control::Seek(position, self.generation.load(Ordering::Relaxed)));
         }
-        
-        return (vec, done);
+
+        self.open_stems(position)?;
+        self.set_paused(paused);
+        return Ok(());
     }
-}
 
-struct AudioState {
-    audio_buffer  : RwLock<Option<AudioBuffer>>,
-    buffer_length : AtomicUsize,
-    
-    position      : AtomicUsize,
-    paused        : AtomicBool,
-    finished      : AtomicBool,
+    // Backend needs replacing, see `set_backend`
+    pub fn is_device_lost(&self) -> bool {
+        stack_token!(token);
+        return self.output.backend.get(token).is_lost();
+    }
 
-    sample_rate   : u32,
-    channel_count : usize,
-}
+    // Only way to reach the callback
+    fn send(&self, control: Control) {
+        // Whatever the callback let go of is freed here instead
+        while self.output.garbage.pop().is_some() {}
 
-impl AudioState {
-    fn new(channel_count: u32, sample_rate: u32) -> AudioState {
-        return AudioState {
-            audio_buffer  : RwLock::new(None),
-            buffer_length : AtomicUsize::new(0),
-            position      : AtomicUsize::new(0),
-            paused        : AtomicBool::new(true),
-            finished      : AtomicBool::new(false),
-            sample_rate   : sample_rate,
-            channel_count : channel_count as usize,
+        // Anything that moves the playhead makes earlier timestamps meaningless
+        if let Control::Pause(_) | Control::Seek(..) | Control::Stream(..) = control {
+            let shared = &self.output.shared;
+            shared.reset.store(shared.nanos(), Ordering::Release);
+        }
+
+        if self.output.controls.push(control).is_err() {
+            warn!("Audio callback isn't keeping up, dropped a control message");
+        }
+    }
+
+    fn stream_config(&self) -> StreamConfig {
+        return StreamConfig {
+            sample_rate    : self.output.sample_rate,
+            channel_count  : self.output.channel_count,
+            rate           : self.get_rate(),
+            preserve_pitch : self.is_preserving_pitch(),
         };
     }
-    
-    fn write_samples<T: Sample>(&self, data: &mut [T]) {
-        if self.paused.load(Ordering::Relaxed) {
-            for sample in data.iter_mut() {
-                *sample = Sample::from(&0.0);
-            }
 
-            return;
+    fn open_stream(&self, song: Arc<AudioFile>, position: usize) -> Result<(AudioStream, StreamReader)> {
+        let config = self.stream_config();
+        self.length.store(config.length(&song), Ordering::Relaxed);
+        return AudioStream::new(song, config, position, self.generation.load(Ordering::Relaxed));
+    }
+
+    // Stems don't have a position of their own, they're opened again wherever the song is
+    fn open_stems(&self, position: usize) -> Result<()> {
+        let mut stems = self.stems.lock().unwrap();
+        for (slot, stem) in stems.iter_mut().enumerate() {
+            if let Some(stem) = stem {
+                let (stream, reader) = AudioStream::new(stem.file.clone(), self.stream_config(), position, self.generation.load(Ordering::Relaxed))?;
+                self.send(Control::Stem(slot, Some(reader), position));
+                stem.stream = stream;
+            }
         }
 
-        let mut audio_buffer = self.audio_buffer.write().unwrap();
-        if let Some(audio_buffer) = audio_buffer.as_mut() {
-            let data_len = data.len();
-            let position = self.position.load(Ordering::Acquire);
-            let (samples, is_final) = audio_buffer.read_samples(position, data_len);
-            for (i, sample) in data.iter_mut().enumerate() {
-                if i >= samples.len() {
-                    break;
-                }
+        return Ok(());
+    }
 
-                *sample = Sample::from(&samples[i]);
-            }
+    // Both ways go through whole frames, so long songs don't drift
+    fn time_at(&self, position: usize) -> Duration {
+        let frames = (position / self.output.channel_count) as u64;
+        let sample_rate = self.output.sample_rate as u64;
 
-            self.position.store(position + data_len, Ordering::Release);
+        // Rounded up, so converting back lands on the same frame
+        let nanos = ((frames % sample_rate) * 1_000_000_000 + sample_rate - 1) / sample_rate;
+        return Duration::new(frames / sample_rate, nanos as u32).mul_f64(self.get_rate());
+    }
+    fn position_at(&self, time: Duration) -> usize {
+        let frames = time.div_f64(self.get_rate()).as_nanos() * self.output.sample_rate as u128 / 1_000_000_000;
+        return frames as usize * self.output.channel_count;
+    }
 
-            if is_final {
-                self.paused.store(true, Ordering::Relaxed);
-                self.finished.store(true, Ordering::Relaxed);
-            }
+    pub fn finished(&self) -> bool {
+        return self.output.shared.finished.load(Ordering::Relaxed);
+    }
+    pub fn length(&self) -> Duration {
+        let length = self.length.load(Ordering::Relaxed);
+        return self.time_at(length);
+    }
+    pub fn get_time(&self) -> Duration {
+        let position = self.output.shared.position.load(Ordering::Acquire);
+        return self.time_at(position);
+    }
+    // Song time that's heard at the returned instant, output latency included. Unlike
+    // `get_time` it doesn't lag behind by up to a callback, `None` until one played
+    pub fn timestamp(&self) -> Option<(Duration, Instant)> {
+        let (position, heard) = self.output.shared.stamp()?;
+        return Some((self.time_at(position), self.output.shared.epoch + Duration::from_nanos(heard)));
+    }
+    pub fn set_time(&mut self, time: Duration) {
+        let position = self.position_at(time);
+        let mut stream = self.stream.lock().unwrap();
+        self.output.shared.position.store(position, Ordering::Release);
+
+        // Start decoding right away instead of on the next callback
+        let generation = self.generation.fetch_add(1, Ordering::Relaxed) + 1;
+        if let Some(stream) = stream.as_mut() {
+            stream.seek(position, generation);
         }
+
+        for stem in self.stems.lock().unwrap().iter_mut().flatten() {
+            stem.stream.seek(position, generation);
+        }
+
+        self.send(Control::Seek(position, generation));
     }
 
-    fn decode_song(&self, song: &AudioData) -> Result<(AudioBuffer, usize)> {
-        return AudioBuffer::new(song, self.sample_rate, self.channel_count);
+    pub fn get_rate(&self) -> f64 {
+        return f64::from_bits(self.rate.load(Ordering::Relaxed));
     }
-    
-    fn play(&self, song: &AudioData) -> Result<()> {
-        let (samples, length) = self.decode_song(song)?;
-        self.position.store(0, Ordering::SeqCst);
-        self.set_paused(true);
-        *self.audio_buffer.write().unwrap() = Some(samples);
-        self.buffer_length.store(length, Ordering::SeqCst);
+    pub fn set_rate(&self, rate: f64) -> Result<()> {
+        return self.reopen(rate.clamp(MIN_RATE, MAX_RATE), self.is_preserving_pitch());
+    }
+
+    // Stream is rendered at a fixed rate, so it has to be opened again
+    fn reopen(&self, rate: f64, preserve_pitch: bool) -> Result<()> {
+        let old_rate = self.get_rate();
+        self.rate.store(rate.to_bits(), Ordering::Relaxed);
+        self.preserve_pitch.store(preserve_pitch, Ordering::Relaxed);
+
+        let song = self.song.lock().unwrap().clone();
+        if let Some(song) = song {
+            let mut stream = self.stream.lock().unwrap();
+            let position = (self.output.shared.position.load(Ordering::Acquire) as f64 * old_rate / rate) as usize;
+            let position = position - position % self.output.channel_count;
+            let (new_stream, reader) = self.open_stream(song, position)?;
+
+            self.output.shared.position.store(position, Ordering::Release);
+            self.send(Control::Stream(Some(reader), position, rate));
+            *stream = Some(new_stream);
+            self.open_stems(position)?;
+        }
+
         return Ok(());
     }
-    fn stop(&self) {
-        self.set_paused(true);
 
-        *self.audio_buffer.write().unwrap() = None;
+    pub fn get_volume(&self) -> f32 {
+        return f32::from_bits(self.volume.load(Ordering::Relaxed));
     }
-    fn pause(&self) {
-        let paused = self.paused.load(Ordering::Acquire);
-        self.paused.store(!paused, Ordering::Release);
+    pub fn set_volume(&self, volume: f32) {
+        let volume = volume.max(0.0);
+        self.volume.store(volume.to_bits(), Ordering::Relaxed);
+        self.send(Control::Volume(volume));
     }
-    fn set_paused(&self, state: bool) {
-        self.paused.store(state, Ordering::Relaxed);
+
+    // Plays `file` along with the song from now on, returns the slot it went into
+    pub fn add_stem(&self, file: &AudioFile) -> Result<usize> {
+        let _stream = self.stream.lock().unwrap(); // No seeking until it's in place
+        let mut stems = self.stems.lock().unwrap();
+        let slot = stems.iter().position(Option::is_none).ok_or_else(|| Report::msg(format!("Can't play more than {} stems", MAX_STEMS)))?;
+
+        let file = Arc::new(file.clone());
+        let position = self.output.shared.position.load(Ordering::Acquire);
+        let (stream, reader) = AudioStream::new(file.clone(), self.stream_config(), position, self.generation.load(Ordering::Relaxed))?;
+        self.send(Control::Stem(slot, Some(reader), position));
+        stems[slot] = Some(Stem { file, stream });
+        return Ok(slot);
     }
-    fn seek(&self, position: usize) {
-        self.position.store(position, Ordering::Release);
+    pub fn remove_stem(&self, slot: usize) {
+        match self.stems.lock().unwrap().get_mut(slot) {
+            Some(stem) => *stem = None,
+            None => return,
+        }
+
+        // Next stem in this slot starts out fresh
+        self.send(Control::Stem(slot, None, 0));
+        self.set_mix(Track::Stem(slot), TrackMix::default());
     }
-}
 
-pub struct Audio {
-    _stream      : Sticky<Box<dyn StreamTrait>>,
-    player_state : Arc<AudioState>,
-}
+    // Gain, pan, mute and solo of the song or a stem
+    pub fn get_mix(&self, track: Track) -> TrackMix {
+        return self.mixes.lock().unwrap()[track.index()];
+    }
+    pub fn set_mix(&self, track: Track, mix: TrackMix) {
+        self.mixes.lock().unwrap()[track.index()] = mix;
+        self.send_mixes();
+    }
 
-impl Audio {
-    pub fn new() -> Result<Audio> {
-        let device = {
-            let mut selected_host = cpal::default_host();
-            for host in cpal::available_hosts() {
-                if host.name().to_lowercase().contains("jack") {
-                    selected_host = cpal::host_from_id(host)?;
-                }
-            }
+    // Soloing one track changes all the others
+    fn send_mixes(&self) {
+        let mixes = *self.mixes.lock().unwrap();
+        let any_solo = mixes.iter().any(|mix| mix.solo);
+        for track in iter::once(Track::Song).chain((0 .. MAX_STEMS).map(Track::Stem)) {
+            self.send(Control::Mix(track, mixes[track.index()].gains(any_solo)));
+        }
+    }
 
-            info!("Selected Host: {:?}", selected_host.id());
-            let mut selected_device = selected_host
-                .default_output_device()
-                .ok_or_else(|| Report::msg("No output device found"))?;
+    pub fn get_bus(&self, bus: Bus) -> BusMix {
+        return self.buses.lock().unwrap()[bus as usize];
+    }
+    pub fn set_bus(&self, bus: Bus, mix: BusMix) {
+        self.buses.lock().unwrap()[bus as usize] = mix;
+        self.send(Control::Bus(bus, mix.gain()));
+    }
 
-            for device in selected_host.output_devices()? {
-                if let Ok(name) = device.name().map(|s| s.to_lowercase()) {
-                    if name.contains("pipewire") || name.contains("pulse") || name.contains("jack")
-                    {
-                        selected_device = device;
-                    }
-                }
-            }
+    fn send_buses(&self) {
+        let buses = *self.buses.lock().unwrap();
+        for bus in [Bus::Song, Bus::Hitsounds, Bus::Metronome] {
+            self.send(Control::Bus(bus, buses[bus as usize].gain()));
+        }
+    }
 
-            info!("Selected Device: {}", selected_device.name().unwrap_or_else(|_| "Unknown".to_string()));
-            selected_device
-        };
+    // Converts to the device's format once, so playing it later is just mixing
+    pub fn load_sound(&self, data: &AudioData) -> Result<Sound> {
+        let channel_count = self.output.channel_count;
+        let frames = data.samples.first().map_or(0, Vec::len);
+        let mapped = ChannelMap::new(data.channels, data.channel_count, channel_count).map(&data.samples, 0, frames);
+
+        // Linear, so the sound starts exactly where it's scheduled
+        let ratio = self.output.sample_rate as f64 / data.sample_rate as f64;
+        let resampled: Vec<Vec<f32>> = mapped.iter().map(|channel| {
+            (0 .. (frames as f64 * ratio).ceil() as usize).map(|frame| {
+                let position = frame as f64 / ratio;
+                let (index, fraction) = (position as usize, position.fract() as f32);
+                let current = channel.get(index).copied().unwrap_or(0.0);
+                let next = channel.get(index + 1).copied().unwrap_or(0.0);
+                current + (next - current) * fraction
+            }).collect()
+        }).collect();
+
+        return Ok(Sound { samples: Arc::new(interleave(&resampled)) });
+    }
 
-        let mut supported_configs = device.supported_output_configs()?.collect::<Vec<_>>();
-        fn rank_supported_config(config: &SupportedStreamConfigRange) -> u32 {
-            let chans = config.channels() as u32;
-            let channel_rank = match chans {
-                0 => 0,
-                1 => 1,
-                2 => 4,
-                4 => 3,
-                _ => 2,
-            };
-            
-            let min_sample_rank = if config.min_sample_rate().0 <= 48000 { 3 } else { 0 };
-            let max_sample_rank = if config.max_sample_rate().0 >= 48000 { 3 } else { 0 };
-            let sample_format_rank = if config.sample_format() == SampleFormat::F32 { 4 } else { 0 };
-            channel_rank + min_sample_rank + max_sample_rank + sample_format_rank
+    // Sine that fades out over `length`, for clicks that don't need a sample file
+    pub fn tone(&self, frequency: f32, length: Duration) -> Sound {
+        let sample_rate = self.output.sample_rate as f32;
+        let frames = (length.as_secs_f32() * sample_rate) as usize;
+        let mut samples = Vec::with_capacity(frames * self.output.channel_count);
+        for frame in 0 .. frames {
+            // Down to -60 dB by the end
+            let envelope = (-6.9 * frame as f32 / frames as f32).exp();
+            let value = (std::f32::consts::TAU * frequency * frame as f32 / sample_rate).sin() * envelope;
+            samples.extend(std::iter::repeat(value).take(self.output.channel_count));
         }
-        
-        supported_configs.sort_by_key(|c_2| std::cmp::Reverse(rank_supported_config(c_2)));
-        let supported_config = supported_configs.into_iter().next().ok_or_else(|| Report::msg("No supported output config"))?;
-
-        let sample_rate_range = supported_config.min_sample_rate().0..supported_config.max_sample_rate().0;
-        let supported_config = match sample_rate_range {
-            rate if rate.contains(&48000) => supported_config.with_sample_rate(cpal::SampleRate(48000)),
-            rate if rate.contains(&44100) => supported_config.with_sample_rate(cpal::SampleRate(48000)),
-            rate if rate.end <= 48000     => supported_config.with_sample_rate(cpal::SampleRate(48000)),
-                                        _ => supported_config.with_sample_rate(cpal::SampleRate(sample_rate_range.start)) };
-
-        let sample_format = supported_config.sample_format();
-        let sample_rate = supported_config.sample_rate().0;
-        let channel_count = supported_config.channels();
-        let config = supported_config.into();
-        let err_fn = |err| error!("Playback error: {}", err);
-        let player_state = Arc::new(AudioState::new(channel_count as u32, sample_rate));
-        info!("SR, CC, SF: {sample_rate}, {channel_count}, {sample_format:?}");
-
-        let stream = {
-            let player_state = player_state.clone();
-            match sample_format {
-                SampleFormat::F32 => device.build_output_stream(&config, move |data, _| player_state.write_samples::<f32>(data), err_fn)?,
-                SampleFormat::I16 => device.build_output_stream(&config, move |data, _| player_state.write_samples::<i16>(data), err_fn)?,
-                SampleFormat::U16 => device.build_output_stream(&config, move |data, _| player_state.write_samples::<u16>(data), err_fn)?, } };
-
-        stream.play()?;
 
-        return Ok(Audio {
-            _stream: Sticky::new(Box::new(stream)),
-            player_state: player_state,
-        });
-    }
-    
-    fn sample_length(&self) -> Duration {
-        return Duration::from_nanos(1000000000 / (self.player_state.sample_rate as u64 * self.player_state.channel_count as u64));
+        return Sound { samples: Arc::new(samples) };
     }
 
-    pub fn finished(&self) -> bool {
-        let finished = self.player_state.finished.load(Ordering::Relaxed);
-        return finished;
+    // Plays `sound` through `bus` when the song reaches `time`
+    pub fn schedule(&self, sound: &Sound, time: Duration, volume: f32, bus: Bus) {
+        self.send(Control::Schedule(Voice::new(sound, time.as_secs_f64(), volume, bus)));
     }
-    pub fn length(&self) -> Duration {
-        let duration_per_sample = self.sample_length();
-        return self.player_state.buffer_length.load(Ordering::Relaxed) as u32 * duration_per_sample;
+    pub fn clear_scheduled(&self) {
+        self.send(Control::ClearScheduled);
     }
-    pub fn get_time(&self) -> Duration {
-        let duration_per_sample = self.sample_length();
-        let position = self.player_state.position.load(Ordering::Acquire);
-        return position as u32 * duration_per_sample;
+
+    pub fn is_preserving_pitch(&self) -> bool {
+        return self.preserve_pitch.load(Ordering::Relaxed);
     }
-    pub fn set_time(&mut self, time: Duration) {
-        let duration_per_sample = self.sample_length();
-        let samples = (time.as_nanos() / duration_per_sample.as_nanos()) as f64 as usize;
-        self.player_state.seek(samples);
+    pub fn set_preserving_pitch(&self, value: bool) -> Result<()> {
+        return self.reopen(self.get_rate(), value);
     }
 
-    pub fn play(&self, song: &AudioData) -> Result<()> {
-        return self.player_state.play(song);
+    // Output is switched to the song's own rate if it can be, so sounds have to be loaded after this
+    pub fn play(&mut self, song: &AudioFile) -> Result<()> {
+        if let Err(err) = self.match_sample_rate(song.sample_rate) {
+            warn!("Failed to switch the output to {} Hz, resampling instead: {:#}", song.sample_rate, err);
+        }
+
+        let song = Arc::new(song.clone());
+        let mut stream = self.stream.lock().unwrap();
+        let (new_stream, reader) = self.open_stream(song.clone(), 0)?;
+
+        self.set_paused(true);
+        self.output.shared.position.store(0, Ordering::Release);
+        self.output.shared.finished.store(false, Ordering::Relaxed);
+        self.send(Control::Stream(Some(reader), 0, self.get_rate()));
+        self.open_stems(0)?;
+
+        *stream = Some(new_stream);
+        *self.song.lock().unwrap() = Some(song);
+        return Ok(());
+    }
+    fn match_sample_rate(&mut self, sample_rate: u32) -> Result<()> {
+        stack_token!(token);
+        if let Some(backend) = self.output.backend.get(token).at_sample_rate(sample_rate)? {
+            self.stop();
+            self.set_backend(backend)?;
+        }
+
+        return Ok(());
     }
     pub fn stop(&self) {
-        return self.player_state.stop();
+        self.set_paused(true);
+
+        let mut stream = self.stream.lock().unwrap();
+        self.send(Control::Stream(None, self.output.shared.position.load(Ordering::Acquire), self.get_rate()));
+        *stream = None;
+        *self.song.lock().unwrap() = None;
     }
     pub fn pause(&self) {
-        self.player_state.pause();
+        self.set_paused(!self.is_paused());
     }
     pub fn set_paused(&self, state: bool) {
-        self.player_state.set_paused(state);
+        self.output.shared.paused.store(state, Ordering::Relaxed);
+        self.send(Control::Pause(state));
     }
     pub fn is_paused(&self) -> bool {
-        return self.player_state.paused.load(Ordering::Relaxed);
+        return self.output.shared.paused.load(Ordering::Relaxed);
     }
 }
 
+// Fully decoded into memory, meant for short samples. Songs should be streamed with `AudioFile`
 #[derive(Debug, Clone)]
 pub struct AudioData {
     samples: Vec<Vec<f32>>,
     sample_rate: u32,
     channel_count: usize,
+    channels: Channels,
 }
 
 impl AudioData {
@@ -352,7 +491,7 @@ impl AudioData {
             &DecoderOptions::default(),
         )?;
 
-        fn decode_buffer(buffer: AudioBufferRef, spec: SignalSpec, song_samples: &mut Vec<Vec<f32>>) {
+        fn decode_buffer(buffer: AudioBufferRef, spec: SignalSpec, song_samples: &mut [Vec<f32>]) {
             if buffer.frames() > 0 {
                 let mut samples = SampleBuffer::new(buffer.frames() as u64, spec);
                 samples.copy_interleaved_ref(buffer);
@@ -366,25 +505,24 @@ impl AudioData {
             }
         }
 
-        let mut song = loop {
-            match probe.format.next_packet() {
-                Ok(packet) => {
-                    let buffer = decoder.decode(&packet)?;
-                    let spec = *buffer.spec();
+        let mut song = match probe.format.next_packet() {
+            Ok(packet) => {
+                let buffer = decoder.decode(&packet)?;
+                let spec = *buffer.spec();
 
-                    let mut song_samples = vec![Vec::new(); spec.channels.count()];
-                    decode_buffer(buffer, spec, &mut song_samples);
-                    
-                    break AudioData {
-                        samples: song_samples,
-                        sample_rate: spec.rate,
-                        channel_count: spec.channels.count(),
-                    };
-                }
+                let mut song_samples = vec![Vec::new(); spec.channels.count()];
+                decode_buffer(buffer, spec, &mut song_samples);
                 
-                Err(SymphoniaError::IoError(_)) => return Err(Report::msg("No audio data decoded")),
-                Err(e) => return Err(e.into()),
+                AudioData {
+                    samples: song_samples,
+                    sample_rate: spec.rate,
+                    channel_count: spec.channels.count(),
+                    channels: spec.channels,
+                }
             }
+            
+            Err(SymphoniaError::IoError(_)) => return Err(Report::msg("No audio data decoded")),
+            Err(e) => return Err(e.into()),
         };
 
 
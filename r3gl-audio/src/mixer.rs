@@ -0,0 +1,101 @@
+use std::sync::Arc;
+
+use crate::tracks::{Bus, BUS_COUNT};
+
+// Short sample, already converted to the device's rate and channels
+#[derive(Debug, Clone)]
+pub struct Sound {
+    pub(crate) samples: Arc<Vec<f32>>, // Interleaved
+}
+
+impl Sound {
+    pub fn is_empty(&self) -> bool {
+        return self.samples.is_empty();
+    }
+}
+
+// Sounds playing at once, anything scheduled past this is dropped
+pub(crate) const MAX_VOICES: usize = 256;
+
+#[derive(Debug)]
+pub(crate) struct Voice {
+    samples : Arc<Vec<f32>>,
+    start   : f64, // Song time in seconds
+    volume  : f32,
+    bus     : Bus,
+    played  : usize,
+}
+
+impl Voice {
+    pub fn new(sound: &Sound, start: f64, volume: f32, bus: Bus) -> Voice {
+        return Voice {
+            samples : sound.samples.clone(),
+            start   : start,
+            volume  : volume,
+            bus     : bus,
+            played  : 0,
+        };
+    }
+}
+
+// Plays scheduled sounds on top of the song. Runs in the audio callback, so it never
+// allocates, and voices it's done with go to `discard` to be freed somewhere else
+#[derive(Debug)]
+pub(crate) struct Mixer {
+    voices: Vec<Voice>,
+}
+
+impl Mixer {
+    pub fn new() -> Mixer {
+        return Mixer { voices: Vec::with_capacity(MAX_VOICES) };
+    }
+
+    // Hands the voice back if there's no room for it
+    pub fn schedule(&mut self, voice: Voice) -> Option<Voice> {
+        if self.voices.len() == self.voices.capacity() {
+            return Some(voice);
+        }
+
+        self.voices.push(voice);
+        return None;
+    }
+
+    pub fn clear(&mut self, mut discard: impl FnMut(Voice)) {
+        for voice in self.voices.drain(..) {
+            discard(voice);
+        }
+    }
+
+    // `position` is where `data` starts in the song, both in interleaved samples
+    pub fn mix(&mut self, data: &mut [f32], position: usize, samples_per_second: f64, channel_count: usize, buses: &[f32; BUS_COUNT], mut discard: impl FnMut(Voice)) {
+        let end = position + data.len();
+        let mut i = 0;
+        while i < self.voices.len() {
+            let voice = &mut self.voices[i];
+            let offset = if voice.played == 0 {
+                let start = (voice.start * samples_per_second) as usize;
+                let start = start - start % channel_count;
+                if start >= end {
+                    i += 1;
+                    continue;
+                }
+
+                // Scheduled too late, play right away instead of skipping
+                start.saturating_sub(position)
+            } else { 0 };
+
+            let remaining = &voice.samples[voice.played ..];
+            let volume = voice.volume * buses[voice.bus as usize];
+            for (sample, value) in data[offset ..].iter_mut().zip(remaining) {
+                *sample += value * volume;
+            }
+
+            voice.played += remaining.len().min(data.len() - offset);
+            if voice.played < voice.samples.len() {
+                i += 1;
+            } else {
+                discard(self.voices.swap_remove(i));
+            }
+        }
+    }
+}
\ No newline at end of file
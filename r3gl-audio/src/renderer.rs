@@ -0,0 +1,310 @@
+use std::mem;
+use std::sync::Arc;
+use std::sync::atomic::{self, AtomicBool, AtomicU64, AtomicUsize, Ordering};
+
+use crossbeam::queue::ArrayQueue;
+use instant::{Duration, Instant};
+
+use crate::{mixer::{Mixer, Voice, MAX_VOICES}, stream::StreamReader, tracks::{channel_gain, Bus, Track, BUS_COUNT, MAX_STEMS, TRACK_COUNT}};
+
+// Control messages waiting for the next callback, anything past this is dropped
+pub(crate) const CONTROL_CAPACITY: usize = 1024;
+
+// Every message lets go of at most one thing, and so does every voice
+pub(crate) const GARBAGE_CAPACITY: usize = CONTROL_CAPACITY + MAX_VOICES + 1;
+
+// Stems are read through this many frames at a time
+const STEM_SCRATCH_FRAMES: usize = 4096;
+
+pub(crate) enum Control {
+    Pause(bool),
+    Seek(usize, u64),                        // Output position, stream generation
+    Stream(Option<StreamReader>, usize, f64), // Output position, playback rate
+    Stem(usize, Option<StreamReader>, usize), // Slot, output position it starts at
+    Mix(Track, (f32, f32)),                   // Left and right gain
+    Bus(Bus, f32),
+    Volume(f32),
+    Schedule(Voice),
+    ClearScheduled,
+}
+
+// Freeing memory can block, so the callback hands it back instead
+#[allow(dead_code)] // Only ever dropped
+pub(crate) enum Garbage {
+    Stream(StreamReader),
+    Voice(Voice),
+}
+
+// Written by the callback, read by `Audio`
+pub(crate) struct Shared {
+    pub position : AtomicUsize,
+    pub paused   : AtomicBool,
+    pub finished : AtomicBool,
+
+    // Last callback that played something, see `publish`. Times are nanos since `epoch`
+    pub epoch    : Instant,
+    pub sequence : AtomicUsize, // Odd while it's being written
+    pub stamped  : AtomicUsize, // Position after the callback
+    pub rendered : AtomicU64,   // When the callback ran
+    pub heard    : AtomicU64,   // When `stamped` comes out of the speakers
+    pub reset    : AtomicU64,   // Callbacks before this were for a position that's gone
+}
+
+impl Shared {
+    pub fn new() -> Shared {
+        return Shared {
+            position : AtomicUsize::new(0),
+            paused   : AtomicBool::new(true),
+            finished : AtomicBool::new(false),
+
+            epoch    : Instant::now(),
+            sequence : AtomicUsize::new(0),
+            stamped  : AtomicUsize::new(0),
+            rendered : AtomicU64::new(0),
+            heard    : AtomicU64::new(0),
+            reset    : AtomicU64::new(0),
+        };
+    }
+
+    pub fn nanos(&self) -> u64 {
+        return self.epoch.elapsed().as_nanos() as u64;
+    }
+
+    // Only ever called by the callback, so there's a single writer
+    fn publish(&self, position: usize, rendered: Duration, heard: Duration) {
+        let sequence = self.sequence.load(Ordering::Relaxed);
+        self.sequence.store(sequence + 1, Ordering::Relaxed);
+        atomic::fence(Ordering::Release);
+
+        self.stamped.store(position, Ordering::Relaxed);
+        self.rendered.store(rendered.as_nanos() as u64, Ordering::Relaxed);
+        self.heard.store(heard.as_nanos() as u64, Ordering::Relaxed);
+        self.sequence.store(sequence + 2, Ordering::Release);
+    }
+
+    // Position and when it's heard, if a callback played anything since the last reset
+    pub fn stamp(&self) -> Option<(usize, u64)> {
+        loop {
+            let sequence = self.sequence.load(Ordering::Acquire);
+            if sequence == 0 {
+                return None;
+            }
+
+            if sequence % 2 == 1 {
+                std::hint::spin_loop();
+                continue;
+            }
+
+            let stamped = self.stamped.load(Ordering::Relaxed);
+            let rendered = self.rendered.load(Ordering::Relaxed);
+            let heard = self.heard.load(Ordering::Relaxed);
+            atomic::fence(Ordering::Acquire);
+            if self.sequence.load(Ordering::Relaxed) != sequence {
+                continue;
+            }
+
+            if rendered < self.reset.load(Ordering::Acquire) {
+                return None;
+            }
+
+            return Some((stamped, heard));
+        }
+    }
+}
+
+// Owned by the audio callback. Never locks, allocates or frees
+pub(crate) struct Renderer {
+    controls      : Arc<ArrayQueue<Control>>,
+    garbage       : Arc<ArrayQueue<Garbage>>,
+    shared        : Arc<Shared>,
+
+    stream        : Option<StreamReader>,
+    stems         : Vec<Option<StreamReader>>,
+    behind        : [usize; MAX_STEMS], // Samples a stem missed while its decoder caught up
+    scratch       : Vec<f32>,
+    mixer         : Mixer,
+    position      : usize,
+    published     : usize, // Last position seen in `shared`
+    paused        : bool,
+    volume        : f32,
+    gains         : [(f32, f32); TRACK_COUNT],
+    buses         : [f32; BUS_COUNT],
+    rate          : f64,
+
+    sample_rate   : u32,
+    channel_count : usize,
+}
+
+impl Renderer {
+    pub fn new(controls: Arc<ArrayQueue<Control>>, garbage: Arc<ArrayQueue<Garbage>>, shared: Arc<Shared>, sample_rate: u32, channel_count: usize) -> Renderer {
+        return Renderer {
+            controls      : controls,
+            garbage       : garbage,
+            shared        : shared,
+
+            stream        : None,
+            stems         : (0 .. MAX_STEMS).map(|_| None).collect(),
+            behind        : [0; MAX_STEMS],
+            scratch       : vec![0.0; STEM_SCRATCH_FRAMES * channel_count],
+            mixer         : Mixer::new(),
+            position      : 0,
+            published     : 0,
+            paused        : true,
+            volume        : 1.0,
+            gains         : [(1.0, 1.0); TRACK_COUNT],
+            buses         : [1.0; BUS_COUNT],
+            rate          : 1.0,
+
+            sample_rate   : sample_rate,
+            channel_count : channel_count,
+        };
+    }
+
+    // `latency` is how long until the start of `data` is heard
+    pub fn render(&mut self, data: &mut [f32], latency: Duration) {
+        let rendered = self.shared.epoch.elapsed();
+        while let Some(control) = self.controls.pop() {
+            self.apply(control);
+        }
+
+        data.fill(0.0);
+        let stream = match (&mut self.stream, self.paused) {
+            (Some(stream), false) => stream,
+            _ => return,
+        };
+
+        // Silence while the decoder catches up, without moving on
+        let (read, ended) = stream.read(data);
+        let played = if ended { data.len() } else { read };
+
+        // Song bus first, everything else goes on top of it
+        let bus = self.buses[Bus::Song as usize];
+        let gains = self.gains[Track::Song.index()];
+        for (i, sample) in data[.. played].iter_mut().enumerate() {
+            *sample *= channel_gain(i % self.channel_count, self.channel_count, gains) * bus;
+        }
+
+        self.mix_stems(&mut data[.. played]);
+
+        let garbage = &self.garbage;
+        let samples_per_second = (self.sample_rate as usize * self.channel_count) as f64 / self.rate;
+        self.mixer.mix(&mut data[.. played], self.position, samples_per_second, self.channel_count, &self.buses, |voice| discard(garbage, Garbage::Voice(voice)));
+
+        if self.volume != 1.0 {
+            data[.. played].iter_mut().for_each(|sample| *sample *= self.volume);
+        }
+
+        // A seek that's still in the queue already moved the position, so it's kept
+        self.position += played;
+        let _ = self.shared.position.compare_exchange(self.published, self.position, Ordering::AcqRel, Ordering::Relaxed);
+        self.published = self.position;
+
+        // Lets the clock follow the audio between callbacks
+        let frames = (played / self.channel_count) as u64;
+        let heard = rendered + latency + Duration::from_nanos(frames * 1_000_000_000 / self.sample_rate as u64);
+        self.shared.publish(self.position, rendered, heard);
+
+        if ended {
+            self.paused = true;
+            self.shared.paused.store(true, Ordering::Relaxed);
+            self.shared.finished.store(true, Ordering::Relaxed);
+        }
+    }
+
+    // Stems follow the song's position, one whose decoder fell behind skips ahead instead of drifting
+    fn mix_stems(&mut self, data: &mut [f32]) {
+        let bus = self.buses[Bus::Song as usize];
+        let channel_count = self.channel_count;
+        for (slot, stem) in self.stems.iter_mut().enumerate() {
+            let stem = match stem {
+                Some(stem) => stem,
+                None => continue,
+            };
+
+            let (left, right) = self.gains[Track::Stem(slot).index()];
+            let gains = (left * bus, right * bus);
+            let behind = &mut self.behind[slot];
+            for chunk in data.chunks_mut(self.scratch.len()) {
+                while *behind > 0 {
+                    let count = (*behind).min(self.scratch.len());
+                    let (read, ended) = stem.read(&mut self.scratch[.. count]);
+                    *behind = if ended { 0 } else { *behind - read };
+                    if read < count {
+                        break;
+                    }
+                }
+
+                if *behind > 0 {
+                    *behind += chunk.len();
+                    continue;
+                }
+
+                let scratch = &mut self.scratch[.. chunk.len()];
+                let (read, ended) = stem.read(scratch);
+                if !ended {
+                    *behind += chunk.len() - read;
+                }
+
+                for (i, (sample, value)) in chunk.iter_mut().zip(&scratch[.. read]).enumerate() {
+                    *sample += value * channel_gain(i % channel_count, channel_count, gains);
+                }
+            }
+        }
+    }
+
+    fn apply(&mut self, control: Control) {
+        let garbage = &self.garbage;
+        match control {
+            Control::Pause(paused) => self.paused = paused,
+
+            Control::Seek(position, generation) => {
+                for stream in self.stream.iter_mut().chain(self.stems.iter_mut().flatten()) {
+                    stream.seek(generation);
+                }
+
+                self.position = position;
+                self.published = position;
+                self.behind = [0; MAX_STEMS];
+            }
+
+            Control::Stream(stream, position, rate) => {
+                if let Some(stream) = mem::replace(&mut self.stream, stream) {
+                    discard(garbage, Garbage::Stream(stream));
+                }
+
+                self.position = position;
+                self.published = position;
+                self.rate = rate;
+                self.behind = [0; MAX_STEMS];
+            }
+
+            Control::Stem(slot, stem, position) => {
+                if let Some(stem) = mem::replace(&mut self.stems[slot], stem) {
+                    discard(garbage, Garbage::Stream(stem));
+                }
+
+                // Song kept playing while the stem was being opened
+                self.behind[slot] = self.position.saturating_sub(position);
+            }
+
+            Control::Mix(track, gains) => self.gains[track.index()] = gains,
+            Control::Bus(bus, gain) => self.buses[bus as usize] = gain,
+            Control::Volume(volume) => self.volume = volume,
+
+            Control::Schedule(voice) => {
+                if let Some(voice) = self.mixer.schedule(voice) {
+                    discard(garbage, Garbage::Voice(voice));
+                }
+            }
+
+            Control::ClearScheduled => self.mixer.clear(|voice| discard(garbage, Garbage::Voice(voice))),
+        }
+    }
+}
+
+// Leaks if nobody collected the garbage for a while, still better than freeing it here
+fn discard(garbage: &ArrayQueue<Garbage>, item: Garbage) {
+    if let Err(item) = garbage.push(item) {
+        mem::forget(item);
+    }
+}
\ No newline at end of file
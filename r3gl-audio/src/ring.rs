@@ -0,0 +1,118 @@
+use std::cell::UnsafeCell;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
+
+// Not ended yet
+const OPEN: usize = usize::MAX;
+
+// Single producer, single consumer sample queue. Neither side ever locks or allocates.
+// Reads and writes are counted from the start and never wrap, only their slots do.
+struct Ring {
+    slots      : Box<[UnsafeCell<f32>]>,
+    frame_size : usize,
+
+    read       : AtomicUsize,
+    write      : AtomicUsize,
+
+    // Samples written before `boundary` belong to generations older than `generation`
+    generation : AtomicU64,
+    boundary   : AtomicUsize,
+    end        : AtomicUsize, // Write count where the current generation ended
+}
+
+// Each slot is only touched by one side at a time, ordered through `read` and `write`
+unsafe impl Sync for Ring {}
+
+pub(crate) struct Producer {
+    ring: Arc<Ring>,
+}
+
+pub(crate) struct Consumer {
+    ring: Arc<Ring>,
+}
+
+// `frame_size` keeps frames whole, so channels never get swapped
+pub(crate) fn ring(capacity: usize, frame_size: usize) -> (Producer, Consumer) {
+    let capacity = capacity - capacity % frame_size;
+    let ring = Arc::new(Ring {
+        slots      : (0 .. capacity).map(|_| UnsafeCell::new(0.0)).collect(),
+        frame_size : frame_size,
+
+        read       : AtomicUsize::new(0),
+        write      : AtomicUsize::new(0),
+
+        generation : AtomicU64::new(0),
+        boundary   : AtomicUsize::new(0),
+        end        : AtomicUsize::new(OPEN),
+    });
+
+    return (Producer { ring: ring.clone() }, Consumer { ring });
+}
+
+impl Producer {
+    // Returns how many samples fit
+    pub fn push(&mut self, samples: &[f32]) -> usize {
+        let ring = &self.ring;
+        let write = ring.write.load(Ordering::Relaxed);
+        let free = ring.slots.len() - (write - ring.read.load(Ordering::Acquire));
+        let count = free.min(samples.len());
+        let count = count - count % ring.frame_size;
+
+        for (i, sample) in samples[.. count].iter().enumerate() {
+            unsafe { *ring.slots[(write + i) % ring.slots.len()].get() = *sample; }
+        }
+
+        ring.write.store(write + count, Ordering::Release);
+        return count;
+    }
+
+    // Everything written so far is stale for consumers waiting on `generation`
+    pub fn start_generation(&mut self, generation: u64) {
+        let ring = &self.ring;
+        ring.end.store(OPEN, Ordering::Relaxed);
+        ring.boundary.store(ring.write.load(Ordering::Relaxed), Ordering::Relaxed);
+        ring.generation.store(generation, Ordering::Release);
+    }
+
+    pub fn finish(&mut self) {
+        self.ring.end.store(self.ring.write.load(Ordering::Relaxed), Ordering::Release);
+    }
+}
+
+impl Consumer {
+    // Returns how many samples were read
+    pub fn pop(&mut self, output: &mut [f32]) -> usize {
+        let ring = &self.ring;
+        let read = ring.read.load(Ordering::Relaxed);
+        let count = (ring.write.load(Ordering::Acquire) - read).min(output.len());
+        let count = count - count % ring.frame_size;
+
+        for (i, sample) in output[.. count].iter_mut().enumerate() {
+            *sample = unsafe { *ring.slots[(read + i) % ring.slots.len()].get() };
+        }
+
+        ring.read.store(read + count, Ordering::Release);
+        return count;
+    }
+
+    // Drops stale samples once the producer got to `generation`, false until then
+    pub fn reached(&mut self, generation: u64) -> bool {
+        let ring = &self.ring;
+        if ring.generation.load(Ordering::Acquire) < generation {
+            return false;
+        }
+
+        // Only ever forward, a newer generation might already be in progress
+        let boundary = ring.boundary.load(Ordering::Relaxed);
+        if boundary > ring.read.load(Ordering::Relaxed) {
+            ring.read.store(boundary, Ordering::Release);
+        }
+
+        return true;
+    }
+
+    pub fn is_finished(&self) -> bool {
+        let end = self.ring.end.load(Ordering::Acquire);
+        return end != OPEN && self.ring.read.load(Ordering::Relaxed) >= end;
+    }
+}
\ No newline at end of file
@@ -0,0 +1,210 @@
+use std::fs::File;
+use std::path::{Path, PathBuf};
+
+use color_eyre::eyre::{Report, Result};
+use instant::Duration;
+use log::warn;
+use symphonia::core::audio::{Channels, SampleBuffer};
+use symphonia::core::codecs::{Decoder, DecoderOptions};
+use symphonia::core::errors::Error as SymphoniaError;
+use symphonia::core::formats::{FormatOptions, FormatReader, SeekMode, SeekTo};
+use symphonia::core::io::{MediaSourceStream, MediaSourceStreamOptions};
+use symphonia::core::meta::MetadataOptions;
+use symphonia::core::probe::Hint;
+use symphonia::core::units::TimeBase;
+use symphonia::default;
+
+// Song on disk, decoded while it plays instead of all at once
+#[derive(Debug, Clone)]
+pub struct AudioFile {
+    path: PathBuf,
+
+    pub(crate) sample_rate   : u32,
+    pub(crate) channel_count : usize,
+    pub(crate) channels      : Channels,
+    pub(crate) frames        : u64,
+}
+
+impl AudioFile {
+    pub fn open(path: impl AsRef<Path>) -> Result<AudioFile> {
+        let path = path.as_ref().to_owned();
+        let mut source = Source::open(&path)?;
+
+        // Some formats only tell after decoding something
+        if source.channels.is_none() {
+            source.next()?;
+        }
+
+        let channels = source.channels.ok_or_else(|| Report::msg("No audio data decoded"))?;
+        let frames = match source.frames {
+            Some(frames) => frames,
+            None => source.count_frames()?,
+        };
+
+        return Ok(AudioFile {
+            sample_rate   : source.sample_rate,
+            channel_count : channels.count(),
+            channels      : channels,
+            frames        : frames,
+            path          : path,
+        });
+    }
+
+    pub fn duration(&self) -> Duration {
+        return Duration::from_secs_f64(self.frames as f64 / self.sample_rate as f64);
+    }
+
+    pub(crate) fn source(&self) -> Result<Source> {
+        return Source::open(&self.path);
+    }
+}
+
+// Decoder for a single track, hands out planar packets
+pub(crate) struct Source {
+    format    : Box<dyn FormatReader>,
+    decoder   : Box<dyn Decoder>,
+    track     : u32,
+    time_base : Option<TimeBase>,
+
+    sample_rate : u32,
+    channels    : Option<Channels>,
+    frames      : Option<u64>,
+
+    // Frames to drop after a seek that landed early
+    skip      : u64,
+}
+
+impl Source {
+    fn open(path: &Path) -> Result<Source> {
+        let mut hint = Hint::new();
+        if let Some(extension) = path.extension().and_then(|s| s.to_str()) {
+            hint.with_extension(extension);
+        }
+
+        let media_source_stream = MediaSourceStream::new(Box::new(File::open(path)?), MediaSourceStreamOptions::default());
+        let options = FormatOptions { enable_gapless: true, ..FormatOptions::default() };
+        let probe = default::get_probe().format(&hint, media_source_stream, &options, &MetadataOptions::default())?;
+
+        let format = probe.format;
+        let track = format.default_track().ok_or_else(|| Report::msg("No default track in audio file"))?;
+        let (track, params) = (track.id, track.codec_params.clone());
+        let decoder = default::get_codecs().make(&params, &DecoderOptions::default())?;
+
+        return Ok(Source {
+            track     : track,
+            format    : format,
+            decoder   : decoder,
+            time_base : params.time_base,
+
+            sample_rate : params.sample_rate.ok_or_else(|| Report::msg("Unknown sample rate"))?,
+            channels    : params.channels,
+            frames      : params.n_frames,
+
+            skip      : 0,
+        });
+    }
+
+    // `None` once the track is over
+    pub(crate) fn next(&mut self) -> Result<Option<Vec<Vec<f32>>>> {
+        loop {
+            let packet = match self.format.next_packet() {
+                Ok(packet) => packet,
+                Err(SymphoniaError::IoError(_)) => return Ok(None),
+                Err(err) => return Err(err.into()),
+            };
+
+            if packet.track_id() != self.track {
+                continue;
+            }
+
+            let buffer = match self.decoder.decode(&packet) {
+                Ok(buffer) => buffer,
+                Err(SymphoniaError::DecodeError(err)) => {
+                    warn!("Skipping undecodable packet: {}", err);
+                    continue;
+                }
+
+                Err(err) => return Err(err.into()),
+            };
+
+            if buffer.frames() == 0 {
+                continue;
+            }
+
+            let spec = *buffer.spec();
+            let mut samples = SampleBuffer::new(buffer.frames() as u64, spec);
+            samples.copy_interleaved_ref(buffer);
+            self.channels = Some(spec.channels);
+
+            let count = spec.channels.count();
+            let mut planar = vec![Vec::with_capacity(samples.len() / count); count];
+            for frame in samples.samples().chunks(count) {
+                for (channel, sample) in frame.iter().enumerate() {
+                    planar[channel].push(*sample);
+                }
+            }
+
+            if self.skip > 0 {
+                let skipped = (self.skip as usize).min(planar[0].len());
+                planar.iter_mut().for_each(|channel| { channel.drain(.. skipped); });
+                self.skip -= skipped as u64;
+
+                if planar[0].is_empty() {
+                    continue;
+                }
+            }
+
+            return Ok(Some(planar));
+        }
+    }
+
+    pub(crate) fn seek(&mut self, frame: u64) -> Result<()> {
+        // Going through seconds can land a frame short, the timestamp is exact
+        let seeked = self.format.seek(SeekMode::Accurate, SeekTo::TimeStamp {
+            ts: self.frames_to_ts(frame),
+            track_id: self.track,
+        })?;
+
+        self.decoder.reset();
+        self.skip = self.ts_to_frames(seeked.required_ts.saturating_sub(seeked.actual_ts));
+        return Ok(());
+    }
+
+    // Without a frame count in the header every packet has to be looked at, but not decoded
+    fn count_frames(&mut self) -> Result<u64> {
+        let mut duration = 0;
+        loop {
+            match self.format.next_packet() {
+                Ok(packet) if packet.track_id() == self.track => duration += packet.dur(),
+                Ok(_) => {},
+                Err(SymphoniaError::IoError(_)) => break,
+                Err(err) => return Err(err.into()),
+            }
+        }
+
+        return Ok(self.ts_to_frames(duration));
+    }
+
+    fn ts_to_frames(&self, ts: u64) -> u64 {
+        return match self.time_base {
+            Some(time_base) => {
+                let time = time_base.calc_time(ts);
+                ((time.seconds as f64 + time.frac) * self.sample_rate as f64).round() as u64
+            }
+
+            None => ts,
+        };
+    }
+
+    fn frames_to_ts(&self, frames: u64) -> u64 {
+        return match self.time_base {
+            Some(time_base) => {
+                let units = frames as u128 * time_base.denom as u128;
+                let per_ts = time_base.numer as u128 * self.sample_rate as u128;
+                ((units + per_ts / 2) / per_ts) as u64
+            }
+
+            None => frames,
+        };
+    }
+}
\ No newline at end of file
@@ -0,0 +1,108 @@
+use std::f32::consts::TAU;
+use std::sync::Arc;
+
+use rustfft::{Fft, FftPlanner, num_complex::Complex};
+
+// Samples per transform, half of it ends up as frequency bins
+const FFT_SIZE: usize = 1024;
+
+// No overlap, a song is a lot of columns already
+const HOP_SIZE: usize = 1024;
+
+// Quieter than this is drawn as silence
+const FLOOR_DB: f32 = -80.0;
+
+// Loudness of every frequency over time, stored as bytes to keep whole songs small
+#[derive(Debug, Clone)]
+pub struct Spectrogram {
+    sample_rate : u32,
+    columns     : Vec<u8>, // `bins()` per column, lowest frequency first
+}
+
+impl Spectrogram {
+    pub fn bins(&self) -> usize {
+        return FFT_SIZE / 2;
+    }
+
+    pub fn max_frequency(&self) -> f32 {
+        return self.sample_rate as f32 / 2.0;
+    }
+
+    // Loudness from 0 to 255 around `time` seconds
+    pub fn column_at(&self, time: f64) -> Option<&[u8]> {
+        if time < 0.0 {
+            return None;
+        }
+
+        let start = (time * self.sample_rate as f64 / HOP_SIZE as f64) as usize * self.bins();
+        return self.columns.get(start .. start + self.bins());
+    }
+}
+
+// Takes mono samples as they're decoded
+pub(crate) struct SpectrogramBuilder {
+    sample_rate : u32,
+    fft         : Arc<dyn Fft<f32>>,
+    window      : Vec<f32>,
+
+    pending     : Vec<f32>,
+    buffer      : Vec<Complex<f32>>,
+    columns     : Vec<u8>,
+}
+
+impl SpectrogramBuilder {
+    pub fn new(sample_rate: u32) -> SpectrogramBuilder {
+        return SpectrogramBuilder {
+            sample_rate : sample_rate,
+            fft         : FftPlanner::new().plan_fft_forward(FFT_SIZE),
+            window      : (0 .. FFT_SIZE).map(|i| 0.5 - 0.5 * (TAU * i as f32 / FFT_SIZE as f32).cos()).collect(), // Hann
+
+            pending     : Vec::with_capacity(FFT_SIZE),
+            buffer      : vec![Complex::default(); FFT_SIZE],
+            columns     : vec![],
+        };
+    }
+
+    pub fn push(&mut self, samples: &[f32]) {
+        let mut samples = samples;
+        while !samples.is_empty() {
+            let count = (FFT_SIZE - self.pending.len()).min(samples.len());
+            self.pending.extend_from_slice(&samples[.. count]);
+            samples = &samples[count ..];
+
+            if self.pending.len() == FFT_SIZE {
+                self.transform();
+            }
+        }
+    }
+
+    fn transform(&mut self) {
+        for ((value, sample), window) in self.buffer.iter_mut().zip(&self.pending).zip(&self.window) {
+            *value = Complex::new(sample * window, 0.0);
+        }
+
+        self.fft.process(&mut self.buffer);
+
+        // Full scale sine is 0 dB, a Hann window halves the amplitude
+        let scale = FFT_SIZE as f32 / 4.0;
+        for bin in &self.buffer[.. FFT_SIZE / 2] {
+            let db = 20.0 * (bin.norm() / scale).max(f32::MIN_POSITIVE).log10();
+            self.columns.push(((db - FLOOR_DB) / -FLOOR_DB * 255.0).clamp(0.0, 255.0) as u8);
+        }
+
+        self.pending.drain(.. HOP_SIZE);
+    }
+
+    pub fn finish(mut self) -> Spectrogram {
+        // Last bit of the song is padded with silence
+        if !self.pending.is_empty() {
+            self.pending.resize(FFT_SIZE, 0.0);
+            self.transform();
+        }
+
+        return Spectrogram {
+            sample_rate : self.sample_rate,
+            columns     : self.columns,
+        };
+    }
+}
\ No newline at end of file
@@ -0,0 +1,360 @@
+use std::sync::Arc;
+use std::thread;
+use std::time::Duration;
+
+use color_eyre::eyre::Result;
+use crossbeam::channel::{unbounded, Receiver, RecvTimeoutError, Sender};
+use log::error;
+use rubato::{SincFixedIn, SincInterpolationParameters, SincInterpolationType, WindowFunction, Resampler};
+
+use crate::{channels::ChannelMap, ring::{ring, Consumer, Producer}, source::{AudioFile, Source}, stretch::TimeStretch};
+
+// Source frames resampled at once
+const BLOCK_SIZE: usize = 1024;
+
+// Resampled blocks decoded ahead of playback, bounds memory use
+const BLOCKS_AHEAD: usize = 32;
+
+// How long the decoder sleeps while the buffer is full
+const WAIT_INTERVAL: Duration = Duration::from_millis(5);
+
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct StreamConfig {
+    pub sample_rate    : u32,
+    pub channel_count  : usize,
+    pub rate           : f64,
+    pub preserve_pitch : bool,
+}
+
+impl StreamConfig {
+    // Interleaved output samples for the whole file
+    pub fn length(&self, file: &AudioFile) -> usize {
+        let frames = file.frames as f64 * self.sample_rate as f64 / file.sample_rate as f64 / self.rate;
+        return frames.ceil() as usize * self.channel_count;
+    }
+}
+
+enum Request {
+    Seek(u64, usize), // Generation, output position
+}
+
+// Controls the decoder thread, samples come out of the matching `StreamReader`
+pub(crate) struct AudioStream {
+    requests: Sender<Request>,
+}
+
+// Playback end of a stream, owned by the audio callback
+pub(crate) struct StreamReader {
+    samples    : Consumer,
+    generation : u64, // Samples of older generations are skipped
+}
+
+impl AudioStream {
+    // Generations are handed out by the caller, so streams that play together can share them
+    pub fn new(file: Arc<AudioFile>, config: StreamConfig, position: usize, generation: u64) -> Result<(AudioStream, StreamReader)> {
+        let source = file.source()?;
+        let (requests, requests_rx) = unbounded();
+        let (mut producer, consumer) = ring(BLOCK_SIZE * BLOCKS_AHEAD * config.channel_count, config.channel_count);
+        producer.start_generation(generation);
+
+        thread::spawn(move || {
+            let mut pipeline = Pipeline::new(source, &file, config);
+            pipeline.seek(position);
+            decode(pipeline, requests_rx, producer);
+        });
+
+        let stream = AudioStream { requests };
+        let reader = StreamReader { samples: consumer, generation: generation };
+        return Ok((stream, reader));
+    }
+
+    // Restarts the decoder at `position`, the reader has to be told about `generation`
+    pub fn seek(&mut self, position: usize, generation: u64) {
+        // Decoder thread only stops when we do
+        let _ = self.requests.send(Request::Seek(generation, position));
+    }
+}
+
+impl StreamReader {
+    pub fn seek(&mut self, generation: u64) {
+        self.generation = generation;
+    }
+
+    // Never waits for the decoder, reads fewer samples than asked for if it's behind.
+    // Returns how many were read and whether the stream ended
+    pub fn read(&mut self, data: &mut [f32]) -> (usize, bool) {
+        if !self.samples.reached(self.generation) {
+            return (0, false);
+        }
+
+        let count = self.samples.pop(data);
+        return (count, count < data.len() && self.samples.is_finished());
+    }
+}
+
+fn decode(mut pipeline: Pipeline, requests: Receiver<Request>, mut producer: Producer) {
+    let mut pending = vec![];
+    let mut written = 0;
+    let mut request = None;
+    loop {
+        // Only the latest seek matters
+        if let Some(Request::Seek(generation, position)) = requests.try_iter().last().or_else(|| request.take()) {
+            pipeline.seek(position);
+            pending.clear();
+            written = 0;
+            producer.start_generation(generation);
+        }
+
+        if written < pending.len() {
+            written += producer.push(&pending[written ..]);
+
+            // Seeks are handled even while waiting for room in the buffer
+            if written < pending.len() {
+                request = match requests.recv_timeout(WAIT_INTERVAL) {
+                    Ok(request) => Some(request),
+                    Err(RecvTimeoutError::Timeout) => None,
+                    Err(RecvTimeoutError::Disconnected) => return,
+                };
+            }
+
+            continue;
+        }
+
+        match pipeline.next() {
+            Ok(Some(samples)) => {
+                pending = samples;
+                written = 0;
+            }
+
+            result => {
+                if let Err(err) = result {
+                    error!("Failed to decode audio: {:#}", err);
+                }
+
+                // Nothing left to do until the next seek
+                producer.finish();
+                request = match requests.recv() {
+                    Ok(request) => Some(request),
+                    Err(_) => return,
+                };
+            }
+        }
+    }
+}
+
+struct Pipeline {
+    source      : Source,
+    config      : StreamConfig,
+    map         : ChannelMap,
+    ratio       : f64, // Resampler ratio
+    scale       : f64, // Output frames per source frame
+
+    resampler   : Option<SincFixedIn<f32>>,
+    resampled   : Vec<Vec<f32>>,
+    fed         : usize, // Source frames resampled since the seek, without padding
+    produced    : usize, // Frames handed out since the seek
+    stretch     : Option<TimeStretch>,
+    pending     : Vec<Vec<f32>>, // Mapped to device channels, waiting for a full block
+    source_done : bool,
+    flushed     : bool, // What the resampler held back at the end came out
+    ended       : bool,
+}
+
+impl Pipeline {
+    fn new(source: Source, file: &AudioFile, config: StreamConfig) -> Pipeline {
+        // Either resample to the rate (pitch follows) or stretch afterwards (pitch is kept)
+        let speed = if config.preserve_pitch { 1.0 } else { config.rate };
+
+        return Pipeline {
+            source      : source,
+            config      : config,
+            map         : ChannelMap::new(file.channels, file.channel_count, config.channel_count),
+            ratio       : config.sample_rate as f64 / file.sample_rate as f64 / speed,
+            scale       : config.sample_rate as f64 / file.sample_rate as f64 / config.rate,
+
+            resampler   : None,
+            resampled   : vec![],
+            fed         : 0,
+            produced    : 0,
+            stretch     : None,
+            pending     : vec![vec![]; config.channel_count],
+            source_done : false,
+            flushed     : false,
+            ended       : false,
+        };
+    }
+
+    fn seek(&mut self, position: usize) {
+        let frame = (position / self.config.channel_count) as f64 / self.scale;
+
+        self.pending.iter_mut().for_each(Vec::clear);
+        self.stretch = (self.config.preserve_pitch && self.config.rate != 1.0).then(|| TimeStretch::new(self.config.rate, self.config.channel_count));
+        self.resampler = SincFixedIn::<f32>::new(
+            self.ratio,
+            2.0,
+            SincInterpolationParameters {
+                sinc_len: 256,
+                f_cutoff: 0.95,
+                interpolation: SincInterpolationType::Linear,
+                oversampling_factor: 256,
+                window: WindowFunction::BlackmanHarris2,
+            },
+            BLOCK_SIZE,
+            self.config.channel_count,
+        ).map_err(|err| error!("Failed to create resampler: {}", err)).ok();
+        self.resampled = self.resampler.as_ref().map_or(vec![], |resampler| resampler.output_buffer_allocate(true));
+
+        self.fed = 0;
+        self.produced = 0;
+
+        // Past the end just ends the stream
+        self.source_done = false;
+        self.flushed = false;
+        self.ended = self.resampler.is_none();
+        if let Err(err) = self.source.seek(frame.round() as u64) {
+            error!("Failed to seek audio: {:#}", err);
+            self.source_done = true;
+        }
+    }
+
+    // Next interleaved block, `None` once everything was played
+    fn next(&mut self) -> Result<Option<Vec<f32>>> {
+        loop {
+            if self.ended {
+                return Ok(None);
+            }
+
+            while self.pending[0].len() < BLOCK_SIZE && !self.source_done {
+                match self.source.next()? {
+                    Some(planar) => {
+                        let mapped = self.map.map(&planar, 0, planar[0].len());
+                        for (pending, mapped) in self.pending.iter_mut().zip(mapped) {
+                            pending.extend(mapped);
+                        }
+                    }
+
+                    None => self.source_done = true,
+                }
+            }
+
+            let resampler = match &mut self.resampler {
+                Some(resampler) => resampler,
+                None => {
+                    self.ended = true;
+                    return Ok(None);
+                }
+            };
+
+            let frames = if !self.pending[0].is_empty() {
+                // Last block is padded with zeroes
+                self.fed += BLOCK_SIZE.min(self.pending[0].len());
+                let block: Vec<Vec<f32>> = self.pending.iter_mut().map(|pending| {
+                    let mut block: Vec<f32> = pending.drain(.. BLOCK_SIZE.min(pending.len())).collect();
+                    block.resize(BLOCK_SIZE, 0.0);
+                    block
+                }).collect();
+
+                resampler.process_into_buffer(&block, &mut self.resampled, None)?.1
+            } else if !self.flushed {
+                // Output lines up with the input from the start, but the filter holds on to the
+                // last of the song until something comes after it
+                self.flushed = true;
+                resampler.process_partial_into_buffer(None::<&[Vec<f32>]>, &mut self.resampled, None)?.1
+            } else {
+                self.ended = true;
+                return Ok(self.stretch.as_mut().map(TimeStretch::finish));
+            };
+
+            // Padding and flushing would play on past the song otherwise
+            let length = (self.fed as f64 * self.ratio).ceil() as usize;
+            let frames = frames.min(length.saturating_sub(self.produced));
+            self.produced += frames;
+            if frames == 0 {
+                continue;
+            }
+
+            let resampled = &self.resampled;
+            let mut samples: Vec<f32> = (0 .. frames).flat_map(|frame| resampled.iter().map(move |channel| channel[frame])).collect();
+            if let Some(stretch) = &mut self.stretch {
+                samples = stretch.process(&samples);
+            }
+
+            return Ok(Some(samples));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::backend::wav::WavWriter;
+
+    // Whole blocks, so nothing but the flush gets the end out of the resampler
+    const FRAMES: usize = BLOCK_SIZE * 43;
+
+    // Silent mono file with a single full scale sample
+    fn impulse(name: &str, sample_rate: u32, at: usize) -> AudioFile {
+        let path = std::env::temp_dir().join(format!("r3gl-audio-{}-{}.wav", std::process::id(), name));
+        let mut samples = vec![0.0; FRAMES];
+        samples[at] = 1.0;
+
+        WavWriter::new(&path, sample_rate, 1).unwrap().write(&samples).unwrap();
+        return AudioFile::open(&path).unwrap();
+    }
+
+    fn render(file: &AudioFile, sample_rate: u32, position: usize) -> Vec<f32> {
+        let config = StreamConfig { sample_rate, channel_count: 1, rate: 1.0, preserve_pitch: false };
+        let mut pipeline = Pipeline::new(file.source().unwrap(), file, config);
+        pipeline.seek(position);
+
+        let mut samples = vec![];
+        while let Some(block) = pipeline.next().unwrap() {
+            samples.extend(block);
+        }
+
+        return samples;
+    }
+
+    fn peak(samples: &[f32]) -> usize {
+        return (0 .. samples.len()).max_by(|a, b| samples[*a].abs().total_cmp(&samples[*b].abs())).unwrap();
+    }
+
+    #[test]
+    fn lines_up_at_start() {
+        let samples = render(&impulse("start", 44100, 4410), 48000, 0);
+        assert_eq!(peak(&samples), 4800);
+    }
+
+    #[test]
+    fn lines_up_after_seek() {
+        let samples = render(&impulse("seek", 44100, 4410), 48000, 2400);
+        assert_eq!(peak(&samples), 2400);
+    }
+
+    #[test]
+    fn same_rate_passes_through() {
+        let samples = render(&impulse("same", 44100, 4410), 44100, 0);
+        assert_eq!(samples.len(), FRAMES);
+        assert_eq!(peak(&samples), 4410);
+    }
+
+    #[test]
+    fn keeps_the_end() {
+        let at = FRAMES - 5;
+        let file = impulse("end", 44100, at);
+        let samples = render(&file, 48000, 0);
+
+        let config = StreamConfig { sample_rate: 48000, channel_count: 1, rate: 1.0, preserve_pitch: false };
+        assert_eq!(samples.len(), config.length(&file));
+        assert_eq!(peak(&samples), (at as f64 * 48000.0 / 44100.0).round() as usize);
+    }
+
+    #[test]
+    fn seeks_to_exact_frame() {
+        // Same rate, so nothing but the seek can move the peak
+        for position in (1 .. 40000).step_by(997) {
+            let samples = render(&impulse("exact", 48000, 40000), 48000, position);
+            assert_eq!(peak(&samples), 40000 - position, "seeked to {position}");
+        }
+    }
+}
\ No newline at end of file
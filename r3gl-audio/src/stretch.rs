@@ -0,0 +1,114 @@
+use std::collections::VecDeque;
+use std::f32::consts::PI;
+
+// Frames per analysis window, consecutive windows overlap by half
+const WINDOW : usize = 2048;
+const HOP    : usize = WINDOW / 2;
+
+// How far (in frames) a window may be shifted to line up with the previous one
+const SEEK   : usize = 256;
+
+// Only every n-th frame is compared when looking for the best shift
+const STRIDE : usize = 4;
+
+// WSOLA time stretching, changes tempo while keeping the pitch
+pub struct TimeStretch {
+    rate     : f64,
+    channels : usize,
+    window   : Vec<f32>,
+
+    input    : VecDeque<f32>,
+    position : f64,           // Start of the next window in `input`, in frames
+    previous : Option<usize>, // Start of the last window in `input`, in frames
+    output   : Vec<f32>,      // Overlap-add accumulator
+}
+
+impl TimeStretch {
+    pub fn new(rate: f64, channels: usize) -> TimeStretch {
+        // Periodic Hann, sums up to 1 at half overlap
+        let window = (0 .. WINDOW)
+            .map(|i| 0.5 - 0.5 * (2.0 * PI * i as f32 / WINDOW as f32).cos())
+            .collect();
+
+        return TimeStretch {
+            rate     : rate,
+            channels : channels,
+            window   : window,
+
+            input    : VecDeque::new(),
+            position : 0.0,
+            previous : None,
+            output   : vec![0.0; WINDOW * channels],
+        };
+    }
+
+    // Takes and returns interleaved samples
+    pub fn process(&mut self, samples: &[f32]) -> Vec<f32> {
+        self.input.extend(samples);
+
+        let mut processed = Vec::new();
+        loop {
+            let frames = self.input.len() / self.channels;
+            let target = self.position.round() as usize;
+            if target + WINDOW + SEEK > frames {
+                break;
+            }
+
+            let start = match self.previous {
+                Some(previous) => self.best_start(previous + HOP, target),
+                None           => target,
+            };
+
+            for i in 0 .. WINDOW {
+                for c in 0 .. self.channels {
+                    self.output[i * self.channels + c] += self.input[(start + i) * self.channels + c] * self.window[i];
+                }
+            }
+
+            processed.extend(self.output.drain(.. HOP * self.channels));
+            self.output.resize(WINDOW * self.channels, 0.0);
+            self.previous = Some(start);
+            self.position += HOP as f64 * self.rate;
+
+            // Drop everything the next window can't reach
+            let consumed = start.min((self.position as usize).saturating_sub(SEEK));
+            self.input.drain(.. consumed * self.channels);
+            self.position -= consumed as f64;
+            self.previous = Some(start - consumed);
+        }
+
+        return processed;
+    }
+
+    // Whatever is left in the accumulator
+    pub fn finish(&mut self) -> Vec<f32> {
+        self.input.clear();
+        return self.output.drain(..).collect();
+    }
+
+    // Start near `target` that continues the previous window the smoothest
+    fn best_start(&self, natural: usize, target: usize) -> usize {
+        let frame = |start: usize, i: usize| -> f32 {
+            return (0 .. self.channels).map(|c| self.input[(start + i) * self.channels + c]).sum();
+        };
+
+        let mut best = target;
+        let mut best_score = f32::MIN;
+        for start in target.saturating_sub(SEEK) ..= target + SEEK {
+            let (mut correlation, mut energy) = (0.0, 0.0);
+            for i in (0 .. HOP).step_by(STRIDE) {
+                let sample = frame(start, i);
+                correlation += sample * frame(natural, i);
+                energy += sample * sample;
+            }
+
+            let score = correlation / energy.sqrt().max(f32::EPSILON);
+            if score > best_score {
+                best_score = score;
+                best = start;
+            }
+        }
+
+        return best;
+    }
+}
\ No newline at end of file
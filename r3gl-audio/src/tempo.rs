@@ -0,0 +1,206 @@
+use std::f32::consts::TAU;
+use std::sync::Arc;
+
+use rustfft::{Fft, FftPlanner, num_complex::Complex};
+
+use crate::AudioData;
+
+// Onsets are found in windows of this many samples, one every `HOP_SIZE`
+const FFT_SIZE: usize = 1024;
+const HOP_SIZE: usize = 256;
+
+// Tempos outside of this are taken as a multiple of one inside
+const MIN_BPM: f64 = 60.0;
+const MAX_BPM: f64 = 240.0;
+
+// Most tempos are around this, breaks ties between a tempo and its double or half
+const PRIOR_BPM: f64 = 120.0;
+
+// Envelope frames the local average is taken over, each side
+const AVERAGE_RADIUS: usize = 16;
+
+// Phases tried per envelope frame
+const PHASE_STEPS: f64 = 2.0;
+
+const MAX_CANDIDATES: usize = 5;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct TempoCandidate {
+    pub bpm        : f64,
+    pub offset     : f64, // First beat in ms
+    pub confidence : f32, // 0.0 ..= 1.0
+}
+
+// Most likely first
+pub fn detect_tempo(data: &AudioData) -> Vec<TempoCandidate> {
+    let mut builder = OnsetBuilder::new(data.sample_rate);
+    let frames = data.samples.iter().map(Vec::len).min().unwrap_or(0);
+    let mono: Vec<f32> = (0 .. frames).map(|frame| data.samples.iter().map(|channel| channel[frame]).sum::<f32>() / data.samples.len() as f32).collect();
+    builder.push(&mono);
+    return builder.finish();
+}
+
+// Spectral flux of mono samples as they're decoded
+pub(crate) struct OnsetBuilder {
+    sample_rate : u32,
+    fft         : Arc<dyn Fft<f32>>,
+    window      : Vec<f32>,
+
+    pending     : Vec<f32>,
+    buffer      : Vec<Complex<f32>>,
+    previous    : Vec<f32>, // Magnitudes of the last window
+    flux        : Vec<f32>,
+}
+
+impl OnsetBuilder {
+    pub fn new(sample_rate: u32) -> OnsetBuilder {
+        return OnsetBuilder {
+            sample_rate : sample_rate,
+            fft         : FftPlanner::new().plan_fft_forward(FFT_SIZE),
+            window      : (0 .. FFT_SIZE).map(|i| 0.5 - 0.5 * (TAU * i as f32 / FFT_SIZE as f32).cos()).collect(), // Hann
+
+            pending     : Vec::with_capacity(FFT_SIZE),
+            buffer      : vec![Complex::default(); FFT_SIZE],
+            previous    : vec![0.0; FFT_SIZE / 2],
+            flux        : vec![],
+        };
+    }
+
+    pub fn push(&mut self, samples: &[f32]) {
+        let mut samples = samples;
+        while !samples.is_empty() {
+            let count = (FFT_SIZE - self.pending.len()).min(samples.len());
+            self.pending.extend_from_slice(&samples[.. count]);
+            samples = &samples[count ..];
+
+            if self.pending.len() == FFT_SIZE {
+                self.transform();
+            }
+        }
+    }
+
+    // Only increases in loudness count, log compressed so quiet parts still show up
+    fn transform(&mut self) {
+        for ((value, sample), window) in self.buffer.iter_mut().zip(&self.pending).zip(&self.window) {
+            *value = Complex::new(sample * window, 0.0);
+        }
+
+        self.fft.process(&mut self.buffer);
+
+        let mut flux = 0.0;
+        for (bin, previous) in self.buffer[.. FFT_SIZE / 2].iter().zip(self.previous.iter_mut()) {
+            let magnitude = (1.0 + 100.0 * bin.norm()).ln();
+            flux += (magnitude - *previous).max(0.0);
+            *previous = magnitude;
+        }
+
+        self.flux.push(flux);
+        self.pending.drain(.. HOP_SIZE);
+    }
+
+    pub fn finish(self) -> Vec<TempoCandidate> {
+        let rate = self.sample_rate as f64 / HOP_SIZE as f64; // Envelope frames per second
+        let envelope = envelope(&self.flux);
+        if envelope.is_empty() {
+            return vec![];
+        }
+
+        // Autocorrelation over every beat length in range
+        let min_lag = (60.0 * rate / MAX_BPM).floor() as usize;
+        let max_lag = ((60.0 * rate / MIN_BPM).ceil() as usize).min(envelope.len() / 2);
+        if min_lag < 1 || min_lag >= max_lag {
+            return vec![];
+        }
+
+        let energy = autocorrelation(&envelope, 0).max(f32::MIN_POSITIVE);
+        let correlation: Vec<f32> = (0 ..= max_lag * 2).map(|lag| autocorrelation(&envelope, lag) / energy).collect();
+
+        // Comb over the first few multiples, so a beat only scores if it keeps going
+        let score = |lag: usize| -> f32 {
+            let comb = (1 ..= 4).map(|multiple| correlation.get(lag * multiple).copied().unwrap_or(0.0) / multiple as f32).sum::<f32>();
+            let octaves = (60.0 * rate / lag as f64 / PRIOR_BPM).log2();
+            return comb * (-0.5 * octaves * octaves).exp() as f32;
+        };
+
+        let scores: Vec<f32> = (min_lag ..= max_lag).map(score).collect();
+        let mut peaks: Vec<(usize, f32)> = (1 .. scores.len() - 1)
+            .filter(|&i| scores[i] > 0.0 && scores[i] >= scores[i - 1] && scores[i] >= scores[i + 1])
+            .map(|i| (min_lag + i, scores[i]))
+            .collect();
+
+        peaks.sort_by(|a, b| b.1.total_cmp(&a.1));
+
+        let best = peaks.first().map_or(1.0, |peak| peak.1);
+        let comb_weight = (1 ..= 4).map(|multiple| 1.0 / multiple as f32).sum::<f32>();
+        let mut candidates: Vec<TempoCandidate> = vec![];
+        for (lag, score) in peaks {
+            let (period, phase) = align(&envelope, lag);
+            let bpm = 60.0 * rate / period;
+
+            // Neighbouring lags end up on the same tempo once refined
+            if candidates.iter().any(|candidate| (candidate.bpm / bpm - 1.0).abs() < 0.02) {
+                continue;
+            }
+
+            // Flux peaks where the window rises fastest, about a hop past its center
+            let offset = (phase * HOP_SIZE as f64 + (FFT_SIZE / 2 + HOP_SIZE) as f64) / self.sample_rate as f64 * 1000.0;
+            let beat_length = 60000.0 / bpm;
+            candidates.push(TempoCandidate {
+                bpm        : bpm,
+                offset     : offset % beat_length,
+                confidence : (score / best * (score / comb_weight).min(1.0)).clamp(0.0, 1.0),
+            });
+
+            if candidates.len() == MAX_CANDIDATES {
+                break;
+            }
+        }
+
+        return candidates;
+    }
+}
+
+// Flux above its local average, so only peaks are left
+fn envelope(flux: &[f32]) -> Vec<f32> {
+    return (0 .. flux.len()).map(|i| {
+        let window = &flux[i.saturating_sub(AVERAGE_RADIUS) .. (i + AVERAGE_RADIUS + 1).min(flux.len())];
+        let average = window.iter().sum::<f32>() / window.len() as f32;
+        (flux[i] - average).max(0.0)
+    }).collect();
+}
+
+fn autocorrelation(envelope: &[f32], lag: usize) -> f32 {
+    if lag >= envelope.len() {
+        return 0.0;
+    }
+
+    let sum: f32 = envelope.iter().zip(&envelope[lag ..]).map(|(a, b)| a * b).sum();
+    return sum / (envelope.len() - lag) as f32;
+}
+
+// Exact beat length and the phase of the first beat, both in envelope frames
+fn align(envelope: &[f32], lag: usize) -> (f64, f64) {
+    let mut best = (lag as f64, 0.0, 0.0);
+    let mut bins = vec![];
+    for step in -100 ..= 100 {
+        let period = lag as f64 + step as f64 / 100.0;
+
+        // Folded onto a single beat, onsets only pile up in one place if the period is right
+        bins.clear();
+        bins.resize((period * PHASE_STEPS) as usize + 1, 0.0);
+        for (frame, value) in envelope.iter().enumerate() {
+            bins[(frame as f64 % period * PHASE_STEPS) as usize] += value;
+        }
+
+        // Onsets smear over neighbouring frames
+        let count = bins.len();
+        for i in 0 .. count {
+            let total = bins[i] + 0.5 * (bins[(i + count - 1) % count] + bins[(i + 1) % count]);
+            if total > best.2 {
+                best = (period, i as f64 / PHASE_STEPS, total);
+            }
+        }
+    }
+
+    return (best.0, best.1);
+}
\ No newline at end of file
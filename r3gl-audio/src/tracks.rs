@@ -0,0 +1,92 @@
+// Stems played along with the song, like a guide track or isolated drums
+pub const MAX_STEMS: usize = 8;
+
+// Song and every stem slot
+pub(crate) const TRACK_COUNT: usize = MAX_STEMS + 1;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Track {
+    Song,
+    Stem(usize), // Slot from `Audio::add_stem`
+}
+
+impl Track {
+    pub(crate) fn index(self) -> usize {
+        return match self {
+            Track::Song => 0,
+            Track::Stem(slot) => slot + 1,
+        };
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct TrackMix {
+    pub gain : f32,
+    pub pan  : f32, // -1.0 is left, 1.0 is right
+    pub mute : bool,
+    pub solo : bool,
+}
+
+impl Default for TrackMix {
+    fn default() -> Self {
+        return Self {
+            gain : 1.0,
+            pan  : 0.0,
+            mute : false,
+            solo : false,
+        };
+    }
+}
+
+impl TrackMix {
+    // Left and right gain. Balance rather than constant power, so the center stays at `gain`
+    pub(crate) fn gains(&self, any_solo: bool) -> (f32, f32) {
+        if self.mute || (any_solo && !self.solo) {
+            return (0.0, 0.0);
+        }
+
+        let pan = self.pan.clamp(-1.0, 1.0);
+        return (self.gain * (1.0 - pan).min(1.0), self.gain * (1.0 + pan).min(1.0));
+    }
+}
+
+// Everything that gets played goes through one of these, and then the master volume
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Bus {
+    Song, // Song and stems
+    Hitsounds,
+    Metronome,
+}
+
+pub(crate) const BUS_COUNT: usize = 3;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct BusMix {
+    pub volume : f32,
+    pub mute   : bool,
+}
+
+impl Default for BusMix {
+    fn default() -> Self {
+        return Self {
+            volume : 1.0,
+            mute   : false,
+        };
+    }
+}
+
+impl BusMix {
+    pub(crate) fn gain(&self) -> f32 {
+        return if self.mute { 0.0 } else { self.volume.max(0.0) };
+    }
+}
+
+// Gain of one channel of an interleaved frame, panning only touches the front pair
+pub(crate) fn channel_gain(channel: usize, channel_count: usize, (left, right): (f32, f32)) -> f32 {
+    return match (channel_count, channel) {
+        (1, _) => (left + right) / 2.0,
+        (_, 0) => left,
+        (_, 1) => right,
+        _ => (left + right) / 2.0,
+    };
+}
\ No newline at end of file
@@ -0,0 +1,111 @@
+// Frames summarised by one peak at the finest level
+const BUCKET_FRAMES: usize = 64;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct Peak {
+    pub min : f32,
+    pub max : f32,
+    pub rms : f32,
+}
+
+impl Peak {
+    // `peaks` can't be empty
+    fn merge(peaks: &[Peak]) -> Peak {
+        let (min, max, squares) = peaks.iter().fold((f32::MAX, f32::MIN, 0.0), |(min, max, squares), peak| {
+            (min.min(peak.min), max.max(peak.max), squares + peak.rms * peak.rms)
+        });
+
+        return Peak { min, max, rms: (squares / peaks.len() as f32).sqrt() };
+    }
+}
+
+// Min, max and RMS of a song at every zoom level, each level has half the peaks of the one before
+#[derive(Debug, Clone)]
+pub struct Waveform {
+    sample_rate : u32,
+    frames      : usize,
+    levels      : Vec<Vec<Peak>>,
+}
+
+impl Waveform {
+    pub fn duration(&self) -> f64 {
+        return self.frames as f64 / self.sample_rate as f64;
+    }
+
+    // One peak per column from `start` to `end` seconds, columns outside of the song are silent
+    pub fn peaks(&self, start: f64, end: f64, columns: usize) -> Vec<Peak> {
+        let sample_rate = self.sample_rate as f64;
+        let frames_per_column = (end - start) * sample_rate / columns as f64;
+
+        // Coarsest level that still has a peak for every column
+        let level = (frames_per_column / BUCKET_FRAMES as f64).log2().floor();
+        let level = if level.is_finite() { level.clamp(0.0, (self.levels.len() - 1) as f64) as usize } else { 0 };
+        let peaks = &self.levels[level];
+        let bucket = (BUCKET_FRAMES << level) as f64;
+
+        return (0 .. columns).map(|column| {
+            let from = (start * sample_rate + column as f64 * frames_per_column) / bucket;
+            let to = (start * sample_rate + (column + 1) as f64 * frames_per_column) / bucket;
+            let (from, to) = (from.floor().max(0.0) as usize, (to.ceil().max(0.0) as usize).min(peaks.len()));
+            if from < to { Peak::merge(&peaks[from .. to]) } else { Peak::default() }
+        }).collect();
+    }
+}
+
+// Takes mono samples as they're decoded
+pub(crate) struct WaveformBuilder {
+    sample_rate : u32,
+    frames      : usize,
+
+    bucket      : Peak, // Sum of squares in `rms` until it's full
+    peaks       : Vec<Peak>,
+}
+
+impl WaveformBuilder {
+    pub fn new(sample_rate: u32) -> WaveformBuilder {
+        return WaveformBuilder {
+            sample_rate : sample_rate,
+            frames      : 0,
+
+            bucket      : Peak { min: f32::MAX, max: f32::MIN, rms: 0.0 },
+            peaks       : vec![],
+        };
+    }
+
+    pub fn push(&mut self, samples: &[f32]) {
+        for sample in samples {
+            self.bucket.min = self.bucket.min.min(*sample);
+            self.bucket.max = self.bucket.max.max(*sample);
+            self.bucket.rms += sample * sample;
+            self.frames += 1;
+
+            if self.frames % BUCKET_FRAMES == 0 {
+                self.close_bucket(BUCKET_FRAMES);
+            }
+        }
+    }
+
+    fn close_bucket(&mut self, frames: usize) {
+        self.bucket.rms = (self.bucket.rms / frames as f32).sqrt();
+        self.peaks.push(self.bucket);
+        self.bucket = Peak { min: f32::MAX, max: f32::MIN, rms: 0.0 };
+    }
+
+    pub fn finish(mut self) -> Waveform {
+        if self.frames % BUCKET_FRAMES != 0 {
+            self.close_bucket(self.frames % BUCKET_FRAMES);
+        }
+
+        let mut levels = vec![self.peaks];
+        while levels.last().unwrap().len() > 1 {
+            let level = levels.last().unwrap().chunks(2).map(Peak::merge).collect();
+            levels.push(level);
+        }
+
+        return Waveform {
+            sample_rate : self.sample_rate,
+            frames      : self.frames,
+            levels      : levels,
+        };
+    }
+}
\ No newline at end of file
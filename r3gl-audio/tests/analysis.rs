@@ -0,0 +1,47 @@
+#![allow(clippy::needless_return)]
+
+use std::thread;
+use std::time::{Duration, Instant};
+
+use color_eyre::eyre::Result;
+use r3gl_audio::{Analysis, AudioFile, Summary};
+
+mod common;
+
+const SAMPLE_RATE: u32 = 44100;
+
+// Waits up to `timeout` for the analysis to stop
+fn wait(analysis: &Analysis, timeout: Duration) -> Option<Result<Summary>> {
+    let start = Instant::now();
+    while start.elapsed() < timeout {
+        if let Some(result) = analysis.try_take() {
+            return Some(result);
+        }
+
+        thread::sleep(Duration::from_millis(10));
+    }
+
+    return None;
+}
+
+#[test]
+fn finishes_when_left_alone() {
+    let song = common::write_wav("analysis-short.wav", SAMPLE_RATE, 1, &common::sine(SAMPLE_RATE, 1, 440.0, 2.0));
+    let analysis = Analysis::start(&AudioFile::open(song).unwrap(), true);
+
+    let summary = wait(&analysis, Duration::from_secs(60)).expect("analysis never finished").unwrap();
+    assert!(summary.spectrogram.is_some());
+}
+
+// Takes far longer than the timeout to analyze, so it only stops in time by checking the flag
+#[test]
+fn stops_when_cancelled() {
+    let len = SAMPLE_RATE as usize * 60 * 10;
+    let song = common::write_wav_from("analysis-long.wav", SAMPLE_RATE, 1, len, (0 .. len).map(|frame| (frame as f32 * 0.05).sin()));
+    let analysis = Analysis::start(&AudioFile::open(song).unwrap(), true);
+    thread::sleep(Duration::from_millis(50));
+    analysis.cancel();
+
+    let result = wait(&analysis, Duration::from_secs(2)).expect("analysis kept running after it was cancelled");
+    assert!(result.is_err());
+}
\ No newline at end of file
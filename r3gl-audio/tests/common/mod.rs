@@ -0,0 +1,131 @@
+// Not every test uses every helper
+#![allow(dead_code, clippy::redundant_field_names)]
+
+use std::fs::{self, File};
+use std::io::{BufWriter, Write};
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::Duration;
+
+use color_eyre::eyre::Result;
+use r3gl_audio::{Backend, Render};
+
+// Per process, so parallel runs don't share files
+pub fn temp_path(name: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("r3gl-audio-{}", std::process::id()));
+    fs::create_dir_all(&dir).unwrap();
+    return dir.join(name);
+}
+
+// 32-bit float WAV
+pub fn write_wav(name: &str, sample_rate: u32, channel_count: usize, samples: &[f32]) -> PathBuf {
+    return write_wav_from(name, sample_rate, channel_count, samples.len(), samples.iter().copied());
+}
+
+// Same, for files too long to keep in memory
+pub fn write_wav_from(name: &str, sample_rate: u32, channel_count: usize, len: usize, samples: impl Iterator<Item = f32>) -> PathBuf {
+    let path = temp_path(name);
+    let block_align = channel_count as u16 * 4;
+    let data_size = len as u32 * 4;
+
+    let mut file = BufWriter::new(File::create(&path).unwrap());
+    file.write_all(b"RIFF").unwrap();
+    file.write_all(&(36 + data_size).to_le_bytes()).unwrap();
+    file.write_all(b"WAVE").unwrap();
+    file.write_all(b"fmt ").unwrap();
+    file.write_all(&16u32.to_le_bytes()).unwrap();
+    file.write_all(&3u16.to_le_bytes()).unwrap();
+    file.write_all(&(channel_count as u16).to_le_bytes()).unwrap();
+    file.write_all(&sample_rate.to_le_bytes()).unwrap();
+    file.write_all(&(sample_rate * block_align as u32).to_le_bytes()).unwrap();
+    file.write_all(&block_align.to_le_bytes()).unwrap();
+    file.write_all(&32u16.to_le_bytes()).unwrap();
+    file.write_all(b"data").unwrap();
+    file.write_all(&data_size.to_le_bytes()).unwrap();
+    for sample in samples.take(len) {
+        file.write_all(&sample.to_le_bytes()).unwrap();
+    }
+
+    file.flush().unwrap();
+    return path;
+}
+
+// Interleaved, same on every channel
+pub fn sine(sample_rate: u32, channel_count: usize, frequency: f32, seconds: f32) -> Vec<f32> {
+    let frames = (sample_rate as f32 * seconds) as usize;
+    return (0 .. frames).flat_map(|frame| {
+        let value = (std::f32::consts::TAU * frequency * frame as f32 / sample_rate as f32).sin() * 0.5;
+        std::iter::repeat(value).take(channel_count)
+    }).collect();
+}
+
+// Output that only renders when the test asks, and can fail in the middle of playback
+pub struct Probe {
+    sample_rate   : u32,
+    channel_count : usize,
+    render        : Arc<Mutex<Option<Render>>>,
+    lost          : Arc<AtomicBool>,
+}
+
+#[derive(Clone)]
+pub struct ProbeHandle {
+    channel_count : usize,
+    render        : Arc<Mutex<Option<Render>>>,
+    lost          : Arc<AtomicBool>,
+}
+
+impl Probe {
+    pub fn new(sample_rate: u32, channel_count: usize) -> (Probe, ProbeHandle) {
+        let render = Arc::new(Mutex::new(None));
+        let lost = Arc::new(AtomicBool::new(false));
+        let handle = ProbeHandle {
+            channel_count : channel_count,
+            render        : render.clone(),
+            lost          : lost.clone(),
+        };
+
+        return (Probe { sample_rate, channel_count, render, lost }, handle);
+    }
+}
+
+impl ProbeHandle {
+    // Interleaved, empty once the probe failed or before it's started
+    pub fn render(&self, frames: usize) -> Vec<f32> {
+        if self.lost.load(Ordering::Relaxed) {
+            return vec![];
+        }
+
+        let mut buffer = vec![0.0; frames * self.channel_count];
+        match &mut *self.render.lock().unwrap() {
+            Some(render) => render(&mut buffer, Duration::ZERO),
+            None => return vec![],
+        }
+
+        return buffer;
+    }
+
+    // Like the device was unplugged
+    pub fn fail(&self) {
+        self.lost.store(true, Ordering::Relaxed);
+    }
+}
+
+impl Backend for Probe {
+    fn sample_rate(&self) -> u32 {
+        return self.sample_rate;
+    }
+
+    fn channel_count(&self) -> usize {
+        return self.channel_count;
+    }
+
+    fn start(&mut self, render: Render) -> Result<()> {
+        *self.render.lock().unwrap() = Some(render);
+        return Ok(());
+    }
+
+    fn is_lost(&self) -> bool {
+        return self.lost.load(Ordering::Relaxed);
+    }
+}
\ No newline at end of file
@@ -0,0 +1,57 @@
+#![allow(clippy::needless_return)]
+
+use std::thread;
+use std::time::Duration;
+
+use r3gl_audio::{Audio, AudioFile};
+
+mod common;
+
+const SONG_RATE: u32 = 44100;
+const DEVICE_RATE: u32 = 48000;
+const MINUTES: usize = 10;
+const CALLBACK_FRAMES: usize = 4800;
+
+fn frame_at(time: Duration) -> usize {
+    return (time.as_secs_f64() * DEVICE_RATE as f64).round() as usize;
+}
+
+// Every minute mark of a silent song is a click, each has to come out on the frame
+// the reported position puts that minute at
+#[test]
+fn stays_in_sync_for_ten_minutes() {
+    let minute = SONG_RATE as usize * 60;
+    let len = minute * MINUTES + SONG_RATE as usize;
+    let samples = (0 .. len).map(|frame| if frame > 0 && frame % minute == 0 { 1.0 } else { 0.0 });
+    let song = common::write_wav_from("drift-song.wav", SONG_RATE, 1, len, samples);
+
+    let (probe, handle) = common::Probe::new(DEVICE_RATE, 1);
+    let mut audio = Audio::with_backend(probe).unwrap();
+    audio.play(&AudioFile::open(song).unwrap()).unwrap();
+    audio.set_paused(false);
+
+    let mut clicks = Vec::new();
+    let end = DEVICE_RATE as usize * 60 * MINUTES + DEVICE_RATE as usize / 2;
+    while frame_at(audio.get_time()) < end {
+        let start = frame_at(audio.get_time());
+        let buffer = handle.render(CALLBACK_FRAMES);
+        let played = frame_at(audio.get_time()) - start;
+
+        // Whatever wasn't played is silence the decoder didn't fill in time
+        for (offset, sample) in buffer[.. played].iter().enumerate() {
+            if *sample > 0.5 {
+                clicks.push(start + offset);
+            }
+        }
+
+        if played < CALLBACK_FRAMES {
+            thread::sleep(Duration::from_millis(1));
+        }
+    }
+
+    let expected: Vec<usize> = (1 ..= MINUTES).map(|minute| minute * 60 * DEVICE_RATE as usize).collect();
+    assert_eq!(clicks.len(), expected.len(), "clicks at {clicks:?}");
+    for (click, expected) in clicks.iter().zip(&expected) {
+        assert!(click.abs_diff(*expected) <= 1, "click at {click}, expected {expected}");
+    }
+}
\ No newline at end of file
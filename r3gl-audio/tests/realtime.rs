@@ -0,0 +1,119 @@
+#![allow(clippy::needless_return)]
+
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::cell::Cell;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use r3gl_audio::{Audio, AudioFile, Bus, BusMix, Drive, NullBackend, Track, TrackMix};
+
+mod common;
+
+// Counts what the thread driving the backend allocates or frees while it renders
+struct CountingAllocator;
+
+thread_local! {
+    static COUNTING: Cell<bool> = const { Cell::new(false) };
+}
+
+static ALLOCATIONS: AtomicUsize = AtomicUsize::new(0);
+
+fn count() {
+    if COUNTING.with(Cell::get) {
+        ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+    }
+}
+
+unsafe impl GlobalAlloc for CountingAllocator {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        count();
+        return System.alloc(layout);
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        count();
+        System.dealloc(ptr, layout);
+    }
+
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        count();
+        return System.realloc(ptr, layout, new_size);
+    }
+}
+
+#[global_allocator]
+static ALLOCATOR: CountingAllocator = CountingAllocator;
+
+const SAMPLE_RATE: u32 = 48000;
+const FRAMES: usize = 1024;
+const CALLBACKS: usize = 2000;
+
+#[test]
+fn render_never_blocks_or_allocates() {
+    // Not the output rate, so the stream goes through the resampler
+    let song = common::write_wav("realtime.wav", 44100, 2, &common::sine(44100, 2, 440.0, 10.0));
+    let backend = NullBackend::new(SAMPLE_RATE, 2, Drive::Manual);
+    let handle = backend.handle();
+
+    let started = Arc::new(AtomicBool::new(false));
+    let done = Arc::new(AtomicBool::new(false));
+
+    // Everything `Audio` does goes on while the callback runs, seeks above all
+    let control = {
+        let (started, done) = (started.clone(), done.clone());
+        thread::spawn(move || {
+            let mut audio = Audio::with_backend(backend).unwrap();
+            let file = AudioFile::open(&song).unwrap();
+            audio.play(&file).unwrap();
+            audio.add_stem(&file).unwrap();
+            let click = audio.tone(1000.0, Duration::from_millis(20));
+            audio.set_paused(false);
+            started.store(true, Ordering::Release);
+
+            let mut seeks = 0;
+            while !done.load(Ordering::Acquire) {
+                let time = Duration::from_millis(seeks * 37 % 9000);
+                audio.set_time(time);
+                audio.schedule(&click, time + Duration::from_millis(5), 1.0, Bus::Hitsounds);
+                audio.set_mix(Track::Stem(0), TrackMix { gain: 0.5, pan: (seeks % 3) as f32 - 1.0, ..Default::default() });
+                audio.set_bus(Bus::Song, BusMix { volume: 0.8, mute: seeks % 7 == 0 });
+                audio.set_volume(0.9);
+                if seeks % 50 == 0 {
+                    audio.clear_scheduled();
+                }
+
+                seeks += 1;
+                thread::sleep(Duration::from_micros(500));
+            }
+
+            audio.stop();
+            return seeks;
+        })
+    };
+
+    while !started.load(Ordering::Acquire) {
+        thread::yield_now();
+    }
+
+    // Sizes the null backend's own buffer, which isn't the renderer's doing
+    handle.advance(FRAMES);
+
+    let budget = Duration::from_secs_f64(FRAMES as f64 / SAMPLE_RATE as f64);
+    let mut slowest = Duration::ZERO;
+    for _ in 0 .. CALLBACKS {
+        COUNTING.with(|counting| counting.set(true));
+        let start = Instant::now();
+        handle.advance(FRAMES);
+        slowest = slowest.max(start.elapsed());
+        COUNTING.with(|counting| counting.set(false));
+    }
+
+    done.store(true, Ordering::Release);
+    let seeks = control.join().unwrap();
+
+    assert!(seeks > 100, "only {} seeks were queued", seeks);
+    assert_eq!(ALLOCATIONS.load(Ordering::Relaxed), 0);
+    assert!(slowest < budget, "a callback took {:?}, longer than the {:?} it renders", slowest, budget);
+}
\ No newline at end of file
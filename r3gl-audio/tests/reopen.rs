@@ -0,0 +1,67 @@
+#![allow(clippy::needless_return)]
+
+use std::thread;
+use std::time::Duration;
+
+use r3gl_audio::{Audio, AudioFile};
+
+mod common;
+
+const SONG_RATE: u32 = 48000;
+const CALLBACK_FRAMES: usize = 480;
+
+// Click two seconds into the song
+const CLICK: f64 = 2.0;
+
+// Renders until the song reaches `until` seconds, returns where the loudest played sample was
+fn play_until(audio: &Audio, handle: &common::ProbeHandle, sample_rate: u32, until: f64) -> (usize, f32) {
+    let frame_at = |time: Duration| (time.as_secs_f64() * sample_rate as f64).round() as usize;
+    let mut loudest = (0, 0.0);
+    while audio.get_time().as_secs_f64() < until {
+        let start = frame_at(audio.get_time());
+        let buffer = handle.render(CALLBACK_FRAMES);
+        let played = frame_at(audio.get_time()) - start;
+        for (offset, sample) in buffer[.. played].iter().enumerate() {
+            if *sample > loudest.1 {
+                loudest = (start + offset, *sample);
+            }
+        }
+
+        if played < CALLBACK_FRAMES {
+            thread::sleep(Duration::from_millis(1));
+        }
+    }
+
+    return loudest;
+}
+
+#[test]
+fn reopens_where_it_left_off() {
+    let mut samples = vec![0.0; SONG_RATE as usize * 3];
+    samples[(CLICK * SONG_RATE as f64) as usize] = 1.0;
+    let song = common::write_wav("reopen-song.wav", SONG_RATE, 1, &samples);
+
+    let (probe, handle) = common::Probe::new(SONG_RATE, 1);
+    let mut audio = Audio::with_backend(probe).unwrap();
+    audio.play(&AudioFile::open(song).unwrap()).unwrap();
+    audio.set_paused(false);
+    play_until(&audio, &handle, SONG_RATE, 1.0);
+
+    // Device goes away mid-song, nothing moves until there's a new one
+    handle.fail();
+    let time = audio.get_time();
+    assert!(audio.is_device_lost());
+    assert!(handle.render(CALLBACK_FRAMES).is_empty());
+    assert_eq!(audio.get_time(), time);
+
+    // Replacement runs at another rate, so the song is resampled from where it stopped
+    let (probe, handle) = common::Probe::new(44100, 1);
+    audio.set_backend(probe).unwrap();
+    assert!(!audio.is_device_lost());
+    assert!(!audio.is_paused());
+    assert!((audio.get_time().as_secs_f64() - time.as_secs_f64()).abs() <= 1.0 / 44100.0);
+
+    let (click, loudness) = play_until(&audio, &handle, 44100, CLICK + 0.2);
+    assert!(loudness > 0.5);
+    assert!(click.abs_diff((CLICK * 44100.0) as usize) <= 1, "click at {click}");
+}
\ No newline at end of file
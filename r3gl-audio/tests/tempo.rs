@@ -0,0 +1,87 @@
+#![allow(clippy::needless_return)]
+
+use std::f32::consts::TAU;
+
+use r3gl_audio::{AudioData, TempoCandidate, detect_tempo};
+
+mod common;
+
+const SAMPLE_RATE: u32 = 44100;
+const SECONDS: f64 = 30.0;
+
+// Close enough to snap to in the editor
+const BPM_TOLERANCE: f64 = 0.1;
+const OFFSET_TOLERANCE: f64 = 5.0; // ms
+
+// Short decaying blip on every beat, starting `offset` ms in
+fn click_track(name: &str, bpm: f64, offset: f64) -> Vec<TempoCandidate> {
+    let frames = (SECONDS * SAMPLE_RATE as f64) as usize;
+    let beat_length = 60.0 / bpm * SAMPLE_RATE as f64;
+    let click_length = SAMPLE_RATE as usize / 50;
+
+    let mut samples = vec![0.0; frames];
+    let mut beat = offset / 1000.0 * SAMPLE_RATE as f64;
+    while (beat as usize) < frames {
+        let start = beat.round() as usize;
+        for (i, sample) in samples[start ..].iter_mut().take(click_length).enumerate() {
+            let t = i as f32 / SAMPLE_RATE as f32;
+            *sample = (TAU * 1000.0 * t).sin() * (-t * 200.0).exp();
+        }
+
+        beat += beat_length;
+    }
+
+    let path = common::write_wav(name, SAMPLE_RATE, 1, &samples);
+    return detect_tempo(&AudioData::from_file(path).unwrap());
+}
+
+fn check(candidates: &[TempoCandidate], bpm: f64, offset: f64) {
+    let top = candidates.first().expect("no tempo found");
+    assert!((top.bpm - bpm).abs() < BPM_TOLERANCE, "found {} bpm, expected {bpm}", top.bpm);
+
+    // Any beat will do, so the offset is compared within one beat length
+    let beat_length = 60000.0 / bpm;
+    let error = (top.offset - offset).rem_euclid(beat_length);
+    let error = error.min(beat_length - error);
+    assert!(error < OFFSET_TOLERANCE, "first beat at {} ms, expected {offset} ms", top.offset);
+
+    // Half and double the tempo fit every click too, they just can't come first
+    for candidate in &candidates[1 ..] {
+        assert!(candidate.confidence <= top.confidence);
+    }
+}
+
+#[test]
+fn finds_round_tempo() {
+    check(&click_track("tempo-120.wav", 120.0, 0.0), 120.0, 0.0);
+}
+
+#[test]
+fn finds_fractional_tempo() {
+    check(&click_track("tempo-174.wav", 174.5, 0.0), 174.5, 0.0);
+}
+
+#[test]
+fn finds_offset() {
+    check(&click_track("tempo-120-offset.wav", 120.0, 137.0), 120.0, 137.0);
+    check(&click_track("tempo-174-offset.wav", 174.5, 250.0), 174.5, 250.0);
+}
+
+#[test]
+fn ranks_octaves_lower() {
+    for bpm in [100.0, 140.0, 174.5] {
+        let candidates = click_track(&format!("tempo-octaves-{bpm}.wav"), bpm, 40.0);
+        let top = candidates[0];
+        assert!((top.bpm - bpm).abs() < BPM_TOLERANCE, "found {} bpm, expected {bpm}", top.bpm);
+
+        // At least one of them is in range and shows up
+        let octaves: Vec<&TempoCandidate> = candidates.iter()
+            .filter(|candidate| [bpm / 2.0, bpm * 2.0].iter().any(|octave| (candidate.bpm / octave - 1.0).abs() < 0.02))
+            .collect();
+
+        assert!(!octaves.is_empty(), "no octave of {bpm} bpm in {candidates:?}");
+        for candidate in octaves {
+            assert!(candidate.confidence < top.confidence, "{} bpm ranked with {bpm}", candidate.bpm);
+        }
+    }
+}
\ No newline at end of file
@@ -0,0 +1,84 @@
+#![allow(clippy::needless_return)]
+
+use std::fs;
+use std::thread;
+use std::time::Duration;
+
+use r3gl_audio::{Audio, AudioData, AudioFile, Bus, Drive, WavBackend};
+
+mod common;
+
+const SAMPLE_RATE: u32 = 48000;
+// Where the click is scheduled, 250 ms in
+const CLICK_FRAME: usize = SAMPLE_RATE as usize / 4;
+
+fn u16_at(bytes: &[u8], offset: usize) -> u16 {
+    return u16::from_le_bytes(bytes[offset .. offset + 2].try_into().unwrap());
+}
+
+fn u32_at(bytes: &[u8], offset: usize) -> u32 {
+    return u32::from_le_bytes(bytes[offset .. offset + 4].try_into().unwrap());
+}
+
+#[test]
+fn records_scheduled_sound() {
+    let song = common::write_wav("wav-song.wav", SAMPLE_RATE, 2, &vec![0.0; SAMPLE_RATE as usize * 2]);
+    let click = [0.25, -0.5, 0.75, 1.0];
+    let click_file = common::write_wav("wav-click.wav", SAMPLE_RATE, 1, &click);
+
+    let path = common::temp_path("wav-out.wav");
+    let backend = WavBackend::new(&path, SAMPLE_RATE, 2, Drive::Manual).unwrap();
+    let handle = backend.handle();
+    let mut audio = Audio::with_backend(backend).unwrap();
+    audio.play(&AudioFile::open(song).unwrap()).unwrap();
+
+    let sound = audio.load_sound(&AudioData::from_file(click_file).unwrap()).unwrap();
+    audio.schedule(&sound, Duration::from_millis(250), 1.0, Bus::Hitsounds);
+    audio.set_paused(false);
+
+    // Silence goes out while the decoder catches up, so output frames are matched up with song frames
+    let mut frames: usize = 0;
+    let mut played = 0;
+    let mut click_at = None;
+    while played < CLICK_FRAME + SAMPLE_RATE as usize / 10 {
+        handle.advance(1);
+        frames += 1;
+
+        let now = (audio.get_time().as_secs_f64() * SAMPLE_RATE as f64).round() as usize;
+        if now > played {
+            if played == CLICK_FRAME {
+                click_at = Some(frames - 1);
+            }
+
+            played = now;
+        } else {
+            thread::sleep(Duration::from_micros(100));
+        }
+    }
+
+    let click_at = click_at.unwrap();
+    let bytes = fs::read(&path).unwrap();
+    let data_size = (frames * 2 * 4) as u32;
+
+    assert_eq!(&bytes[0 .. 4], b"RIFF");
+    assert_eq!(u32_at(&bytes, 4), 36 + data_size);
+    assert_eq!(&bytes[8 .. 16], b"WAVEfmt ");
+    assert_eq!(u32_at(&bytes, 16), 16);
+    assert_eq!(u16_at(&bytes, 20), 3); // Float
+    assert_eq!(u16_at(&bytes, 22), 2);
+    assert_eq!(u32_at(&bytes, 24), SAMPLE_RATE);
+    assert_eq!(u32_at(&bytes, 28), SAMPLE_RATE * 8);
+    assert_eq!(u16_at(&bytes, 32), 8);
+    assert_eq!(u16_at(&bytes, 34), 32);
+    assert_eq!(&bytes[36 .. 40], b"data");
+    assert_eq!(u32_at(&bytes, 40), data_size);
+    assert_eq!(bytes.len(), 44 + data_size as usize);
+
+    let samples: Vec<f32> = bytes[44 ..].chunks(4).map(|sample| f32::from_le_bytes(sample.try_into().unwrap())).collect();
+    let expected: Vec<f32> = (0 .. frames).flat_map(|frame| {
+        let value = frame.checked_sub(click_at).and_then(|offset| click.get(offset)).copied().unwrap_or(0.0);
+        [value, value]
+    }).collect();
+
+    assert_eq!(samples, expected);
+}
\ No newline at end of file
@@ -1,7 +1,10 @@
+#![allow(clippy::needless_return, clippy::redundant_field_names, clippy::new_without_default, clippy::module_inception)]
+
 use color_eyre::eyre::Result;
 use dynamic_arena::DynamicArena;
 use fxhash::FxHashMap;
-use r3gl_app::{state::State, screen::{egui::EGuiScreen, taiko::TaikoScreen}, identifier::Identifier};
+use log::error;
+use r3gl_app::{state::State, screen::{egui::EGuiScreen, taiko::TaikoScreen}, identifier::Identifier, tool::Tool};
 use wcore::{app::{App, AppState}, graphics::context::Context, bindings::{KeyCombination, Action}};
 use winit::event::{VirtualKeyCode, ModifiersState};
 use str_macro::str;
@@ -35,6 +38,132 @@ fn main() -> Result<()> {
                     }
                 ));
 
+                binds.insert(KeyCombination::from((VirtualKeyCode::S, ModifiersState::CTRL)), Action::new(
+                    str!("Save"),
+                    str!("Saves the current beatmap"),
+                    |state: &mut State| {
+                        if let Err(err) = state.editor.save_project(&mut state.projects) {
+                            error!("{:#}", err);
+                        }
+                    }
+                ));
+
+                binds.insert(KeyCombination::from((VirtualKeyCode::Z, ModifiersState::CTRL)), Action::new(
+                    str!("Undo"),
+                    str!("Reverts the last change"),
+                    |state: &mut State| {
+                        if let Err(err) = state.editor.undo() {
+                            error!("{:#}", err);
+                        }
+                    }
+                ));
+
+                binds.insert(KeyCombination::from((VirtualKeyCode::Y, ModifiersState::CTRL)), Action::new(
+                    str!("Redo"),
+                    str!("Applies the last reverted change again"),
+                    |state: &mut State| {
+                        if let Err(err) = state.editor.redo() {
+                            error!("{:#}", err);
+                        }
+                    }
+                ));
+
+                binds.insert(KeyCombination::from((VirtualKeyCode::Delete, ModifiersState::empty())), Action::new(
+                    str!("Delete"),
+                    str!("Removes the selected objects"),
+                    |state: &mut State| {
+                        if let Err(err) = state.editor.delete_selection() {
+                            error!("{:#}", err);
+                        }
+                    }
+                ));
+
+                binds.insert(KeyCombination::from((VirtualKeyCode::Q, ModifiersState::empty())), Action::new(
+                    str!("Switch Color"),
+                    str!("Turns selected dons into kats and the other way around"),
+                    |state: &mut State| {
+                        if let Err(err) = state.editor.switch_selection_color() {
+                            error!("{:#}", err);
+                        }
+                    }
+                ));
+
+                binds.insert(KeyCombination::from((VirtualKeyCode::W, ModifiersState::empty())), Action::new(
+                    str!("Toggle Finisher"),
+                    str!("Toggles big notes for the selected objects"),
+                    |state: &mut State| {
+                        if let Err(err) = state.editor.toggle_selection_big() {
+                            error!("{:#}", err);
+                        }
+                    }
+                ));
+
+                binds.insert(KeyCombination::from((VirtualKeyCode::Left, ModifiersState::ALT)), Action::new(
+                    str!("Nudge Left"),
+                    str!("Moves the selected objects one tick back"),
+                    |state: &mut State| {
+                        if let Err(err) = state.editor.nudge_selection(false) {
+                            error!("{:#}", err);
+                        }
+                    }
+                ));
+
+                binds.insert(KeyCombination::from((VirtualKeyCode::Right, ModifiersState::ALT)), Action::new(
+                    str!("Nudge Right"),
+                    str!("Moves the selected objects one tick forward"),
+                    |state: &mut State| {
+                        if let Err(err) = state.editor.nudge_selection(true) {
+                            error!("{:#}", err);
+                        }
+                    }
+                ));
+
+                binds.insert(KeyCombination::from((VirtualKeyCode::X, ModifiersState::CTRL)), Action::new(
+                    str!("Cut"),
+                    str!("Copies the selected objects and removes them"),
+                    |state: &mut State| {
+                        if let Err(err) = state.editor.cut_selection() {
+                            error!("{:#}", err);
+                        }
+                    }
+                ));
+
+                binds.insert(KeyCombination::from((VirtualKeyCode::C, ModifiersState::CTRL)), Action::new(
+                    str!("Copy"),
+                    str!("Copies the selected objects"),
+                    |state: &mut State| {
+                        state.editor.copy_selection();
+                    }
+                ));
+
+                binds.insert(KeyCombination::from((VirtualKeyCode::V, ModifiersState::CTRL)), Action::new(
+                    str!("Paste"),
+                    str!("Pastes copied objects at the current time"),
+                    |state: &mut State| {
+                        if let Err(err) = state.editor.paste() {
+                            error!("{:#}", err);
+                        }
+                    }
+                ));
+
+                for (key, tool) in [VirtualKeyCode::Key1, VirtualKeyCode::Key2, VirtualKeyCode::Key3, VirtualKeyCode::Key4, VirtualKeyCode::Key5].into_iter().zip(Tool::ALL) {
+                    binds.insert(KeyCombination::from((key, ModifiersState::empty())), Action::new(
+                        format!("{} Tool", tool.name()),
+                        format!("Switches to the {} tool", tool.name().to_lowercase()),
+                        move |state: &mut State| {
+                            state.editor.set_tool(tool);
+                        }
+                    ));
+                }
+
+                binds.insert(KeyCombination::from((VirtualKeyCode::F, ModifiersState::empty())), Action::new(
+                    str!("Finisher"),
+                    str!("Toggles placing big notes"),
+                    |state: &mut State| {
+                        state.editor.toggle_finisher();
+                    }
+                ));
+
                 binds
             });
             
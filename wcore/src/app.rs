@@ -77,13 +77,13 @@ impl<'a, S, I: Identifier> App<'a, S, I> {
                         match event {
                             ref event @ WindowEvent::CursorMoved { device_id, position, modifiers } => {
                                 input_data.cursor_position = (position.x as f32, position.y as f32).into();
-                                self.input(&mut state, &mut app_state, &event, &mut input_data);
+                                self.input(&mut state, &mut app_state, event, &mut input_data);
                             }
 
                             ref event @ WindowEvent::MouseInput { device_id, state: mouse_state, button, modifiers } => {
                                 input_data.mouse_button = button;
                                 input_data.mouse_state = mouse_state;
-                                self.input(&mut state, &mut app_state, &event, &mut input_data);
+                                self.input(&mut state, &mut app_state, event, &mut input_data);
                             }
 
                             WindowEvent::Focused(is_focused) => {
@@ -120,7 +120,7 @@ impl<'a, S, I: Identifier> App<'a, S, I> {
                                     }
                                 }
 
-                                self.input(&mut state, &mut app_state, &event, &mut input_data);
+                                self.input(&mut state, &mut app_state, event, &mut input_data);
                             }
 
                             event @ WindowEvent::DroppedFile(_) => {
@@ -170,10 +170,9 @@ impl<'a, S, I: Identifier> App<'a, S, I> {
         for screen in self.screens.iter_mut().rev() {
             if let WindowEvent::KeyboardInput { input: key_input, .. } = event
             && let Some(key) = key_input.virtual_keycode && let Some(bindings) = app.bindings.get_mut(&screen.identifier())
-            && let Some(action) = bindings.get_mut(&KeyCombination::from((key, input.modifiers))) {
-                if key_input.state == ElementState::Pressed {
-                    action.invoke(state);
-                }
+            && let Some(action) = bindings.get_mut(&KeyCombination::from((key, input.modifiers)))
+            && key_input.state == ElementState::Pressed {
+                action.invoke(state);
             }
 
             if !input_blocked && !screen.input(state, app, event, input) {
@@ -187,7 +186,7 @@ impl<'a, S, I: Identifier> App<'a, S, I> {
 fn create_window(event_loop: &EventLoop<()>, width: i32, height: i32) -> Window {
     let window = WindowBuilder::new()
         .with_inner_size(LogicalSize::new(width, height))
-        .build(&event_loop)
+        .build(event_loop)
         .unwrap();
 
     return window;
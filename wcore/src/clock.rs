@@ -1,4 +1,13 @@
-use instant::Instant;
+use instant::{Duration, Instant};
+
+// Further off the audio than this and the clock jumps instead of catching up, in ms
+const SNAP_THRESHOLD: f64 = 100.0;
+
+// Drift is taken out over about this much song time, in ms
+const CORRECTION_WINDOW: f64 = 500.0;
+
+// Most the clock runs faster or slower than the song while catching up
+const MAX_CORRECTION: f64 = 0.05;
 
 pub trait Clock {
     fn set_time(&mut self, time: u32);
@@ -10,64 +19,238 @@ pub trait Clock {
 
     fn set_length(&mut self, value: u32);
     fn get_length(&self) -> u32;
+
+    fn set_rate(&mut self, value: f64, time: u32);
+    fn get_rate(&self) -> f64;
 }
 
+// Runs on its own between `sync`s, so it moves every frame even though the audio
+// position only moves once per callback
 pub struct SyncClock {
-    last_pause: Instant,
-    last_time: u32,
+    anchor: Instant, // `time` was the time at this instant
+    time: f64,       // ms
+    speed: f64,      // `rate` with drift correction
     paused: bool,
     length: u32,
+    rate: f64,
+    offset: i32,     // ms, positive when audio is heard later than the device says
 }
 
 impl SyncClock {
     pub fn new() -> Self {
         return Self {
-            last_pause: Instant::now(),
-            last_time: 0,
+            anchor: Instant::now(),
+            time: 0.0,
+            speed: 1.0,
             paused: true,
             length: 0,
+            rate: 1.0,
+            offset: 0,
         };
     }
-}
 
-impl Clock for SyncClock {
-    fn set_time(&mut self, time: u32) {
-        self.last_pause = Instant::now();
-        self.last_time = time;
+    // `time` in ms is heard at `heard`. Small drift is corrected gradually, so
+    // the clock never jumps, and only large differences snap right to it
+    pub fn sync(&mut self, time: f64, heard: Instant) {
+        self.sync_at(time, heard, Instant::now());
     }
 
-    fn get_time(&mut self) -> u32 {
+    fn sync_at(&mut self, time: f64, heard: Instant, now: Instant) {
         if self.paused {
-            return self.last_time;
+            return;
+        }
+
+        let offset = Duration::from_millis(self.offset.unsigned_abs() as u64);
+        let heard = if self.offset >= 0 { heard + offset } else { heard.checked_sub(offset).unwrap_or(heard) };
+
+        let error = time - self.time_at(heard);
+        if error.abs() > SNAP_THRESHOLD {
+            self.time = time + seconds_between(heard, now) * 1000.0 * self.rate;
+            self.speed = self.rate;
         } else {
-            let now = instant::Instant::now();
-            let diff = now.duration_since(self.last_pause).as_millis() as u32;
-            let time = diff + self.last_time;
+            self.time = self.time_at(now);
+            self.speed = self.rate * (1.0 + (error / CORRECTION_WINDOW).clamp(-MAX_CORRECTION, MAX_CORRECTION));
+        }
+
+        self.anchor = now;
+    }
 
-            if time >= self.length {
-                self.paused = true;
-                self.last_time = self.length;
-            }
+    // Global audio offset, for outputs that are late in ways the device doesn't report
+    pub fn set_offset(&mut self, value: i32) { self.offset = value; }
+    pub fn get_offset(&self) -> i32 { return self.offset; }
 
-            return time;
+    fn time_at(&self, at: Instant) -> f64 {
+        if self.paused {
+            return self.time;
         }
+
+        return self.time + seconds_between(self.anchor, at) * 1000.0 * self.speed;
+    }
+
+    fn restart(&mut self, time: u32) {
+        self.anchor = Instant::now();
+        self.time = time as f64;
+        self.speed = self.rate;
+    }
+}
+
+impl Clock for SyncClock {
+    fn set_time(&mut self, time: u32) {
+        self.restart(time);
+    }
+
+    fn get_time(&mut self) -> u32 {
+        let time = self.time_at(Instant::now());
+        if !self.paused && time >= self.length as f64 {
+            self.paused = true;
+            self.restart(self.length);
+            return self.length;
+        }
+
+        return time.max(0.0) as u32;
     }
 
     fn is_paused(&self) -> bool { return self.paused; }
     fn set_paused(&mut self, value: bool, time: u32) {
         self.paused = value;
-
-        self.last_pause = Instant::now();
-        self.last_time = time;
-     }
+        self.restart(time);
+    }
 
     fn toggle_paused(&mut self, time: u32) {
         self.paused = !self.paused;
-        
-        self.last_pause = Instant::now();
-        self.last_time = time;
+        self.restart(time);
     }
     
     fn set_length(&mut self, value: u32) { self.length = value; }
     fn get_length(&self) -> u32 { return self.length; }
+
+    fn set_rate(&mut self, value: f64, time: u32) {
+        self.rate = value;
+        self.restart(time);
+    }
+
+    fn get_rate(&self) -> f64 { return self.rate; }
+}
+
+// Negative when `to` comes first, latency puts audio timestamps in the future
+fn seconds_between(from: Instant, to: Instant) -> f64 {
+    if to >= from {
+        return to.duration_since(from).as_secs_f64();
+    } else {
+        return -from.duration_since(to).as_secs_f64();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Stands in for an output that calls back every `PERIOD` and reports when the
+    // first sample of each buffer is heard
+    const PERIOD: Duration = Duration::from_millis(10);
+
+    struct VirtualSink {
+        start: Instant,
+        callbacks: u32,
+        ahead: f64, // ms the reported position is ahead of the clock's
+        speed: f64, // song ms per wall clock ms, off 1.0 when the device clock drifts
+    }
+
+    impl VirtualSink {
+        fn new(start: Instant, ahead: f64, speed: f64) -> Self {
+            return Self { start, callbacks: 0, ahead, speed };
+        }
+
+        // Position, when it's heard and when the callback ran
+        fn callback(&mut self) -> (f64, Instant, Instant) {
+            self.callbacks += 1;
+            let now = self.start + PERIOD * self.callbacks;
+            let heard = now + Duration::from_millis(20);
+            let time = seconds_between(self.start, heard) * 1000.0 * self.speed + self.ahead;
+            return (time, heard, now);
+        }
+    }
+
+    fn playing(start: Instant) -> SyncClock {
+        let mut clock = SyncClock::new();
+        clock.anchor = start;
+        clock.paused = false;
+        clock.length = u32::MAX;
+        return clock;
+    }
+
+    // Runs the sink for `duration` of wall clock, returning how far the clock is behind the sink at the end
+    fn run(clock: &mut SyncClock, sink: &mut VirtualSink, duration: Duration, mut each: impl FnMut(&SyncClock, f64)) -> f64 {
+        let mut error = 0.0;
+        for _ in 0 .. duration.as_millis() / PERIOD.as_millis() {
+            let (time, heard, now) = sink.callback();
+            clock.sync_at(time, heard, now);
+            error = time - clock.time_at(heard);
+            each(clock, error);
+        }
+
+        return error;
+    }
+
+    #[test]
+    fn snaps_past_threshold() {
+        let start = Instant::now();
+        let mut clock = playing(start);
+        let mut sink = VirtualSink::new(start, SNAP_THRESHOLD + 50.0, 1.0);
+
+        let (time, heard, now) = sink.callback();
+        clock.sync_at(time, heard, now);
+        assert!((clock.time_at(heard) - time).abs() < 1e-6);
+        assert_eq!(clock.speed, clock.rate);
+    }
+
+    #[test]
+    fn converges_within_window() {
+        let start = Instant::now();
+        let mut clock = playing(start);
+        let mut sink = VirtualSink::new(start, 20.0, 1.0);
+
+        let window = Duration::from_millis(CORRECTION_WINDOW as u64);
+        let mut last = f64::MAX;
+        let error = run(&mut clock, &mut sink, window, |clock, error| {
+            assert!(error >= 0.0 && error < last, "error grew to {}", error);
+            assert!((clock.speed / clock.rate - 1.0).abs() <= MAX_CORRECTION + 1e-9);
+            last = error;
+        });
+
+        // Proportional catch up, so a window takes out all but about 1/e of the drift
+        assert!(error < 20.0 * 0.4, "{} ms still off", error);
+        assert!(run(&mut clock, &mut sink, window * 4, |_, _| ()) < 1.0);
+    }
+
+    #[test]
+    fn correction_is_bounded() {
+        let start = Instant::now();
+        let mut clock = playing(start);
+        let mut sink = VirtualSink::new(start, SNAP_THRESHOLD - 10.0, 1.0);
+
+        let mut last = f64::MAX;
+        run(&mut clock, &mut sink, Duration::from_secs(1), |clock, error| {
+            assert!(error > 0.0 && error < last, "snapped or diverged at {} ms", error);
+            assert!(clock.speed <= clock.rate * (1.0 + MAX_CORRECTION) + 1e-9);
+            last = error;
+        });
+    }
+
+    #[test]
+    fn follows_drifting_device() {
+        let start = Instant::now();
+        let mut clock = playing(start);
+        clock.set_rate(1.5, 0);
+        clock.anchor = start;
+
+        // Device clock runs 0.2% fast against ours
+        let mut sink = VirtualSink::new(start, 0.0, 1.5 * 1.002);
+        let error = run(&mut clock, &mut sink, Duration::from_secs(60), |clock, _| {
+            assert!((clock.speed / clock.rate - 1.0).abs() <= MAX_CORRECTION + 1e-9);
+        });
+
+        // Steady state lag is what keeps the correction at the drift
+        assert!(error.abs() < 0.002 * CORRECTION_WINDOW + 0.1, "{} ms off", error);
+    }
 }
\ No newline at end of file
@@ -103,9 +103,9 @@ fn translate_mouse_button(button: MouseButton) -> Option<egui::PointerButton> {
 
 fn is_printable_char(chr: char) -> bool {
     let is_in_private_use_area =
-        '\u{e000}'   <= chr && chr <= '\u{f8ff}'
-     || '\u{f0000}'  <= chr && chr <= '\u{ffffd}'
-     || '\u{100000}' <= chr && chr <= '\u{10fffd}';
+        ('\u{e000}'..='\u{f8ff}').contains(&chr)
+     || ('\u{f0000}'..='\u{ffffd}').contains(&chr)
+     || ('\u{100000}'..='\u{10fffd}').contains(&chr);
 
     return !is_in_private_use_area && !chr.is_ascii_control();
 }
@@ -182,7 +182,7 @@ impl EGui {
     pub fn input(&mut self, event: &WindowEvent) {
         match event {
             WindowEvent::KeyboardInput      { input, .. }         => { self.on_key_input(input); }
-            WindowEvent::ModifiersChanged   ( state )             => { self.modfiers = state.clone(); }
+            WindowEvent::ModifiersChanged   ( state )             => { self.modfiers = *state; }
             WindowEvent::ReceivedCharacter  ( ch )                => { self.on_text_input(*ch) }
             WindowEvent::CursorMoved        { position, .. }      => { self.on_mouse_move(*position) }
             WindowEvent::MouseWheel         { delta, .. }         => { self.on_mouse_wheel(*delta); }
@@ -26,10 +26,9 @@ impl Context {
             &wgpu::DeviceDescriptor {
                 label    : None,
                 features : wgpu::Features::empty(),
-                limits   : {
-                    let mut limits = wgpu::Limits::default();
-                    limits.max_bind_groups = 8;
-                    limits 
+                limits   : wgpu::Limits {
+                    max_bind_groups: 8,
+                    ..Default::default()
                 },
             },
             None,
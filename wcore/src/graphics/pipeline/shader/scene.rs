@@ -1,5 +1,7 @@
 use crate::graphics::scene::Scene;
 
+/// # Safety
+/// The pipeline's layout has to bind the uniform `update` writes the scene to
 pub unsafe trait SceneSlot {
     fn update(&self, queue: &wgpu::Queue, scene: &impl Scene);
 }
\ No newline at end of file
@@ -49,7 +49,7 @@ impl<I: Instance<R>, R: Pod + Zeroable, V: Pod + Zeroable> InstancedMesh<I, R, V
     }
 
     pub fn update(&mut self, data: &[V], queue: &wgpu::Queue) {
-        queue.write_buffer(&self.buffer, 0, bytemuck::cast_slice(&data));
+        queue.write_buffer(&self.buffer, 0, bytemuck::cast_slice(data));
     }
 
     pub fn update_instances(&mut self, data: &[R], queue: &wgpu::Queue) {
@@ -60,7 +60,7 @@ impl<I: Instance<R>, R: Pod + Zeroable, V: Pod + Zeroable> InstancedMesh<I, R, V
         return device.create_buffer_init(
             &wgpu::util::BufferInitDescriptor {
                 label: None,
-                contents: bytemuck::cast_slice(&data),
+                contents: bytemuck::cast_slice(data),
                 usage: wgpu::BufferUsages::VERTEX | wgpu::BufferUsages::COPY_DST,
             }
         );
@@ -69,7 +69,7 @@ impl<I: Instance<R>, R: Pod + Zeroable, V: Pod + Zeroable> InstancedMesh<I, R, V
 
 impl<I: Instance<R>, R: Pod + Zeroable, V: Pod + Zeroable> Drawable for InstancedMesh<I, R, V> {
     fn draw<'a>(&'a self, render_pass: &mut wgpu::RenderPass<'a>) {
-        if self.instances.len() > 0 {
+        if !self.instances.is_empty() {
             render_pass.set_vertex_buffer(0, self.buffer.slice(..));
             render_pass.set_vertex_buffer(1, self.instance_buffer.slice(..));
             render_pass.draw(0 .. self.vertices.len() as u32, 0 .. self.instances.len() as u32);
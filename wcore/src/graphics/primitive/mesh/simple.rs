@@ -25,14 +25,14 @@ impl<V: Pod + Zeroable> Mesh<V> {
     }
 
     pub fn update(&mut self, data: &[V], queue: &wgpu::Queue) {
-        queue.write_buffer(&self.buffer, 0, bytemuck::cast_slice(&data));
+        queue.write_buffer(&self.buffer, 0, bytemuck::cast_slice(data));
     }
 
     pub(crate) fn make_buffer(device: &wgpu::Device, vertices: &[V]) -> wgpu::Buffer {
         return device.create_buffer_init(
             &wgpu::util::BufferInitDescriptor {
                 label: None,
-                contents: bytemuck::cast_slice(&vertices),
+                contents: bytemuck::cast_slice(vertices),
                 usage: wgpu::BufferUsages::VERTEX | wgpu::BufferUsages::COPY_DST,
             }
         );
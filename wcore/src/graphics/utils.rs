@@ -21,21 +21,21 @@ pub fn render_pass<'a>(encoder: &'a mut wgpu::CommandEncoder, view: &'a wgpu::Te
                 }
             })
         ],
-        depth_stencil_attachment: if depth_buffer.is_some() { Some(wgpu::RenderPassDepthStencilAttachment {
-            view: depth_buffer.unwrap(),
+        depth_stencil_attachment: depth_buffer.map(|depth_buffer| wgpu::RenderPassDepthStencilAttachment {
+            view: depth_buffer,
             depth_ops: Some(wgpu::Operations {
                 load: wgpu::LoadOp::Clear(1.0),
                 store: true,
             }),
             stencil_ops: None,
-        }) } else { None },
+        }),
     });
 }
 
 pub fn render<'a>(encoder      : &'a mut wgpu::CommandEncoder,
                   view         : &'a wgpu::TextureView,
                   depth_buffer : Option<&'a TextureView>,
-                  lambda       : impl FnOnce(wgpu::RenderPass<'a>) -> ()) {
+                  lambda       : impl FnOnce(wgpu::RenderPass<'a>)) {
     #[allow(unused_mut)]
     let mut pass = render_pass(encoder, view, depth_buffer);
     lambda(pass);
@@ -67,12 +67,12 @@ pub fn pipeline(device  : &Device,
 
         // Shaders
         vertex: wgpu::VertexState {
-            module      : &shader,
+            module      : shader,
             entry_point : "vertex_main",
             buffers     : buffers,
         },
         fragment: Some(wgpu::FragmentState {
-            module      : &shader,
+            module      : shader,
             entry_point : "fragment_main",
             targets     : &[Some(wgpu::ColorTargetState {
                 format     : config.format,
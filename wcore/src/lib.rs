@@ -1,5 +1,6 @@
 #![feature(associated_type_defaults)]
 #![feature(let_chains)]
+#![allow(clippy::needless_return, clippy::redundant_field_names, clippy::new_without_default, clippy::module_inception)]
 pub mod app;
 pub mod screen;
 pub mod graphics;
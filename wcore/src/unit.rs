@@ -10,8 +10,8 @@ pub trait Unit {
     type InputState<'a> = ();
     type MouseState<'a> = ();
 
-    fn update<'a>(&mut self, state: Self::UpdateState<'a>, now: Instant) { }
+    fn update(&mut self, state: Self::UpdateState<'_>, now: Instant) { }
     fn render<'a: 'b, 'b>(&'a mut self, state: Self::RenderState<'a>, render_pass: &mut wgpu::RenderPass<'b>, graphics: &Context) { }
-    fn input<'a>(&mut self, state: Self::InputState<'a>, event: &WindowEvent, input: &Input) { }
-    fn mouse<'a>(&mut self, state: Self::MouseState<'a>, x_delta: f32, y_delta: f32) { }
+    fn input(&mut self, state: Self::InputState<'_>, event: &WindowEvent, input: &Input) { }
+    fn mouse(&mut self, state: Self::MouseState<'_>, x_delta: f32, y_delta: f32) { }
 }
\ No newline at end of file